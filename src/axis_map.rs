@@ -0,0 +1,574 @@
+use crate::click_cfg::ClickCfg;
+use crate::click_src::ClickSrc;
+use crate::enabled_enum::OnOff;
+use crate::int_cfg::IntCfg;
+use crate::int_src::IntSrc;
+use micromath::vector::I32x3;
+use micromath::F32Ext;
+
+/// Largest deviation of a rotation matrix from an orthonormal matrix.
+const MATRIX_TOLERANCE: f32 = 1e-3;
+/// Per-axis flags of INTx_CFG, INTx_SRC and CLICK_CFG, a pair per axis.
+const AXIS_PAIR_BITS: u8 = 0b11_1111;
+const CLICK_SIGN_BIT_OFFSET: u8 = 3;
+
+/// Sensor axis, optionally pointing in the opposite direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignedAxis {
+    X,
+    MinusX,
+    Y,
+    MinusY,
+    Z,
+    MinusZ,
+}
+
+impl SignedAxis {
+    pub const ALL: [SignedAxis; 6] = [
+        SignedAxis::X,
+        SignedAxis::MinusX,
+        SignedAxis::Y,
+        SignedAxis::MinusY,
+        SignedAxis::Z,
+        SignedAxis::MinusZ,
+    ];
+
+    /// Index of the axis, 0 for X, 1 for Y and 2 for Z.
+    pub fn index(&self) -> usize {
+        match self {
+            SignedAxis::X | SignedAxis::MinusX => 0,
+            SignedAxis::Y | SignedAxis::MinusY => 1,
+            SignedAxis::Z | SignedAxis::MinusZ => 2,
+        }
+    }
+    pub fn is_negative(&self) -> bool {
+        matches!(
+            self,
+            SignedAxis::MinusX | SignedAxis::MinusY | SignedAxis::MinusZ
+        )
+    }
+    fn sign(&self) -> i32 {
+        if self.is_negative() {
+            -1
+        } else {
+            1
+        }
+    }
+    fn unit(&self) -> [i32; 3] {
+        let mut unit = [0; 3];
+        unit[self.index()] = self.sign();
+        unit
+    }
+    fn from_unit(unit: [i32; 3]) -> Option<Self> {
+        SignedAxis::ALL.into_iter().find(|axis| axis.unit() == unit)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mapping {
+    Rotation([SignedAxis; 3]),
+    Matrix([[f32; 3]; 3]),
+}
+
+/// Transforms samples from the sensor axes to the axes of the board the
+/// sensor is mounted on, either by one of the 24 right-angle rotations or
+/// by an arbitrary rotation matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisMap {
+    mapping: Mapping,
+}
+
+impl Default for AxisMap {
+    fn default() -> Self {
+        AxisMap {
+            mapping: Mapping::Rotation([
+                SignedAxis::X,
+                SignedAxis::Y,
+                SignedAxis::Z,
+            ]),
+        }
+    }
+}
+
+impl AxisMap {
+    /// Right-angle rotation given by the sensor axes the board X, Y and Z
+    /// axes point along, e.g. `AxisMap::rotation(SignedAxis::Y,
+    /// SignedAxis::MinusX, SignedAxis::Z)` for a sensor rotated by 90°
+    /// around Z. Mirroring mappings are refused.
+    pub fn rotation(
+        x: SignedAxis,
+        y: SignedAxis,
+        z: SignedAxis,
+    ) -> Result<Self, &'static str> {
+        if cross(x.unit(), y.unit()) != z.unit() {
+            return Err("Axes do not form a right-handed rotation");
+        }
+        Ok(AxisMap {
+            mapping: Mapping::Rotation([x, y, z]),
+        })
+    }
+    /// All 24 right-angle rotations, starting with the identity.
+    pub fn rotations() -> impl Iterator<Item = AxisMap> {
+        SignedAxis::ALL.into_iter().flat_map(|x| {
+            SignedAxis::ALL.into_iter().filter_map(move |y| {
+                let z = SignedAxis::from_unit(cross(x.unit(), y.unit()))?;
+                AxisMap::rotation(x, y, z).ok()
+            })
+        })
+    }
+    /// Arbitrary rotation, each row holding a board axis in sensor
+    /// coordinates.
+    pub fn matrix(matrix: [[f32; 3]; 3]) -> Result<Self, &'static str> {
+        for (row, first) in matrix.iter().enumerate() {
+            for (column, second) in matrix.iter().enumerate() {
+                let expected = if row == column { 1.0 } else { 0.0 };
+                let product: f32 =
+                    first.iter().zip(second).map(|(a, b)| a * b).sum();
+                if F32Ext::abs(product - expected) > MATRIX_TOLERANCE {
+                    return Err("Matrix is not orthonormal");
+                }
+            }
+        }
+        let [x, y, z] = matrix;
+        let handedness = (y[1] * z[2] - y[2] * z[1]) * x[0]
+            + (y[2] * z[0] - y[0] * z[2]) * x[1]
+            + (y[0] * z[1] - y[1] * z[0]) * x[2];
+        if handedness < 0.0 {
+            return Err("Matrix mirrors the axes");
+        }
+        Ok(AxisMap {
+            mapping: Mapping::Matrix(matrix),
+        })
+    }
+    /// Rotation matrix of mappings created with [`AxisMap::matrix`].
+    #[cfg(any(test, feature = "fixed-point"))]
+    pub(crate) fn matrix_rows(&self) -> Option<[[f32; 3]; 3]> {
+        match self.mapping {
+            Mapping::Rotation(_) => None,
+            Mapping::Matrix(matrix) => Some(matrix),
+        }
+    }
+    /// Sensor axes closest to the board X, Y and Z axes. They equal the
+    /// rotation of right-angle mappings.
+    pub fn nearest_axes(&self) -> [SignedAxis; 3] {
+        match self.mapping {
+            Mapping::Rotation(axes) => axes,
+            Mapping::Matrix(matrix) => matrix.map(|row| {
+                let index = (0..3)
+                    .max_by(|a, b| {
+                        F32Ext::abs(row[*a]).total_cmp(&F32Ext::abs(row[*b]))
+                    })
+                    .unwrap_or(0);
+                let mut unit = [0; 3];
+                unit[index] = if row[index] < 0.0 { -1 } else { 1 };
+                SignedAxis::from_unit(unit).unwrap_or(SignedAxis::X)
+            }),
+        }
+    }
+    /// True if the board axes have distinct nearest sensor axes, as for
+    /// every right-angle mapping. Matrices close to 45° between two axes
+    /// may map two board axes to the same sensor axis.
+    pub fn has_distinct_nearest_axes(&self) -> bool {
+        let [x, y, z] = self.nearest_axes().map(|axis| axis.index());
+        x != y && y != z && x != z
+    }
+    /// Transforms a sample from sensor to board axes.
+    pub fn apply(&self, sample: I32x3) -> I32x3 {
+        let values = [sample.x, sample.y, sample.z];
+        let mapped = match self.mapping {
+            Mapping::Rotation(axes) => {
+                axes.map(|axis| axis.sign() * values[axis.index()])
+            }
+            Mapping::Matrix(matrix) => matrix.map(|row| {
+                F32Ext::round(
+                    row.iter()
+                        .zip(values)
+                        .map(|(factor, value)| factor * value as f32)
+                        .sum::<f32>(),
+                ) as i32
+            }),
+        };
+        I32x3 {
+            x: mapped[0],
+            y: mapped[1],
+            z: mapped[2],
+        }
+    }
+    /// Transforms a sample from board back to sensor axes.
+    pub fn to_sensor(&self, sample: I32x3) -> I32x3 {
+        let values = [sample.x, sample.y, sample.z];
+        let mut mapped = [0; 3];
+        match self.mapping {
+            Mapping::Rotation(axes) => {
+                for (value, axis) in values.iter().zip(axes) {
+                    mapped[axis.index()] = axis.sign() * value;
+                }
+            }
+            Mapping::Matrix(matrix) => {
+                for (index, component) in mapped.iter_mut().enumerate() {
+                    *component = F32Ext::round(
+                        matrix
+                            .iter()
+                            .zip(values)
+                            .map(|(row, value)| row[index] * value as f32)
+                            .sum::<f32>(),
+                    ) as i32;
+                }
+            }
+        }
+        I32x3 {
+            x: mapped[0],
+            y: mapped[1],
+            z: mapped[2],
+        }
+    }
+    /// Moves the per-axis interrupt enables given for board axes to the
+    /// nearest sensor axes. In 6D mode, high and low swap on axes pointing
+    /// in the opposite direction; otherwise they compare the absolute
+    /// acceleration and keep their meaning.
+    ///
+    /// Fails if an axis is enabled and the mapping does not have distinct
+    /// nearest axes, see [`AxisMap::has_distinct_nearest_axes`].
+    pub fn int_cfg_to_sensor(
+        &self,
+        cfg: &IntCfg,
+    ) -> Result<IntCfg, &'static str> {
+        let raw = cfg.get_raw_value();
+        let pairs = self.pairs_to_sensor(
+            raw & AXIS_PAIR_BITS,
+            cfg.six_d() == OnOff::Enabled,
+        )?;
+        Ok(IntCfg::try_from(raw & !AXIS_PAIR_BITS | pairs).unwrap_or(*cfg))
+    }
+    /// Inverse of [`AxisMap::int_cfg_to_sensor`].
+    pub fn int_cfg_to_board(&self, cfg: &IntCfg) -> IntCfg {
+        let raw = cfg.get_raw_value();
+        let pairs = self.pairs_to_board(
+            raw & AXIS_PAIR_BITS,
+            cfg.six_d() == OnOff::Enabled,
+        );
+        IntCfg::try_from(raw & !AXIS_PAIR_BITS | pairs).unwrap_or(*cfg)
+    }
+    /// Moves the axis flags of an interrupt source read with the sensor
+    /// configuration `cfg` to the board axes.
+    pub fn int_src_to_board(&self, src: &IntSrc, cfg: &IntCfg) -> IntSrc {
+        let raw = src.get_raw_value();
+        let pairs = self.pairs_to_board(
+            raw & AXIS_PAIR_BITS,
+            cfg.six_d() == OnOff::Enabled,
+        );
+        IntSrc::try_from(raw & !AXIS_PAIR_BITS | pairs).unwrap_or(*src)
+    }
+    /// Moves single and double click enables given for board axes to the
+    /// nearest sensor axes. Fails like [`AxisMap::int_cfg_to_sensor`].
+    pub fn click_cfg_to_sensor(
+        &self,
+        cfg: &ClickCfg,
+    ) -> Result<ClickCfg, &'static str> {
+        let pairs = self.pairs_to_sensor(cfg.get_raw_value(), false)?;
+        Ok(ClickCfg::try_from(pairs).unwrap_or(*cfg))
+    }
+    /// Inverse of [`AxisMap::click_cfg_to_sensor`].
+    pub fn click_cfg_to_board(&self, cfg: &ClickCfg) -> ClickCfg {
+        let pairs = self.pairs_to_board(cfg.get_raw_value(), false);
+        ClickCfg::try_from(pairs).unwrap_or(*cfg)
+    }
+    /// Moves the click axis to the board axes, flipping the sign if the
+    /// board axis points in the opposite direction.
+    pub fn click_src_to_board(&self, src: &ClickSrc) -> ClickSrc {
+        let raw = src.get_raw_value();
+        let mut mapped = raw & !0b111;
+        for (board, axis) in self.nearest_axes().iter().enumerate() {
+            if raw >> axis.index() & 1 == 1 {
+                mapped |= 1 << board;
+                if axis.is_negative() {
+                    mapped ^= 1 << CLICK_SIGN_BIT_OFFSET;
+                }
+            }
+        }
+        ClickSrc::try_from(mapped).unwrap_or(*src)
+    }
+    fn pairs_to_sensor(
+        &self,
+        pairs: u8,
+        swap_if_negative: bool,
+    ) -> Result<u8, &'static str> {
+        if pairs != 0 && !self.has_distinct_nearest_axes() {
+            return Err("Board axes do not have distinct nearest sensor axes");
+        }
+        let mut mapped = 0;
+        for (board, axis) in self.nearest_axes().iter().enumerate() {
+            let pair = swap(
+                pairs >> (2 * board) & 0b11,
+                swap_if_negative && axis.is_negative(),
+            );
+            mapped |= pair << (2 * axis.index());
+        }
+        Ok(mapped)
+    }
+    fn pairs_to_board(&self, pairs: u8, swap_if_negative: bool) -> u8 {
+        let mut mapped = 0;
+        for (board, axis) in self.nearest_axes().iter().enumerate() {
+            let pair = swap(
+                pairs >> (2 * axis.index()) & 0b11,
+                swap_if_negative && axis.is_negative(),
+            );
+            mapped |= pair << (2 * board);
+        }
+        mapped
+    }
+}
+
+fn cross(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Swaps the low and high flag of an axis.
+fn swap(pair: u8, swap: bool) -> u8 {
+    if swap {
+        (pair & 1) << 1 | pair >> 1
+    } else {
+        pair
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AxisMap, SignedAxis};
+    use crate::click_cfg::ClickCfg;
+    use crate::click_src::ClickSrc;
+    use crate::enabled_enum::OnOff;
+    use crate::int_cfg::IntCfg;
+    use crate::int_src::IntSrc;
+    use crate::register::Int2;
+    use crate::simulator::Simulator;
+    use crate::{Error, Lis3dh, RegisterAddresses};
+    use micromath::vector::I32x3;
+
+    const SAMPLE: I32x3 = I32x3 {
+        x: 100,
+        y: 200,
+        z: 1000,
+    };
+
+    #[test]
+    fn there_are_24_reversible_rotations() {
+        let rotations = AxisMap::rotations();
+        let mut count = 0;
+        for (index, rotation) in rotations.enumerate() {
+            assert_eq!(rotation == AxisMap::default(), index == 0);
+            assert_eq!(rotation.to_sensor(rotation.apply(SAMPLE)), SAMPLE);
+            count += 1;
+        }
+        assert_eq!(count, 24);
+        assert!(
+            AxisMap::rotation(SignedAxis::Y, SignedAxis::X, SignedAxis::Z)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn matrix_rotates_samples_and_interrupt_axes() {
+        let (sin, cos) = (0.5, 0.866_025_4);
+        let map = AxisMap::matrix([
+            [cos, sin, 0.0],
+            [-sin, cos, 0.0],
+            [0.0, 0.0, 1.0],
+        ])
+        .unwrap();
+        let sample = map.apply(I32x3 {
+            x: 1000,
+            y: 0,
+            z: 0,
+        });
+        assert_eq!((sample.x, sample.y, sample.z), (866, -500, 0));
+        assert_eq!(
+            map.to_sensor(sample),
+            I32x3 {
+                x: 1000,
+                y: 0,
+                z: 0
+            }
+        );
+        assert_eq!(
+            map.nearest_axes(),
+            [SignedAxis::X, SignedAxis::Y, SignedAxis::Z]
+        );
+        assert!(AxisMap::matrix([
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, -1.0],
+        ])
+        .is_err());
+        assert!(AxisMap::matrix([[1.0; 3]; 3]).is_err());
+    }
+
+    #[test]
+    fn matrices_without_distinct_nearest_axes_are_refused_for_interrupts() {
+        // Board X and Y are both closest to the sensor Y axis.
+        let map = AxisMap::matrix([
+            [0.344_691_4, 0.760_971, 0.549_646_2],
+            [0.549_646_2, -0.638_271_5, 0.538_979_2],
+            [0.760_971, 0.116_329_4, -0.638_271_5],
+        ])
+        .unwrap();
+        assert_eq!(
+            map.nearest_axes(),
+            [SignedAxis::Y, SignedAxis::MinusY, SignedAxis::X]
+        );
+        assert!(!map.has_distinct_nearest_axes());
+        let mut cfg = IntCfg::default();
+        assert!(map.int_cfg_to_sensor(&cfg).is_ok());
+        cfg.set_xhie(OnOff::Enabled);
+        assert!(map.int_cfg_to_sensor(&cfg).is_err());
+
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_int1_cfg_setting(cfg).unwrap();
+        assert!(lis3dh.set_axis_map(map).is_err());
+        assert_eq!(lis3dh.axis_map(), AxisMap::default());
+        lis3dh.set_int1_cfg_setting(IntCfg::default()).unwrap();
+        lis3dh.set_axis_map(map).unwrap();
+        assert!(lis3dh.set_int1_cfg_setting(cfg).is_err());
+        let mut click_cfg = ClickCfg::default();
+        click_cfg.set_zs(OnOff::Enabled);
+        assert!(matches!(
+            lis3dh.write_click_cfg(&mut cs, &mut spi, click_cfg),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn driver_uses_board_axes_for_samples_and_interrupts() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        // Rotated by 90° around Z.
+        lis3dh
+            .set_axis_map(
+                AxisMap::rotation(
+                    SignedAxis::Y,
+                    SignedAxis::MinusX,
+                    SignedAxis::Z,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        simulator.inject_sample(SAMPLE);
+        let sample = lis3dh.get_accel_norm(&mut cs, &mut spi).unwrap();
+        assert_eq!((sample.x, sample.y, sample.z), (200, -100, 1000));
+
+        let click_src = ClickSrc::try_from(0b0100_0001).unwrap();
+        let click_src = lis3dh.axis_map().click_src_to_board(&click_src);
+        assert!(click_src.is_y() && click_src.is_negative());
+
+        // Upside down, board Z high is sensor Z low in 6D mode only.
+        lis3dh
+            .set_axis_map(
+                AxisMap::rotation(
+                    SignedAxis::X,
+                    SignedAxis::MinusY,
+                    SignedAxis::MinusZ,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let mut cfg = IntCfg::default();
+        cfg.set_zhie(OnOff::Enabled);
+        cfg.set_six_d(OnOff::Enabled);
+        lis3dh.set_int1_cfg_setting(cfg).unwrap();
+        assert_eq!(lis3dh.int_1_cfg_setting(), cfg);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let written: IntCfg = lis3dh.read(&mut cs, &mut spi).unwrap();
+        assert_eq!(written.zlie(), OnOff::Enabled);
+        assert_eq!(written.zhie(), OnOff::Disabled);
+        let src = IntSrc::try_from(0b0101_0000).unwrap();
+        let src = lis3dh.axis_map().int_src_to_board(&src, &written);
+        assert!(src.is_z_high());
+
+        cfg.set_six_d(OnOff::Disabled);
+        lis3dh.set_int1_cfg_setting(cfg).unwrap();
+        assert_eq!(lis3dh.int_1_cfg_setting().zhie(), OnOff::Enabled);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let written: IntCfg = lis3dh.read(&mut cs, &mut spi).unwrap();
+        assert_eq!(written.zhie(), OnOff::Enabled);
+    }
+
+    #[test]
+    fn int1_configuration_keeps_board_axes_across_axis_map_changes() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        let mut cfg = IntCfg::default();
+        cfg.set_xhie(OnOff::Enabled);
+        lis3dh.set_int1_cfg_setting(cfg).unwrap();
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        assert!(lis3dh.dirty_registers().is_empty());
+
+        lis3dh
+            .set_axis_map(
+                AxisMap::rotation(
+                    SignedAxis::Y,
+                    SignedAxis::MinusX,
+                    SignedAxis::Z,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(lis3dh.int_1_cfg_setting(), cfg);
+        assert!(lis3dh
+            .dirty_registers()
+            .contains(RegisterAddresses::Int1Cfg));
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let written: IntCfg = lis3dh.read(&mut cs, &mut spi).unwrap();
+        assert_eq!(written.yhie(), OnOff::Enabled);
+        assert_eq!(
+            lis3dh.get_int_1_cfg_values(&mut cs, &mut spi).unwrap(),
+            cfg
+        );
+    }
+
+    #[test]
+    fn int2_and_click_configurations_follow_axis_map_changes() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        let mut int2_cfg = IntCfg::default();
+        int2_cfg.set_xhie(OnOff::Enabled);
+        lis3dh.write_int2_cfg(&mut cs, &mut spi, int2_cfg).unwrap();
+        let mut click_cfg = ClickCfg::default();
+        click_cfg.set_xs(OnOff::Enabled);
+        lis3dh
+            .write_click_cfg(&mut cs, &mut spi, click_cfg)
+            .unwrap();
+        assert!(lis3dh.dirty_registers().is_empty());
+
+        lis3dh
+            .set_axis_map(
+                AxisMap::rotation(
+                    SignedAxis::Y,
+                    SignedAxis::MinusX,
+                    SignedAxis::Z,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let dirty = lis3dh.dirty_registers();
+        assert!(dirty.contains(RegisterAddresses::Int2Cfg));
+        assert!(dirty.contains(RegisterAddresses::ClickCfg));
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let written: Int2<IntCfg> = lis3dh.read(&mut cs, &mut spi).unwrap();
+        assert_eq!(written.0.yhie(), OnOff::Enabled);
+        assert_eq!(written.0.xhie(), OnOff::Disabled);
+        let written: ClickCfg = lis3dh.read(&mut cs, &mut spi).unwrap();
+        assert_eq!(written.ys(), OnOff::Enabled);
+        assert_eq!(written.xs(), OnOff::Disabled);
+        assert!(lis3dh.dirty_registers().is_empty());
+    }
+}
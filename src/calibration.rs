@@ -0,0 +1,404 @@
+use micromath::vector::I32x3;
+use micromath::F32Ext;
+
+/// Acceleration measured along an axis pointing up, in mg.
+const GRAVITY: f32 = 1000.0;
+const FORMAT_VERSION: u8 = 1;
+/// Length of a serialised [`Calibration`].
+pub const CALIBRATION_SIZE: usize = 62;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    XUp,
+    XDown,
+    YUp,
+    YDown,
+    ZUp,
+    ZDown,
+}
+
+impl Orientation {
+    /// All orientations in the order the tumble procedure visits them.
+    pub const ALL: [Orientation; 6] = [
+        Orientation::ZUp,
+        Orientation::ZDown,
+        Orientation::XUp,
+        Orientation::XDown,
+        Orientation::YUp,
+        Orientation::YDown,
+    ];
+
+    /// Index of the axis pointing along gravity.
+    pub fn axis(&self) -> usize {
+        match self {
+            Orientation::XUp | Orientation::XDown => 0,
+            Orientation::YUp | Orientation::YDown => 1,
+            Orientation::ZUp | Orientation::ZDown => 2,
+        }
+    }
+    pub fn is_up(&self) -> bool {
+        matches!(self, Orientation::XUp | Orientation::YUp | Orientation::ZUp)
+    }
+    fn index(&self) -> usize {
+        2 * self.axis() + !self.is_up() as usize
+    }
+    fn up(axis: usize) -> Self {
+        [Orientation::XUp, Orientation::YUp, Orientation::ZUp][axis]
+    }
+    fn down(axis: usize) -> Self {
+        [Orientation::XDown, Orientation::YDown, Orientation::ZDown][axis]
+    }
+}
+
+/// Per-axis offset and scale correction with optional cross-axis
+/// compensation. A calibrated sample is
+/// `correction * (scale * (sample - offset))`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    offset: [f32; 3],
+    scale: [f32; 3],
+    correction: [[f32; 3]; 3],
+}
+
+const IDENTITY: [[f32; 3]; 3] =
+    [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration {
+            offset: [0.0; 3],
+            scale: [1.0; 3],
+            correction: IDENTITY,
+        }
+    }
+}
+
+impl Calibration {
+    pub fn new(offset: [f32; 3], scale: [f32; 3]) -> Self {
+        Calibration {
+            offset,
+            scale,
+            correction: IDENTITY,
+        }
+    }
+    /// Offset of every axis in mg.
+    pub fn offset(&self) -> [f32; 3] {
+        self.offset
+    }
+    pub fn scale(&self) -> [f32; 3] {
+        self.scale
+    }
+    /// Matrix compensating cross-axis sensitivity, identity if the
+    /// calibration was solved without it.
+    pub fn cross_axis_correction(&self) -> [[f32; 3]; 3] {
+        self.correction
+    }
+    /// Applies the calibration to a sample in mg.
+    pub fn apply(&self, sample: I32x3) -> I32x3 {
+        let scaled = [
+            (sample.x as f32 - self.offset[0]) * self.scale[0],
+            (sample.y as f32 - self.offset[1]) * self.scale[1],
+            (sample.z as f32 - self.offset[2]) * self.scale[2],
+        ];
+        let corrected = multiply(&self.correction, scaled);
+        I32x3 {
+            x: F32Ext::round(corrected[0]) as i32,
+            y: F32Ext::round(corrected[1]) as i32,
+            z: F32Ext::round(corrected[2]) as i32,
+        }
+    }
+    /// Serialises the calibration, e.g. to store it in flash. All values
+    /// are little-endian `f32`, followed by an XOR checksum.
+    pub fn to_bytes(&self) -> [u8; CALIBRATION_SIZE] {
+        let mut bytes = [0; CALIBRATION_SIZE];
+        bytes[0] = FORMAT_VERSION;
+        let values = self
+            .offset
+            .iter()
+            .chain(self.scale.iter())
+            .chain(self.correction.iter().flatten());
+        for (chunk, value) in bytes[1..].chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes[CALIBRATION_SIZE - 1] = checksum(&bytes[..CALIBRATION_SIZE - 1]);
+        bytes
+    }
+    pub fn from_bytes(
+        bytes: &[u8; CALIBRATION_SIZE],
+    ) -> Result<Self, &'static str> {
+        if bytes[0] != FORMAT_VERSION {
+            return Err("Unknown calibration format version");
+        }
+        if checksum(&bytes[..CALIBRATION_SIZE - 1])
+            != bytes[CALIBRATION_SIZE - 1]
+        {
+            return Err("Calibration checksum does not match");
+        }
+        let mut values = [0.0; 15];
+        for (value, chunk) in values.iter_mut().zip(bytes[1..].chunks_exact(4))
+        {
+            *value =
+                f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            if !value.is_finite() {
+                return Err("Calibration contains a non-finite value");
+            }
+        }
+        let mut calibration = Calibration::default();
+        calibration.offset.copy_from_slice(&values[0..3]);
+        calibration.scale.copy_from_slice(&values[3..6]);
+        for (row, chunk) in calibration
+            .correction
+            .iter_mut()
+            .zip(values[6..].chunks_exact(3))
+        {
+            row.copy_from_slice(chunk);
+        }
+        if calibration.scale.contains(&0.0) {
+            return Err("Calibration scale must not be zero");
+        }
+        Ok(calibration)
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |checksum, byte| checksum ^ byte)
+}
+
+fn multiply(matrix: &[[f32; 3]; 3], vector: [f32; 3]) -> [f32; 3] {
+    matrix
+        .map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+fn invert(m: &[[f32; 3]; 3]) -> Option<[[f32; 3]; 3]> {
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let determinant = m[0][0] * cofactor(1, 2, 1, 2)
+        - m[0][1] * cofactor(1, 2, 0, 2)
+        + m[0][2] * cofactor(1, 2, 0, 1);
+    if F32Ext::abs(determinant) < f32::EPSILON {
+        return None;
+    }
+    let adjugate = [
+        [
+            cofactor(1, 2, 1, 2),
+            -cofactor(0, 2, 1, 2),
+            cofactor(0, 1, 1, 2),
+        ],
+        [
+            -cofactor(1, 2, 0, 2),
+            cofactor(0, 2, 0, 2),
+            -cofactor(0, 1, 0, 2),
+        ],
+        [
+            cofactor(1, 2, 0, 1),
+            -cofactor(0, 2, 0, 1),
+            cofactor(0, 1, 0, 1),
+        ],
+    ];
+    Some(adjugate.map(|row| row.map(|value| value / determinant)))
+}
+
+/// Guides the six-position tumble calibration: the sensor is placed with
+/// every axis pointing up and down in turn while samples are averaged.
+///
+/// Feed uncalibrated samples, e.g. from
+/// [`crate::Lis3dh::get_accel_norm_uncalibrated`], for the orientation
+/// returned by [`SixPositionCalibration::next_orientation`]. Averaging at
+/// least 32 high-resolution samples per face while the sensor rests keeps
+/// the remaining tilt error well below 1°.
+#[derive(Clone, Copy, Debug)]
+pub struct SixPositionCalibration {
+    sums: [[i64; 3]; 6],
+    counts: [u32; 6],
+    samples_per_orientation: u32,
+}
+
+impl SixPositionCalibration {
+    pub fn new(samples_per_orientation: u32) -> Self {
+        SixPositionCalibration {
+            sums: [[0; 3]; 6],
+            counts: [0; 6],
+            samples_per_orientation: samples_per_orientation.max(1),
+        }
+    }
+    /// Returns the orientation still needing samples, `None` once all are
+    /// collected.
+    pub fn next_orientation(&self) -> Option<Orientation> {
+        Orientation::ALL
+            .into_iter()
+            .find(|orientation| !self.is_orientation_complete(*orientation))
+    }
+    pub fn is_orientation_complete(&self, orientation: Orientation) -> bool {
+        self.counts[orientation.index()] >= self.samples_per_orientation
+    }
+    pub fn is_complete(&self) -> bool {
+        self.next_orientation().is_none()
+    }
+    /// Adds an uncalibrated sample in mg. Samples whose dominant axis does
+    /// not match the orientation are refused, as the sensor was most
+    /// likely placed on the wrong face.
+    pub fn add_sample(
+        &mut self,
+        orientation: Orientation,
+        sample: I32x3,
+    ) -> Result<(), &'static str> {
+        let values = [sample.x, sample.y, sample.z];
+        let axis = orientation.axis();
+        let dominant = values[axis].abs() > values[(axis + 1) % 3].abs()
+            && values[axis].abs() > values[(axis + 2) % 3].abs();
+        if !dominant || (values[axis] > 0) != orientation.is_up() {
+            return Err("Sample does not match the orientation");
+        }
+        let index = orientation.index();
+        for (sum, value) in self.sums[index].iter_mut().zip(values) {
+            *sum += value as i64;
+        }
+        self.counts[index] += 1;
+        Ok(())
+    }
+    /// Average of the samples collected for the orientation in mg.
+    pub fn average(&self, orientation: Orientation) -> Option<[f32; 3]> {
+        let index = orientation.index();
+        let count = self.counts[index];
+        (count > 0)
+            .then(|| self.sums[index].map(|sum| sum as f32 / count as f32))
+    }
+    /// Solves offset and scale of every axis and, if requested, the
+    /// cross-axis sensitivity.
+    pub fn solve(
+        &self,
+        with_cross_axis: bool,
+    ) -> Result<Calibration, &'static str> {
+        if !self.is_complete() {
+            return Err("Samples are missing for at least one orientation");
+        }
+        let averages = Orientation::ALL.map(|orientation| {
+            (orientation, self.average(orientation).unwrap_or_default())
+        });
+        let average = |orientation: Orientation| {
+            averages
+                .iter()
+                .find(|(candidate, _)| *candidate == orientation)
+                .map(|(_, average)| *average)
+                .unwrap_or_default()
+        };
+        let mut calibration = Calibration::default();
+        for axis in 0..3 {
+            let up = average(Orientation::up(axis))[axis];
+            let down = average(Orientation::down(axis))[axis];
+            if up - down < GRAVITY / 2.0 {
+                return Err("Up and down readings of an axis are too close");
+            }
+            calibration.offset[axis] = (up + down) / 2.0;
+            calibration.scale[axis] = 2.0 * GRAVITY / (up - down);
+        }
+        if with_cross_axis {
+            let scaled = |orientation: Orientation| {
+                let average = average(orientation);
+                [0, 1, 2].map(|axis| {
+                    (average[axis] - calibration.offset[axis])
+                        * calibration.scale[axis]
+                })
+            };
+            let mut sensitivity = IDENTITY;
+            for axis in 0..3 {
+                let up = scaled(Orientation::up(axis));
+                let down = scaled(Orientation::down(axis));
+                for (row, sensitivity_row) in sensitivity.iter_mut().enumerate()
+                {
+                    if row != axis {
+                        sensitivity_row[axis] =
+                            (up[row] - down[row]) / (2.0 * GRAVITY);
+                    }
+                }
+            }
+            calibration.correction = invert(&sensitivity)
+                .ok_or("Cross-axis sensitivity can not be compensated")?;
+        }
+        Ok(calibration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Calibration, Orientation, SixPositionCalibration};
+    use micromath::vector::I32x3;
+    use micromath::F32Ext;
+
+    const GAIN: [f32; 3] = [1.04, 0.97, 1.02];
+    const OFFSET: [f32; 3] = [35.0, -52.0, 80.0];
+    const CROSS_AXIS: [[f32; 3]; 3] =
+        [[0.0, 0.02, -0.01], [0.015, 0.0, 0.01], [-0.02, 0.005, 0.0]];
+
+    /// Reading of a sensor with known errors for the true acceleration.
+    fn measure(truth: [f32; 3]) -> I32x3 {
+        let reading = [0, 1, 2].map(|axis| {
+            let coupled = truth[axis]
+                + (0..3)
+                    .map(|other| CROSS_AXIS[axis][other] * truth[other])
+                    .sum::<f32>();
+            GAIN[axis] * coupled + OFFSET[axis]
+        });
+        I32x3 {
+            x: F32Ext::round(reading[0]) as i32,
+            y: F32Ext::round(reading[1]) as i32,
+            z: F32Ext::round(reading[2]) as i32,
+        }
+    }
+
+    fn tumble() -> SixPositionCalibration {
+        let mut procedure = SixPositionCalibration::new(4);
+        while let Some(orientation) = procedure.next_orientation() {
+            let mut truth = [0.0; 3];
+            truth[orientation.axis()] =
+                if orientation.is_up() { 1000.0 } else { -1000.0 };
+            procedure.add_sample(orientation, measure(truth)).unwrap();
+        }
+        procedure
+    }
+
+    fn largest_error_in_mg(truth: [f32; 3], sample: I32x3) -> f32 {
+        let sample = [sample.x as f32, sample.y as f32, sample.z as f32];
+        (0..3)
+            .map(|axis| F32Ext::abs(sample[axis] - truth[axis]))
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn tumble_procedure_compensates_offset_gain_and_cross_axis() {
+        let procedure = tumble();
+        let calibration = procedure.solve(true).unwrap();
+        let truth = [500.0, -500.0, 707.1];
+        assert!(largest_error_in_mg(truth, measure(truth)) > 50.0);
+        // 3 mg correspond to less than 0.2° of tilt.
+        let calibrated = calibration.apply(measure(truth));
+        assert!(largest_error_in_mg(truth, calibrated) < 3.0);
+        let without_cross_axis = procedure.solve(false).unwrap();
+        assert_eq!(without_cross_axis.cross_axis_correction(), super::IDENTITY);
+    }
+
+    #[test]
+    fn samples_not_matching_the_orientation_are_refused() {
+        let mut procedure = SixPositionCalibration::new(1);
+        assert_eq!(procedure.next_orientation(), Some(Orientation::ZUp));
+        let z_down = I32x3 {
+            x: 10,
+            y: 20,
+            z: -990,
+        };
+        assert!(procedure.add_sample(Orientation::ZUp, z_down).is_err());
+        assert!(procedure.solve(false).is_err());
+        procedure.add_sample(Orientation::ZDown, z_down).unwrap();
+        assert_eq!(procedure.next_orientation(), Some(Orientation::ZUp));
+    }
+
+    #[test]
+    fn calibration_survives_serialisation() {
+        let calibration = tumble().solve(true).unwrap();
+        let mut bytes = calibration.to_bytes();
+        assert_eq!(Calibration::from_bytes(&bytes), Ok(calibration));
+        bytes[5] ^= 0x01;
+        assert!(Calibration::from_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::enabled_enum;
+use crate::enabled_enum::OnOff;
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+
+const XS_BIT_OFFSET: u8 = 0;
+const XD_BIT_OFFSET: u8 = 1;
+const YS_BIT_OFFSET: u8 = 2;
+const YD_BIT_OFFSET: u8 = 3;
+const ZS_BIT_OFFSET: u8 = 4;
+const ZD_BIT_OFFSET: u8 = 5;
+
+/// Enables single and double click detection per axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClickCfg {
+    zd: OnOff,
+    zs: OnOff,
+    yd: OnOff,
+    ys: OnOff,
+    xd: OnOff,
+    xs: OnOff,
+}
+
+impl ClickCfg {
+    pub fn set_zd(&mut self, zd: OnOff) {
+        self.zd = zd;
+    }
+    pub fn set_zs(&mut self, zs: OnOff) {
+        self.zs = zs;
+    }
+    pub fn set_yd(&mut self, yd: OnOff) {
+        self.yd = yd;
+    }
+    pub fn set_ys(&mut self, ys: OnOff) {
+        self.ys = ys;
+    }
+    pub fn set_xd(&mut self, xd: OnOff) {
+        self.xd = xd;
+    }
+    pub fn set_xs(&mut self, xs: OnOff) {
+        self.xs = xs;
+    }
+    pub fn zd(&self) -> OnOff {
+        self.zd
+    }
+    pub fn zs(&self) -> OnOff {
+        self.zs
+    }
+    pub fn yd(&self) -> OnOff {
+        self.yd
+    }
+    pub fn ys(&self) -> OnOff {
+        self.ys
+    }
+    pub fn xd(&self) -> OnOff {
+        self.xd
+    }
+    pub fn xs(&self) -> OnOff {
+        self.xs
+    }
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.zd as u8) << ZD_BIT_OFFSET
+            | (self.zs as u8) << ZS_BIT_OFFSET
+            | (self.yd as u8) << YD_BIT_OFFSET
+            | (self.ys as u8) << YS_BIT_OFFSET
+            | (self.xd as u8) << XD_BIT_OFFSET
+            | (self.xs as u8) << XS_BIT_OFFSET
+    }
+}
+
+impl TryFrom<u8> for ClickCfg {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & 0b1100_0000 != 0 {
+            return Err("CLICK_CFG bits 6 and 7 have to be 0");
+        }
+        Ok(ClickCfg {
+            zd: enabled_enum::get_state_from_bit_value(value >> ZD_BIT_OFFSET),
+            zs: enabled_enum::get_state_from_bit_value(value >> ZS_BIT_OFFSET),
+            yd: enabled_enum::get_state_from_bit_value(value >> YD_BIT_OFFSET),
+            ys: enabled_enum::get_state_from_bit_value(value >> YS_BIT_OFFSET),
+            xd: enabled_enum::get_state_from_bit_value(value >> XD_BIT_OFFSET),
+            xs: enabled_enum::get_state_from_bit_value(value >> XS_BIT_OFFSET),
+        })
+    }
+}
+
+impl Register for ClickCfg {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::ClickCfg;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("xs", 1 << XS_BIT_OFFSET),
+        RegisterField::new("xd", 1 << XD_BIT_OFFSET),
+        RegisterField::new("ys", 1 << YS_BIT_OFFSET),
+        RegisterField::new("yd", 1 << YD_BIT_OFFSET),
+        RegisterField::new("zs", 1 << ZS_BIT_OFFSET),
+        RegisterField::new("zd", 1 << ZD_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClickCfg, OnOff};
+
+    #[test]
+    fn conversion_from_raw_value_works() {
+        let click_cfg = ClickCfg::try_from(0b0010_0101).unwrap();
+        assert_eq!(click_cfg.zd(), OnOff::Enabled);
+        assert_eq!(click_cfg.zs(), OnOff::Disabled);
+        assert_eq!(click_cfg.ys(), OnOff::Enabled);
+        assert_eq!(click_cfg.xs(), OnOff::Enabled);
+        assert_eq!(click_cfg.xd(), OnOff::Disabled);
+        assert_eq!(click_cfg.get_raw_value(), 0b0010_0101);
+        assert!(ClickCfg::try_from(0b0100_0000).is_err());
+    }
+}
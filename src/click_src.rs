@@ -0,0 +1,111 @@
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+
+const X_BIT_OFFSET: u8 = 0;
+const Y_BIT_OFFSET: u8 = 1;
+const Z_BIT_OFFSET: u8 = 2;
+const SIGN_BIT_OFFSET: u8 = 3;
+const SCLICK_BIT_OFFSET: u8 = 4;
+const DCLICK_BIT_OFFSET: u8 = 5;
+const IA_BIT_OFFSET: u8 = 6;
+
+/// Content of CLICK_SRC. Reading it clears a latched click interrupt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClickSrc {
+    interrupt_active: bool,
+    double_click: bool,
+    single_click: bool,
+    negative: bool,
+    z: bool,
+    y: bool,
+    x: bool,
+}
+
+impl ClickSrc {
+    pub fn is_interrupt_active(&self) -> bool {
+        self.interrupt_active
+    }
+    pub fn is_double_click(&self) -> bool {
+        self.double_click
+    }
+    pub fn is_single_click(&self) -> bool {
+        self.single_click
+    }
+    /// True if the detected click was in negative direction.
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+    pub fn is_z(&self) -> bool {
+        self.z
+    }
+    pub fn is_y(&self) -> bool {
+        self.y
+    }
+    pub fn is_x(&self) -> bool {
+        self.x
+    }
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.interrupt_active as u8) << IA_BIT_OFFSET
+            | (self.double_click as u8) << DCLICK_BIT_OFFSET
+            | (self.single_click as u8) << SCLICK_BIT_OFFSET
+            | (self.negative as u8) << SIGN_BIT_OFFSET
+            | (self.z as u8) << Z_BIT_OFFSET
+            | (self.y as u8) << Y_BIT_OFFSET
+            | (self.x as u8) << X_BIT_OFFSET
+    }
+}
+
+impl TryFrom<u8> for ClickSrc {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & 0b1000_0000 != 0 {
+            return Err("CLICK_SRC bit 7 has to be 0");
+        }
+        Ok(ClickSrc {
+            interrupt_active: super::check_if_bit_is_set(value, IA_BIT_OFFSET),
+            double_click: super::check_if_bit_is_set(value, DCLICK_BIT_OFFSET),
+            single_click: super::check_if_bit_is_set(value, SCLICK_BIT_OFFSET),
+            negative: super::check_if_bit_is_set(value, SIGN_BIT_OFFSET),
+            z: super::check_if_bit_is_set(value, Z_BIT_OFFSET),
+            y: super::check_if_bit_is_set(value, Y_BIT_OFFSET),
+            x: super::check_if_bit_is_set(value, X_BIT_OFFSET),
+        })
+    }
+}
+
+impl Register for ClickSrc {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::ClickSrc;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadOnly;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("x", 1 << X_BIT_OFFSET),
+        RegisterField::new("y", 1 << Y_BIT_OFFSET),
+        RegisterField::new("z", 1 << Z_BIT_OFFSET),
+        RegisterField::new("sign", 1 << SIGN_BIT_OFFSET),
+        RegisterField::new("sclick", 1 << SCLICK_BIT_OFFSET),
+        RegisterField::new("dclick", 1 << DCLICK_BIT_OFFSET),
+        RegisterField::new("ia", 1 << IA_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn conversion_from_raw_value_works() {
+        let click_src = super::ClickSrc::try_from(0b0101_1100).unwrap();
+        assert!(click_src.is_interrupt_active());
+        assert!(click_src.is_single_click());
+        assert!(!click_src.is_double_click());
+        assert!(click_src.is_negative());
+        assert!(click_src.is_z());
+        assert!(!click_src.is_x());
+        assert_eq!(click_src.get_raw_value(), 0b0101_1100);
+        assert!(super::ClickSrc::try_from(0b1000_0000).is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use crate::enabled_enum;
 use crate::enabled_enum::OnOff;
 use crate::enabled_enum::OnOff::Enabled;
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
 
 const CTRL_REG_0_DEFAULT_VALUE_LOWER_7_BIT: u8 = 0b001_0000;
 const PULL_UP_CONFIG_BIT_OFFSET: u8 = 7;
@@ -29,13 +32,35 @@ impl CtrlReg0Value {
         (value << PULL_UP_CONFIG_BIT_OFFSET)
             | CTRL_REG_0_DEFAULT_VALUE_LOWER_7_BIT
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
-        CtrlReg0Value {
+}
+
+impl TryFrom<u8> for CtrlReg0Value {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & 0b0111_1111 != CTRL_REG_0_DEFAULT_VALUE_LOWER_7_BIT {
+            return Err("CTRL_REG0 lower 7 bit have to be 0b001_0000");
+        }
+        Ok(CtrlReg0Value {
             pull_up_connected_sdo_sa_0_pin:
                 enabled_enum::get_state_from_bit_value(
                     value >> PULL_UP_CONFIG_BIT_OFFSET,
                 ),
-        }
+        })
+    }
+}
+
+impl Register for CtrlReg0Value {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::CtrlReg0;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0b0001_0000;
+    const FIELDS: &'static [RegisterField] = &[RegisterField::new(
+        "pull_up_connected_sdo_sa_0_pin",
+        1 << PULL_UP_CONFIG_BIT_OFFSET,
+    )];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
     }
 }
 
@@ -44,13 +69,20 @@ mod tests {
     #[test]
     fn conversion_from_raw_value_works() {
         let ctrl_reg_0_raw = 0b1001_0000;
-        let ctrl_reg_0 = super::CtrlReg0Value::from_raw_value(ctrl_reg_0_raw);
+        let ctrl_reg_0 =
+            super::CtrlReg0Value::try_from(ctrl_reg_0_raw).unwrap();
         assert_eq!(
             ctrl_reg_0.pull_up_connected_sdo_sa_0_pin(),
             super::OnOff::Enabled
         );
     }
 
+    #[test]
+    fn conversion_from_raw_value_fails_if_lower_bits_are_modified() {
+        assert!(super::CtrlReg0Value::try_from(0b1000_0000).is_err());
+        assert!(super::CtrlReg0Value::try_from(0b0001_0001).is_err());
+    }
+
     #[test]
     fn conversion_to_raw_value_works() {
         let mut ctrl_reg_0 = super::CtrlReg0Value::default();
@@ -1,66 +1,52 @@
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
 #[derive(FromPrimitive)]
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum XEn {
     XAxisDisabled,
+    #[default]
     XAxisEnabled,
 }
 
-impl Default for XEn {
-    fn default() -> Self {
-        XEn::XAxisEnabled
-    }
-}
-
 const Y_EN_BIT_OFFSET: u8 = 1;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum YEn {
     YAxisDisabled,
+    #[default]
     YAxisEnabled,
 }
 
-impl Default for YEn {
-    fn default() -> Self {
-        YEn::YAxisEnabled
-    }
-}
-
 const Z_EN_BIT_OFFSET: u8 = 2;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum ZEn {
     ZAxisDisabled,
+    #[default]
     ZAxisEnabled,
 }
 
-impl Default for ZEn {
-    fn default() -> Self {
-        ZEn::ZAxisEnabled
-    }
-}
-
 const L_P_EN_BIT_OFFSET: u8 = 3;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum LPEn {
+    #[default]
     HighResolutionNormalMode,
     LowPowerEnabled,
 }
 
-impl Default for LPEn {
-    fn default() -> Self {
-        LPEn::HighResolutionNormalMode
-    }
-}
-
 const DATA_RATE_SELECTION_BIT_OFFSET: u8 = 4;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, FromPrimitive, ToPrimitive, PartialEq, Eq)]
+#[derive(
+    Copy, Clone, Debug, FromPrimitive, ToPrimitive, PartialEq, Eq, Default,
+)]
 pub enum ODR {
+    #[default]
     PowerDownMode = 0_u8,
     Hz1,
     Hz10,
@@ -73,9 +59,25 @@ pub enum ODR {
     HrNormal1Pont344kHzLowPower5Point376kHz,
 }
 
-impl Default for ODR {
-    fn default() -> Self {
-        ODR::PowerDownMode
+impl ODR {
+    /// Sample rate in Hz, `None` in power-down mode or if the data rate is
+    /// not available in the selected power mode.
+    pub fn sample_rate_hz(&self, l_p_en: LPEn) -> Option<u32> {
+        let low_power = l_p_en == LPEn::LowPowerEnabled;
+        match self {
+            ODR::PowerDownMode => None,
+            ODR::Hz1 => Some(1),
+            ODR::Hz10 => Some(10),
+            ODR::Hz25 => Some(25),
+            ODR::Hz50 => Some(50),
+            ODR::Hz100 => Some(100),
+            ODR::Hz200 => Some(200),
+            ODR::Hz400 => Some(400),
+            ODR::LowPowerMode1Point6kHz => low_power.then_some(1600),
+            ODR::HrNormal1Pont344kHzLowPower5Point376kHz => {
+                Some(if low_power { 5376 } else { 1344 })
+            }
+        }
     }
 }
 
@@ -119,6 +121,13 @@ impl CtrlReg1Value {
     pub fn output_data_rate(&self) -> ODR {
         self.output_data_rate
     }
+    /// Sample rate in Hz, an error in power-down mode or if the data rate
+    /// is not available in the selected power mode.
+    pub fn sample_rate_hz(&self) -> Result<u32, &'static str> {
+        self.output_data_rate
+            .sample_rate_hz(self.l_p_en)
+            .ok_or("Sensor does not sample at the configured data rate")
+    }
     pub(super) fn get_raw_value(&self) -> u8 {
         (self.output_data_rate as u8) << DATA_RATE_SELECTION_BIT_OFFSET
             | (self.l_p_en as u8) << L_P_EN_BIT_OFFSET
@@ -126,7 +135,12 @@ impl CtrlReg1Value {
             | (self.y_en as u8) << Y_EN_BIT_OFFSET
             | self.x_en as u8
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
+}
+
+impl TryFrom<u8> for CtrlReg1Value {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         let x_en = if value & 1 == 1 {
             XEn::XAxisEnabled
         } else {
@@ -148,15 +162,36 @@ impl CtrlReg1Value {
             LPEn::HighResolutionNormalMode
         };
         let output_data_rate =
-            ODR::from_u8(value >> DATA_RATE_SELECTION_BIT_OFFSET).unwrap();
+            ODR::from_u8(value >> DATA_RATE_SELECTION_BIT_OFFSET)
+                .ok_or("Output data rate selection 10 to 15 is not allowed")?;
 
-        CtrlReg1Value {
+        Ok(CtrlReg1Value {
             x_en,
             y_en,
             z_en,
             l_p_en,
             output_data_rate,
-        }
+        })
+    }
+}
+
+impl Register for CtrlReg1Value {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::CtrlReg1;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0b0000_0111;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("x_en", 1),
+        RegisterField::new("y_en", 1 << Y_EN_BIT_OFFSET),
+        RegisterField::new("z_en", 1 << Z_EN_BIT_OFFSET),
+        RegisterField::new("l_p_en", 1 << L_P_EN_BIT_OFFSET),
+        RegisterField::new(
+            "output_data_rate",
+            0b1111 << DATA_RATE_SELECTION_BIT_OFFSET,
+        ),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
     }
 }
 
@@ -165,7 +200,7 @@ mod tests {
     #[test]
     fn conversion_from_raw_value_works() {
         let raw_value = 0b10_0101_u8;
-        let ctrl_reg_1 = super::CtrlReg1Value::from_raw_value(raw_value);
+        let ctrl_reg_1 = super::CtrlReg1Value::try_from(raw_value).unwrap();
         assert_eq!(ctrl_reg_1.x_en, super::XEn::XAxisEnabled);
         assert_eq!(ctrl_reg_1.y_en, super::YEn::YAxisDisabled);
         assert_eq!(ctrl_reg_1.z_en, super::ZEn::ZAxisEnabled);
@@ -174,9 +209,44 @@ mod tests {
     }
 
     #[test]
+    fn conversion_from_raw_value_fails_for_reserved_data_rate() {
+        assert!(super::CtrlReg1Value::try_from(0b1001_0111).is_ok());
+        assert!(super::CtrlReg1Value::try_from(0b1010_0111).is_err());
+        assert!(super::CtrlReg1Value::try_from(0b1111_0111).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn conversion_to_raw_value_works() {
         let mut ctrl_reg_value = super::CtrlReg1Value::default();
         ctrl_reg_value.output_data_rate = super::ODR::Hz50;
         assert_eq!(ctrl_reg_value.get_raw_value(), 0b100_0111);
     }
+
+    #[test]
+    fn sample_rate_depends_on_power_mode() {
+        use super::{LPEn, ODR};
+        assert_eq!(
+            ODR::PowerDownMode.sample_rate_hz(LPEn::LowPowerEnabled),
+            None
+        );
+        assert_eq!(
+            ODR::Hz400.sample_rate_hz(LPEn::HighResolutionNormalMode),
+            Some(400)
+        );
+        assert_eq!(
+            ODR::HrNormal1Pont344kHzLowPower5Point376kHz
+                .sample_rate_hz(LPEn::LowPowerEnabled),
+            Some(5376)
+        );
+        assert_eq!(
+            ODR::LowPowerMode1Point6kHz
+                .sample_rate_hz(LPEn::HighResolutionNormalMode),
+            None
+        );
+        let mut ctrl_reg1 = super::CtrlReg1Value::default();
+        assert!(ctrl_reg1.sample_rate_hz().is_err());
+        ctrl_reg1.set_output_data_rate(ODR::Hz25);
+        assert_eq!(ctrl_reg1.sample_rate_hz(), Ok(25));
+    }
 }
@@ -1,19 +1,18 @@
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 const HPM_BIT_OFFSET: u8 = 6;
 #[repr(u8)]
-#[derive(Copy, Clone, FromPrimitive, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, FromPrimitive, Debug, PartialEq, Eq, Default)]
 pub enum HighPassFilterModeSelection {
+    #[default]
     NormalResetByReadingReference,
     ReferenceSignalForFiltering,
     Normal,
     AutoResetOnInterrupt,
 }
-impl Default for HighPassFilterModeSelection {
-    fn default() -> Self {
-        HighPassFilterModeSelection::NormalResetByReadingReference
-    }
-}
 
 /// High-pass filter cutoff frequency selection
 /// The bandwidth of the high-pass filter depends on the selected ODR and on the settings of
@@ -28,45 +27,33 @@ impl Default for HighPassFilterModeSelection {
 /// |:--------|:----- |:-----|:-----|:------|:-------|:-------|:-------|:------- |:------|
 const HPCF_BIT_OFFSET: u8 = 4;
 #[repr(u8)]
-#[derive(Copy, Clone, FromPrimitive, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, FromPrimitive, Debug, PartialEq, Eq, Default)]
 pub enum HighPassFilterCutOffFrequencySelection {
+    #[default]
     OneFiftieth,
     AHundredthOrOneHundredAndTwentyFifth,
     ATwHundredthOrOneTwoHundredAndFiftieth,
     AFourHundredthOrAFifeHundredth,
 }
-impl Default for HighPassFilterCutOffFrequencySelection {
-    fn default() -> Self {
-        HighPassFilterCutOffFrequencySelection::OneFiftieth
-    }
-}
 
 const FDS_BIT_OFFSET: u8 = 3;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum FilteredDataSelection {
+    #[default]
     InternalFilterBypassed,
     InternalFilterSentToFifo,
 }
-impl Default for FilteredDataSelection {
-    fn default() -> Self {
-        FilteredDataSelection::InternalFilterBypassed
-    }
-}
 
 const HP_CLICK_BIT_OFFSET: u8 = 2;
 const HP_IA2_BIT_OFFSET: u8 = 1;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum HighPassFilter {
+    #[default]
     FilterBypassed,
     FilterEnabled,
 }
-impl Default for HighPassFilter {
-    fn default() -> Self {
-        HighPassFilter::FilterBypassed
-    }
-}
 
 #[derive(Clone, Copy, Default, PartialEq, Eq)]
 pub struct CtrlReg2Value {
@@ -123,7 +110,12 @@ impl CtrlReg2Value {
             | (self.hp_ia2 as u8) << HP_IA2_BIT_OFFSET
             | self.hp_ia1 as u8
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
+}
+
+impl TryFrom<u8> for CtrlReg2Value {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         let hp_ia1 = if value & 1 == 1 {
             HighPassFilter::FilterEnabled
         } else {
@@ -147,19 +139,37 @@ impl CtrlReg2Value {
         let hpcf = HighPassFilterCutOffFrequencySelection::from_u8(
             (value & 0b11_0000) >> HPCF_BIT_OFFSET,
         )
-        .unwrap();
+        .ok_or("Invalid high-pass filter cutoff frequency selection")?;
         let hpm = HighPassFilterModeSelection::from_u8(
             (value & 0b1100_0000) >> HPM_BIT_OFFSET,
         )
-        .unwrap();
-        CtrlReg2Value {
+        .ok_or("Invalid high-pass filter mode selection")?;
+        Ok(CtrlReg2Value {
             hp_ia1,
             hp_ia2,
             hp_click,
             fds,
             hpcf,
             hpm,
-        }
+        })
+    }
+}
+
+impl Register for CtrlReg2Value {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::CtrlReg2;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("hp_ia1", 1),
+        RegisterField::new("hp_ia2", 1 << HP_IA2_BIT_OFFSET),
+        RegisterField::new("hp_click", 1 << HP_CLICK_BIT_OFFSET),
+        RegisterField::new("fds", 1 << FDS_BIT_OFFSET),
+        RegisterField::new("hpcf", 0b11 << HPCF_BIT_OFFSET),
+        RegisterField::new("hpm", 0b11 << HPM_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
     }
 }
 
@@ -168,7 +178,7 @@ mod tests {
     #[test]
     fn conversion_from_raw_value_works() {
         let raw_value = 0b0110_0101_u8;
-        let ctrl_reg_2 = super::CtrlReg2Value::from_raw_value(raw_value);
+        let ctrl_reg_2 = super::CtrlReg2Value::try_from(raw_value).unwrap();
         assert_eq!(ctrl_reg_2.hp_ia1, super::HighPassFilter::FilterEnabled);
         assert_eq!(ctrl_reg_2.hp_ia2, super::HighPassFilter::FilterBypassed);
         assert_eq!(ctrl_reg_2.hp_click, super::HighPassFilter::FilterEnabled);
@@ -1,5 +1,8 @@
 use crate::enabled_enum;
 use crate::enabled_enum::OnOff;
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
 
 const OVERRUN_BIT_OFFSET: u8 = 1;
 const WTM_BIT_OFFSET: u8 = 2;
@@ -69,8 +72,25 @@ impl CtrlReg3Value {
     pub fn interrupt_1_fifo_overrun(&self) -> OnOff {
         self.interrupt_1_fifo_overrun
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
-        CtrlReg3Value {
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.interrupt_1_click as u8) << CLICK_BIT_OFFSET
+            | (self.interrupt_1_ia1 as u8) << IA_1_BIT_OFFSET
+            | (self.interrupt_1_ia2 as u8) << IA_2_BIT_OFFSET
+            | (self.interrupt_1_zyx_da as u8) << ZYX_DA_BIT_OFFSET
+            | (self.interrupt_1_321_da as u8) << DA_321_BIT_OFFSET
+            | (self.interrupt_1_fifo_watermark as u8) << WTM_BIT_OFFSET
+            | (self.interrupt_1_fifo_overrun as u8) << OVERRUN_BIT_OFFSET
+    }
+}
+
+impl TryFrom<u8> for CtrlReg3Value {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & 1 == 1 {
+            return Err("CTRL_REG3 bit 0 has to be 0");
+        }
+        Ok(CtrlReg3Value {
             interrupt_1_click: enabled_enum::get_state_from_bit_value(
                 value >> CLICK_BIT_OFFSET,
             ),
@@ -92,16 +112,26 @@ impl CtrlReg3Value {
             interrupt_1_fifo_overrun: enabled_enum::get_state_from_bit_value(
                 value >> OVERRUN_BIT_OFFSET,
             ),
-        }
+        })
     }
-    pub(super) fn get_raw_value(&self) -> u8 {
-        (self.interrupt_1_click as u8) << CLICK_BIT_OFFSET
-            | (self.interrupt_1_ia1 as u8) << IA_1_BIT_OFFSET
-            | (self.interrupt_1_ia2 as u8) << IA_2_BIT_OFFSET
-            | (self.interrupt_1_zyx_da as u8) << ZYX_DA_BIT_OFFSET
-            | (self.interrupt_1_321_da as u8) << DA_321_BIT_OFFSET
-            | (self.interrupt_1_fifo_watermark as u8) << WTM_BIT_OFFSET
-            | (self.interrupt_1_fifo_overrun as u8) << OVERRUN_BIT_OFFSET
+}
+
+impl Register for CtrlReg3Value {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::CtrlReg3;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("interrupt_1_fifo_overrun", 1 << OVERRUN_BIT_OFFSET),
+        RegisterField::new("interrupt_1_fifo_watermark", 1 << WTM_BIT_OFFSET),
+        RegisterField::new("interrupt_1_321_da", 1 << DA_321_BIT_OFFSET),
+        RegisterField::new("interrupt_1_zyx_da", 1 << ZYX_DA_BIT_OFFSET),
+        RegisterField::new("interrupt_1_ia2", 1 << IA_2_BIT_OFFSET),
+        RegisterField::new("interrupt_1_ia1", 1 << IA_1_BIT_OFFSET),
+        RegisterField::new("interrupt_1_click", 1 << CLICK_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
     }
 }
 
@@ -109,8 +139,8 @@ impl CtrlReg3Value {
 mod tests {
     #[test]
     fn conversion_from_raw_value_works() {
-        let raw_value = 0b1010_0101_u8;
-        let ctrl_reg_3 = super::CtrlReg3Value::from_raw_value(raw_value);
+        let raw_value = 0b1010_0100_u8;
+        let ctrl_reg_3 = super::CtrlReg3Value::try_from(raw_value).unwrap();
         assert_eq!(ctrl_reg_3.interrupt_1_click, super::OnOff::Enabled);
         assert_eq!(ctrl_reg_3.interrupt_1_ia1, super::OnOff::Disabled);
         assert_eq!(ctrl_reg_3.interrupt_1_ia2, super::OnOff::Enabled);
@@ -124,6 +154,12 @@ mod tests {
     }
 
     #[test]
+    fn conversion_from_raw_value_fails_if_bit_0_is_set() {
+        assert!(super::CtrlReg3Value::try_from(0b1010_0101).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn conversion_to_raw_value_works() {
         let mut ctrl_reg_3_value = super::CtrlReg3Value::default();
         ctrl_reg_3_value.interrupt_1_ia1 = super::OnOff::Enabled;
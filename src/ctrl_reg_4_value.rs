@@ -1,75 +1,83 @@
 use crate::enabled_enum::OnOff;
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum SpiModeSelection {
+    #[default]
     FourWireInterface,
     ThreeWireInterface,
 }
-impl Default for SpiModeSelection {
-    fn default() -> Self {
-        SpiModeSelection::FourWireInterface
-    }
-}
 
 const SELF_TEST_BIT_OFFSET: u8 = 1;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, FromPrimitive, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, FromPrimitive, PartialEq, Eq, Default)]
 pub enum SelfTest {
+    #[default]
     NormalMode,
     SelfTest0,
     SelfTest1,
 }
-impl Default for SelfTest {
-    fn default() -> Self {
-        SelfTest::NormalMode
-    }
-}
 
 const HIGH_RESOLUTION_OUTPUT_MODE_BIT_OFFSET: u8 = 3;
 
 const FULL_SCALE_SELECTION_OUTPUT_MODE_BIT_OFFSET: u8 = 4;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, FromPrimitive, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, FromPrimitive, PartialEq, Eq, Default)]
 pub enum FullScaleSelection {
+    #[default]
     Gravity2G,
     Gravity4G,
     Gravity8G,
     Gravity16G,
 }
-impl Default for FullScaleSelection {
-    fn default() -> Self {
-        FullScaleSelection::Gravity2G
-    }
-}
 
 const BLE_SETTING_BIT_OFFSET: u8 = 6;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum Endianness {
+    #[default]
     LSBLowerAddress,
     MSBLowerAddress,
 }
-impl Default for Endianness {
-    fn default() -> Self {
-        Endianness::LSBLowerAddress
+
+impl Endianness {
+    /// Combines the bytes read from the lower and the higher address of a
+    /// 16-bit output register pair.
+    pub fn decode_i16(&self, lower_address: u8, higher_address: u8) -> i16 {
+        match self {
+            Endianness::LSBLowerAddress => {
+                i16::from_le_bytes([lower_address, higher_address])
+            }
+            Endianness::MSBLowerAddress => {
+                i16::from_be_bytes([lower_address, higher_address])
+            }
+        }
+    }
+    /// Splits a value into the bytes for the lower and the higher address.
+    pub fn encode_i16(&self, value: i16) -> [u8; 2] {
+        match self {
+            Endianness::LSBLowerAddress => value.to_le_bytes(),
+            Endianness::MSBLowerAddress => value.to_be_bytes(),
+        }
+    }
+    pub fn decode_u16(&self, lower_address: u8, higher_address: u8) -> u16 {
+        self.decode_i16(lower_address, higher_address) as u16
     }
 }
 
 const BDU_SETTING_BIT_OFFSET: u8 = 7;
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum BlockDataUpdate {
+    #[default]
     ContinuousUpdate,
     NotUpdated,
 }
-impl Default for BlockDataUpdate {
-    fn default() -> Self {
-        BlockDataUpdate::ContinuousUpdate
-    }
-}
 
 #[derive(Clone, Copy, Default, PartialEq, Eq)]
 pub struct CtrlReg4Value {
@@ -126,14 +134,19 @@ impl CtrlReg4Value {
             | (self.st as u8) << SELF_TEST_BIT_OFFSET
             | self.sim as u8
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
+}
+
+impl TryFrom<u8> for CtrlReg4Value {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         let sim = if value & 1 == 1 {
             SpiModeSelection::ThreeWireInterface
         } else {
             SpiModeSelection::FourWireInterface
         };
-        let st =
-            SelfTest::from_u8((value & 0b110) >> SELF_TEST_BIT_OFFSET).unwrap();
+        let st = SelfTest::from_u8((value & 0b110) >> SELF_TEST_BIT_OFFSET)
+            .ok_or("Self test selection 0b11 is not allowed")?;
         let hr = if (value >> HIGH_RESOLUTION_OUTPUT_MODE_BIT_OFFSET) & 1 == 1 {
             OnOff::Enabled
         } else {
@@ -143,7 +156,7 @@ impl CtrlReg4Value {
             (value & 0b0011_0000)
                 >> FULL_SCALE_SELECTION_OUTPUT_MODE_BIT_OFFSET,
         )
-        .unwrap();
+        .ok_or("Invalid full scale selection")?;
         let ble = if value >> BLE_SETTING_BIT_OFFSET & 1 == 1 {
             Endianness::MSBLowerAddress
         } else {
@@ -154,14 +167,35 @@ impl CtrlReg4Value {
         } else {
             BlockDataUpdate::ContinuousUpdate
         };
-        CtrlReg4Value {
+        Ok(CtrlReg4Value {
             sim,
             st,
             hr,
             fs,
             ble,
             bdu,
-        }
+        })
+    }
+}
+
+impl Register for CtrlReg4Value {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::CtrlReg4;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("sim", 1),
+        RegisterField::new("st", 0b11 << SELF_TEST_BIT_OFFSET),
+        RegisterField::new("hr", 1 << HIGH_RESOLUTION_OUTPUT_MODE_BIT_OFFSET),
+        RegisterField::new(
+            "fs",
+            0b11 << FULL_SCALE_SELECTION_OUTPUT_MODE_BIT_OFFSET,
+        ),
+        RegisterField::new("ble", 1 << BLE_SETTING_BIT_OFFSET),
+        RegisterField::new("bdu", 1 << BDU_SETTING_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
     }
 }
 
@@ -170,11 +204,17 @@ mod test {
     #[test]
     fn conversion_from_raw_value_works() {
         let raw_value = 0b0101_0100_u8;
-        let ctrl_reg_4 = super::CtrlReg4Value::from_raw_value(raw_value);
+        let ctrl_reg_4 = super::CtrlReg4Value::try_from(raw_value).unwrap();
         assert_eq!(ctrl_reg_4.st, super::SelfTest::SelfTest1)
     }
 
     #[test]
+    fn conversion_from_raw_value_fails_for_reserved_self_test() {
+        assert!(super::CtrlReg4Value::try_from(0b0101_0110).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn conversion_to_raw_value_works() {
         let mut ctrl_reg_value = super::CtrlReg4Value::default();
         ctrl_reg_value.sim = super::SpiModeSelection::ThreeWireInterface;
@@ -184,4 +224,14 @@ mod test {
         ctrl_reg_value.ble = super::Endianness::MSBLowerAddress;
         assert_eq!(ctrl_reg_value.get_raw_value(), 0b0110_1101);
     }
+
+    #[test]
+    fn output_registers_are_decoded_according_to_endianness() {
+        let little = super::Endianness::LSBLowerAddress;
+        let big = super::Endianness::MSBLowerAddress;
+        assert_eq!(little.decode_i16(0x10, 0xc1), -0x3ef0);
+        assert_eq!(big.decode_i16(0xc1, 0x10), -0x3ef0);
+        assert_eq!(little.decode_u16(0x34, 0x12), 0x1234);
+        assert_eq!(big.decode_u16(0x12, 0x34), 0x1234);
+    }
 }
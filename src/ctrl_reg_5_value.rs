@@ -1,5 +1,8 @@
 use crate::enabled_enum;
 use crate::enabled_enum::OnOff;
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
 
 const D4D_INT2_BIT_OFFSET: u8 = 0;
 const LIR_INT2_BIT_OFFSET: u8 = 1;
@@ -8,19 +11,14 @@ const LIR_INT1_BIT_OFFSET: u8 = 3;
 const FIFO_EN_BIT_OFFSET: u8 = 6;
 const BOOT_BIT_OFFSET: u8 = 7;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 #[repr(u8)]
 pub enum BootMode {
+    #[default]
     NormalMode,
     RebootMemoryContent,
 }
 
-impl Default for BootMode {
-    fn default() -> Self {
-        BootMode::NormalMode
-    }
-}
-
 #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub struct CtrlReg5Value {
     boot: BootMode,
@@ -80,10 +78,18 @@ impl CtrlReg5Value {
             | (self.latch_int_on_int_1_src as u8) << LIR_INT1_BIT_OFFSET
             | (self.d4_detection_on_int_1 as u8) << D4D_INT1_BIT_OFFSET
             | (self.latch_int_on_int_2_src as u8) << LIR_INT2_BIT_OFFSET
-            | (self.d4_detection_on_int_2 as u8) << LIR_INT2_BIT_OFFSET
+            | (self.d4_detection_on_int_2 as u8) << D4D_INT2_BIT_OFFSET
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
-        CtrlReg5Value {
+}
+
+impl TryFrom<u8> for CtrlReg5Value {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & 0b0011_0000 != 0 {
+            return Err("CTRL_REG5 bit 4 and 5 have to be 0");
+        }
+        Ok(CtrlReg5Value {
             boot: if (value >> BOOT_BIT_OFFSET) == 0 {
                 BootMode::NormalMode
             } else {
@@ -104,7 +110,25 @@ impl CtrlReg5Value {
             d4_detection_on_int_2: enabled_enum::get_state_from_bit_value(
                 value >> D4D_INT2_BIT_OFFSET,
             ),
-        }
+        })
+    }
+}
+
+impl Register for CtrlReg5Value {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::CtrlReg5;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("d4_detection_on_int_2", 1 << D4D_INT2_BIT_OFFSET),
+        RegisterField::new("latch_int_on_int_2_src", 1 << LIR_INT2_BIT_OFFSET),
+        RegisterField::new("d4_detection_on_int_1", 1 << D4D_INT1_BIT_OFFSET),
+        RegisterField::new("latch_int_on_int_1_src", 1 << LIR_INT1_BIT_OFFSET),
+        RegisterField::new("fifo", 1 << FIFO_EN_BIT_OFFSET),
+        RegisterField::new("boot", 1 << BOOT_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
     }
 }
 
@@ -117,7 +141,7 @@ mod tests {
     fn conversion_from_raw_value_works() {
         let ctrl_reg_5_value_raw = 0b1000_0101_u8;
         let ctrl_reg_5_value =
-            super::CtrlReg5Value::from_raw_value(ctrl_reg_5_value_raw);
+            super::CtrlReg5Value::try_from(ctrl_reg_5_value_raw).unwrap();
         assert_eq!(
             ctrl_reg_5_value,
             super::CtrlReg5Value {
@@ -131,6 +155,11 @@ mod tests {
         );
     }
 
+    #[test]
+    fn conversion_from_raw_value_fails_if_unused_bits_are_set() {
+        assert!(super::CtrlReg5Value::try_from(0b1001_0101).is_err());
+    }
+
     #[test]
     fn conversion_to_raw_value_works() {
         let ctrl_reg_5_value = super::CtrlReg5Value {
@@ -143,4 +172,12 @@ mod tests {
         };
         assert_eq!(ctrl_reg_5_value.get_raw_value(), 0b1000_0100);
     }
+
+    #[test]
+    fn conversion_to_raw_value_and_back_works() {
+        let raw_value = 0b0100_1111_u8;
+        let ctrl_reg_5_value =
+            super::CtrlReg5Value::try_from(raw_value).unwrap();
+        assert_eq!(ctrl_reg_5_value.get_raw_value(), raw_value);
+    }
 }
@@ -0,0 +1,161 @@
+use crate::enabled_enum;
+use crate::enabled_enum::OnOff;
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+
+const INT_POLARITY_BIT_OFFSET: u8 = 1;
+const ACT_BIT_OFFSET: u8 = 3;
+const BOOT_BIT_OFFSET: u8 = 4;
+const IA_2_BIT_OFFSET: u8 = 5;
+const IA_1_BIT_OFFSET: u8 = 6;
+const CLICK_BIT_OFFSET: u8 = 7;
+const FIXED_BITS: u8 = 0b0000_0101;
+
+/// Level of the interrupt pins while an interrupt is active.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum InterruptPolarity {
+    #[default]
+    ActiveHigh,
+    ActiveLow,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CtrlReg6Value {
+    interrupt_2_click: OnOff,
+    interrupt_2_ia1: OnOff,
+    interrupt_2_ia2: OnOff,
+    interrupt_2_boot: OnOff,
+    interrupt_2_activity: OnOff,
+    interrupt_polarity: InterruptPolarity,
+}
+
+impl CtrlReg6Value {
+    pub fn set_interrupt_2_click(&mut self, interrupt_2_click: OnOff) {
+        self.interrupt_2_click = interrupt_2_click;
+    }
+    pub fn set_interrupt_2_ia1(&mut self, interrupt_2_ia1: OnOff) {
+        self.interrupt_2_ia1 = interrupt_2_ia1;
+    }
+    pub fn set_interrupt_2_ia2(&mut self, interrupt_2_ia2: OnOff) {
+        self.interrupt_2_ia2 = interrupt_2_ia2;
+    }
+    pub fn set_interrupt_2_boot(&mut self, interrupt_2_boot: OnOff) {
+        self.interrupt_2_boot = interrupt_2_boot;
+    }
+    pub fn set_interrupt_2_activity(&mut self, interrupt_2_activity: OnOff) {
+        self.interrupt_2_activity = interrupt_2_activity;
+    }
+    pub fn set_interrupt_polarity(
+        &mut self,
+        interrupt_polarity: InterruptPolarity,
+    ) {
+        self.interrupt_polarity = interrupt_polarity;
+    }
+    pub fn interrupt_2_click(&self) -> OnOff {
+        self.interrupt_2_click
+    }
+    pub fn interrupt_2_ia1(&self) -> OnOff {
+        self.interrupt_2_ia1
+    }
+    pub fn interrupt_2_ia2(&self) -> OnOff {
+        self.interrupt_2_ia2
+    }
+    pub fn interrupt_2_boot(&self) -> OnOff {
+        self.interrupt_2_boot
+    }
+    pub fn interrupt_2_activity(&self) -> OnOff {
+        self.interrupt_2_activity
+    }
+    pub fn interrupt_polarity(&self) -> InterruptPolarity {
+        self.interrupt_polarity
+    }
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.interrupt_2_click as u8) << CLICK_BIT_OFFSET
+            | (self.interrupt_2_ia1 as u8) << IA_1_BIT_OFFSET
+            | (self.interrupt_2_ia2 as u8) << IA_2_BIT_OFFSET
+            | (self.interrupt_2_boot as u8) << BOOT_BIT_OFFSET
+            | (self.interrupt_2_activity as u8) << ACT_BIT_OFFSET
+            | (self.interrupt_polarity as u8) << INT_POLARITY_BIT_OFFSET
+    }
+}
+
+impl TryFrom<u8> for CtrlReg6Value {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & FIXED_BITS != 0 {
+            return Err("CTRL_REG6 bits 0 and 2 have to be 0");
+        }
+        Ok(CtrlReg6Value {
+            interrupt_2_click: enabled_enum::get_state_from_bit_value(
+                value >> CLICK_BIT_OFFSET,
+            ),
+            interrupt_2_ia1: enabled_enum::get_state_from_bit_value(
+                value >> IA_1_BIT_OFFSET,
+            ),
+            interrupt_2_ia2: enabled_enum::get_state_from_bit_value(
+                value >> IA_2_BIT_OFFSET,
+            ),
+            interrupt_2_boot: enabled_enum::get_state_from_bit_value(
+                value >> BOOT_BIT_OFFSET,
+            ),
+            interrupt_2_activity: enabled_enum::get_state_from_bit_value(
+                value >> ACT_BIT_OFFSET,
+            ),
+            interrupt_polarity: if super::check_if_bit_is_set(
+                value,
+                INT_POLARITY_BIT_OFFSET,
+            ) {
+                InterruptPolarity::ActiveLow
+            } else {
+                InterruptPolarity::ActiveHigh
+            },
+        })
+    }
+}
+
+impl Register for CtrlReg6Value {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::CtrlReg6;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("int_polarity", 1 << INT_POLARITY_BIT_OFFSET),
+        RegisterField::new("interrupt_2_activity", 1 << ACT_BIT_OFFSET),
+        RegisterField::new("interrupt_2_boot", 1 << BOOT_BIT_OFFSET),
+        RegisterField::new("interrupt_2_ia2", 1 << IA_2_BIT_OFFSET),
+        RegisterField::new("interrupt_2_ia1", 1 << IA_1_BIT_OFFSET),
+        RegisterField::new("interrupt_2_click", 1 << CLICK_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CtrlReg6Value, InterruptPolarity, OnOff};
+
+    #[test]
+    fn conversion_from_raw_value_works() {
+        let ctrl_reg_6 = CtrlReg6Value::try_from(0b1010_1010).unwrap();
+        assert_eq!(ctrl_reg_6.interrupt_2_click(), OnOff::Enabled);
+        assert_eq!(ctrl_reg_6.interrupt_2_ia1(), OnOff::Disabled);
+        assert_eq!(ctrl_reg_6.interrupt_2_ia2(), OnOff::Enabled);
+        assert_eq!(ctrl_reg_6.interrupt_2_boot(), OnOff::Disabled);
+        assert_eq!(ctrl_reg_6.interrupt_2_activity(), OnOff::Enabled);
+        assert_eq!(
+            ctrl_reg_6.interrupt_polarity(),
+            InterruptPolarity::ActiveLow
+        );
+        assert_eq!(ctrl_reg_6.get_raw_value(), 0b1010_1010);
+    }
+
+    #[test]
+    fn conversion_from_raw_value_fails_if_fixed_bits_are_set() {
+        assert!(CtrlReg6Value::try_from(0b0000_0001).is_err());
+        assert!(CtrlReg6Value::try_from(0b0000_0100).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::enabled_enum::OnOff;
+use crate::fifo_ctrl_reg_value::{
+    FifoCtrlRegValue, FifoMode, TriggerSelection,
+};
+use crate::fifo_src_reg_value::FIFO_DEPTH;
+use crate::int_cfg::IntCfg;
+use crate::int_duration_value::IntDuration;
+use crate::int_src::IntSrc;
+use crate::int_ths_value::IntThs;
+use crate::register::Int2;
+use crate::{Error, Lis3dh};
+use hal::blocking::spi::{Transfer, Write};
+use hal::digital::v2::OutputPin;
+use micromath::vector::I32x3;
+
+/// FIFO content captured around a trigger, samples in mg.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CapturedEvent {
+    samples: [I32x3; FIFO_DEPTH as usize],
+    len: usize,
+    trigger_index: usize,
+    corrupted: u32,
+}
+
+impl CapturedEvent {
+    /// Samples before, at and after the trigger, starting with the oldest.
+    pub fn samples(&self) -> &[I32x3] {
+        &self.samples[..self.len]
+    }
+    /// Index of the sample firing the trigger in [`CapturedEvent::samples`].
+    pub fn trigger_index(&self) -> usize {
+        self.trigger_index
+    }
+    pub fn pre_trigger_samples(&self) -> &[I32x3] {
+        &self.samples[..self.trigger_index]
+    }
+    pub fn post_trigger_samples(&self) -> &[I32x3] {
+        &self.samples[(self.trigger_index + 1).min(self.len)..self.len]
+    }
+    /// Bit `n` is set if sample `n` failed the plausibility check, see
+    /// [`crate::Error::CorruptedFifoSamples`]. Corrupted samples are never
+    /// taken as trigger.
+    pub fn corrupted_samples(&self) -> u32 {
+        self.corrupted
+    }
+}
+
+/// Captures the samples around an interrupt with the FIFO in
+/// Stream-to-FIFO mode.
+///
+/// Configure the interrupt generator selected as trigger, then
+/// [`EventCapture::arm`] the capture and [`EventCapture::poll`] it. The
+/// FIFO keeps streaming until the interrupt fires and then fills up to
+/// [`FIFO_DEPTH`] samples, so the number of samples after the trigger
+/// depends on how full the FIFO was at that moment. The trigger is located
+/// by evaluating the interrupt generator configuration on the samples; if
+/// no sample matches, e.g. with 6D detection, the newest sample is
+/// reported as trigger.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EventCapture {
+    trigger_selection: TriggerSelection,
+    cfg: IntCfg,
+    threshold_mg: u32,
+    duration: u8,
+    armed: bool,
+    triggered: bool,
+}
+
+impl EventCapture {
+    pub fn new(trigger_selection: TriggerSelection) -> Self {
+        EventCapture {
+            trigger_selection,
+            cfg: IntCfg::default(),
+            threshold_mg: 0,
+            duration: 0,
+            armed: false,
+            triggered: false,
+        }
+    }
+    pub fn trigger_selection(&self) -> TriggerSelection {
+        self.trigger_selection
+    }
+    /// True from [`EventCapture::arm`] until an event was captured.
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+    /// True once the trigger interrupt fired.
+    pub fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+    /// Enables and clears the FIFO, latches the trigger interrupt and
+    /// switches to Stream-to-FIFO mode.
+    pub fn arm<CS, SPI, CsE, SpiE>(
+        &mut self,
+        lis3dh: &mut Lis3dh,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<(), Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let full_scale = lis3dh.get_ctrl_reg_4_value(cs, spi)?.fs();
+        let (cfg, ths, duration) = match self.trigger_selection {
+            TriggerSelection::Int1 => (
+                lis3dh.read::<IntCfg, _, _, _, _>(cs, spi)?,
+                lis3dh.read::<IntThs, _, _, _, _>(cs, spi)?,
+                lis3dh.read::<IntDuration, _, _, _, _>(cs, spi)?,
+            ),
+            TriggerSelection::Int2 => (
+                lis3dh.read::<Int2<IntCfg>, _, _, _, _>(cs, spi)?.0,
+                lis3dh.read::<Int2<IntThs>, _, _, _, _>(cs, spi)?.0,
+                lis3dh.read::<Int2<IntDuration>, _, _, _, _>(cs, spi)?.0,
+            ),
+        };
+        // The samples are evaluated in board axes.
+        self.cfg = lis3dh.axis_map().int_cfg_to_board(&cfg);
+        self.threshold_mg = ths.threshold_mg(full_scale);
+        self.duration = duration.duration();
+
+        let mut ctrl_reg5 = lis3dh.ctrl_reg5_setting();
+        ctrl_reg5.set_fifo(OnOff::Enabled);
+        match self.trigger_selection {
+            TriggerSelection::Int1 => {
+                ctrl_reg5.set_latch_int_on_int_1_src(OnOff::Enabled)
+            }
+            TriggerSelection::Int2 => {
+                ctrl_reg5.set_latch_int_on_int_2_src(OnOff::Enabled)
+            }
+        }
+        lis3dh.write(cs, spi, ctrl_reg5)?;
+        // Bypass mode empties the FIFO.
+        let mut fifo_ctrl = FifoCtrlRegValue::default();
+        lis3dh.write(cs, spi, fifo_ctrl)?;
+        self.read_source(lis3dh, cs, spi)?;
+        fifo_ctrl.set_fifo_mode(FifoMode::StreamToFifo);
+        fifo_ctrl.set_trigger_selection(self.trigger_selection);
+        lis3dh.write(cs, spi, fifo_ctrl)?;
+        self.armed = true;
+        self.triggered = false;
+        Ok(())
+    }
+    /// Returns the captured event once the trigger fired and the FIFO is
+    /// full. The capture has to be armed again for the next event.
+    pub fn poll<CS, SPI, CsE, SpiE>(
+        &mut self,
+        lis3dh: &mut Lis3dh,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<Option<CapturedEvent>, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        if !self.armed {
+            return Ok(None);
+        }
+        if !self.triggered {
+            self.triggered =
+                self.read_source(lis3dh, cs, spi)?.is_interrupt_active();
+            if !self.triggered {
+                return Ok(None);
+            }
+        }
+        if lis3dh.get_fifo_src_value(cs, spi)?.unread_samples() < FIFO_DEPTH {
+            return Ok(None);
+        }
+        let mut samples = [I32x3 { x: 0, y: 0, z: 0 }; FIFO_DEPTH as usize];
+        let (len, corrupted) = crate::split_corrupted(
+            lis3dh.get_accel_norm_from_fifo(cs, spi, &mut samples),
+        )?;
+        self.armed = false;
+        Ok(Some(CapturedEvent {
+            samples,
+            len,
+            trigger_index: self.find_trigger(&samples[..len], corrupted),
+            corrupted,
+        }))
+    }
+    fn read_source<CS, SPI, CsE, SpiE>(
+        &self,
+        lis3dh: &mut Lis3dh,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<IntSrc, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        match self.trigger_selection {
+            TriggerSelection::Int1 => lis3dh.read(cs, spi),
+            TriggerSelection::Int2 => lis3dh
+                .read::<Int2<IntSrc>, _, _, _, _>(cs, spi)
+                .map(|src| src.0),
+        }
+    }
+    /// First sample completing a run of active samples longer than the
+    /// configured duration.
+    fn find_trigger(&self, samples: &[I32x3], corrupted: u32) -> usize {
+        let mut run = 0;
+        for (index, sample) in samples.iter().enumerate() {
+            if corrupted & 1 << index != 0 {
+                run = 0;
+            } else if IntSrc::evaluate(&self.cfg, self.threshold_mg, *sample)
+                .is_interrupt_active()
+            {
+                run += 1;
+                if run > self.duration as usize {
+                    return index;
+                }
+            } else {
+                run = 0;
+            }
+        }
+        samples.len().saturating_sub(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventCapture;
+    use crate::ctrl_reg_1_value::ODR;
+    use crate::enabled_enum::OnOff;
+    use crate::fifo_ctrl_reg_value::TriggerSelection;
+    use crate::int_cfg::IntCfg;
+    use crate::int_ths_value::IntThs;
+    use crate::register::Int2;
+    use crate::simulator::Simulator;
+    use crate::Lis3dh;
+    use micromath::vector::I32x3;
+
+    const AT_REST: I32x3 = I32x3 {
+        x: 0,
+        y: 0,
+        z: 1008,
+    };
+
+    fn high_z_interrupt() -> (IntCfg, IntThs) {
+        let mut cfg = IntCfg::default();
+        cfg.set_zhie(OnOff::Enabled);
+        let mut ths = IntThs::default();
+        ths.set_threshold(100).unwrap();
+        (cfg, ths)
+    }
+
+    #[test]
+    fn samples_around_the_trigger_are_captured_in_order() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(ODR::Hz100);
+        let (cfg, ths) = high_z_interrupt();
+        lis3dh.set_int1_cfg_setting(cfg).unwrap();
+        lis3dh.set_int1_ths_setting(ths);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut capture = EventCapture::new(TriggerSelection::Int1);
+        capture.arm(&mut lis3dh, &mut cs, &mut spi).unwrap();
+        for _ in 0..10 {
+            simulator.inject_sample(AT_REST);
+        }
+        simulator.inject_sample(I32x3 {
+            x: 0,
+            y: 0,
+            z: 2000,
+        });
+        for _ in 0..10 {
+            simulator.inject_sample(AT_REST);
+        }
+        assert_eq!(capture.poll(&mut lis3dh, &mut cs, &mut spi).unwrap(), None);
+        assert!(capture.is_triggered());
+        for z in 0..20 {
+            simulator.inject_sample(I32x3 {
+                x: 0,
+                y: 0,
+                z: 16 * z,
+            });
+        }
+        let event = capture
+            .poll(&mut lis3dh, &mut cs, &mut spi)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.samples().len(), 32);
+        assert_eq!(event.trigger_index(), 10);
+        assert_eq!(event.samples()[10].z, 2000);
+        assert_eq!(event.pre_trigger_samples().len(), 10);
+        assert_eq!(event.post_trigger_samples().len(), 21);
+        assert_eq!(event.samples()[31].z, 16 * 10);
+        assert!(!capture.is_armed());
+    }
+
+    #[test]
+    fn stream_keeps_the_history_until_the_trigger() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(ODR::Hz100);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let (cfg, ths) = high_z_interrupt();
+        lis3dh.write(&mut cs, &mut spi, Int2(cfg)).unwrap();
+        lis3dh.write(&mut cs, &mut spi, Int2(ths)).unwrap();
+        let mut capture = EventCapture::new(TriggerSelection::Int2);
+        capture.arm(&mut lis3dh, &mut cs, &mut spi).unwrap();
+        for z in 0..50 {
+            simulator.inject_sample(I32x3 {
+                x: 0,
+                y: 0,
+                z: 16 * z,
+            });
+        }
+        assert_eq!(capture.poll(&mut lis3dh, &mut cs, &mut spi).unwrap(), None);
+        assert!(!capture.is_triggered());
+        simulator.inject_sample(I32x3 {
+            x: 0,
+            y: 0,
+            z: -2000,
+        });
+        simulator.inject_sample(AT_REST);
+        let event = capture
+            .poll(&mut lis3dh, &mut cs, &mut spi)
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.trigger_index(), 31);
+        assert_eq!(event.samples()[0].z, 16 * 19);
+        assert!(event.post_trigger_samples().is_empty());
+    }
+}
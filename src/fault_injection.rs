@@ -0,0 +1,401 @@
+use core::cell::RefCell;
+use hal::blocking::spi::{Transfer, Write};
+use hal::digital::v2::OutputPin;
+
+/// Maximum number of faults that can be scheduled at the same time.
+pub const MAX_SCHEDULED_FAULTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Selecting the chip fails.
+    ChipSelectError,
+    /// Every SPI operation of the transaction fails.
+    SpiError,
+    /// The given bits of the n-th byte received in the transaction are
+    /// inverted.
+    BitFlip { byte: usize, mask: u8 },
+    /// Every byte received in the transaction reads as the given value,
+    /// e.g. 0x00 or 0xFF for a MISO line stuck low or high.
+    StuckMiso(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultyBusError<E> {
+    /// Error caused by a scheduled fault.
+    Injected,
+    /// Error of the wrapped chip select pin or bus.
+    Bus(E),
+}
+
+struct State {
+    faults: [Option<(usize, Fault)>; MAX_SCHEDULED_FAULTS],
+    transactions: usize,
+    active_fault: Option<Fault>,
+    received_bytes: usize,
+    selected: bool,
+}
+
+/// Injects faults into chosen transactions between [`crate::Lis3dh`] and a
+/// real or simulated bus.
+///
+/// A transaction starts when chip select is pulled low and is numbered from
+/// zero. Wrap the chip select pin with [`FaultInjector::chip_select`] and
+/// the bus with [`FaultInjector::spi`], then schedule faults by
+/// transaction number.
+///
+/// Injected bus errors are always returned by the driver. Corrupted data is
+/// only refused where it yields a value the sensor can not produce, e.g. a
+/// reserved bit pattern in a register or set unused sample bits. The LIS3DH
+/// has no checksum, so flipped sample data bits and a MISO line stuck low
+/// during a sample read go unnoticed.
+pub struct FaultInjector {
+    state: RefCell<State>,
+}
+
+impl Default for FaultInjector {
+    fn default() -> Self {
+        FaultInjector::new()
+    }
+}
+
+impl FaultInjector {
+    pub fn new() -> Self {
+        FaultInjector {
+            state: RefCell::new(State {
+                faults: [None; MAX_SCHEDULED_FAULTS],
+                transactions: 0,
+                active_fault: None,
+                received_bytes: 0,
+                selected: false,
+            }),
+        }
+    }
+    pub fn chip_select<P: OutputPin>(&self, pin: P) -> FaultyChipSelect<'_, P> {
+        FaultyChipSelect {
+            injector: self,
+            pin,
+        }
+    }
+    pub fn spi<S>(&self, spi: S) -> FaultySpi<'_, S> {
+        FaultySpi {
+            injector: self,
+            spi,
+        }
+    }
+    /// Schedules `fault` for the transaction with the given number. Returns
+    /// the fault back if [`MAX_SCHEDULED_FAULTS`] are already scheduled.
+    pub fn schedule(
+        &self,
+        transaction: usize,
+        fault: Fault,
+    ) -> Result<(), Fault> {
+        let mut state = self.state.borrow_mut();
+        let slot = state
+            .faults
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(fault)?;
+        *slot = Some((transaction, fault));
+        Ok(())
+    }
+    /// Removes all scheduled faults and restarts transaction numbering.
+    pub fn reset(&self) {
+        let mut state = self.state.borrow_mut();
+        state.faults = [None; MAX_SCHEDULED_FAULTS];
+        state.transactions = 0;
+        state.active_fault = None;
+    }
+    /// Number of transactions started so far.
+    pub fn transactions(&self) -> usize {
+        self.state.borrow().transactions
+    }
+    pub fn is_chip_selected(&self) -> bool {
+        self.state.borrow().selected
+    }
+    fn start_transaction(&self) -> Option<Fault> {
+        let mut state = self.state.borrow_mut();
+        let transaction = state.transactions;
+        state.transactions += 1;
+        state.received_bytes = 0;
+        state.active_fault =
+            state.faults.iter_mut().find_map(|slot| match *slot {
+                Some((scheduled, fault)) if scheduled == transaction => {
+                    *slot = None;
+                    Some(fault)
+                }
+                _ => None,
+            });
+        state.active_fault
+    }
+    fn corrupt_received(&self, words: &mut [u8]) {
+        let mut state = self.state.borrow_mut();
+        let first = state.received_bytes;
+        state.received_bytes += words.len();
+        match state.active_fault {
+            Some(Fault::StuckMiso(value)) => words.fill(value),
+            Some(Fault::BitFlip { byte, mask })
+                if (first..first + words.len()).contains(&byte) =>
+            {
+                words[byte - first] ^= mask
+            }
+            _ => (),
+        }
+    }
+    fn fails_spi(&self) -> bool {
+        self.state.borrow().active_fault == Some(Fault::SpiError)
+    }
+}
+
+pub struct FaultyChipSelect<'a, P> {
+    injector: &'a FaultInjector,
+    pin: P,
+}
+
+impl<P> FaultyChipSelect<'_, P> {
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+impl<P: OutputPin> OutputPin for FaultyChipSelect<'_, P> {
+    type Error = FaultyBusError<P::Error>;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        if self.injector.start_transaction() == Some(Fault::ChipSelectError) {
+            return Err(FaultyBusError::Injected);
+        }
+        self.pin.set_low().map_err(FaultyBusError::Bus)?;
+        self.injector.state.borrow_mut().selected = true;
+        Ok(())
+    }
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_high().map_err(FaultyBusError::Bus)?;
+        self.injector.state.borrow_mut().selected = false;
+        Ok(())
+    }
+}
+
+pub struct FaultySpi<'a, S> {
+    injector: &'a FaultInjector,
+    spi: S,
+}
+
+impl<S> FaultySpi<'_, S> {
+    pub fn release(self) -> S {
+        self.spi
+    }
+}
+
+impl<S: Write<u8>> Write<u8> for FaultySpi<'_, S> {
+    type Error = FaultyBusError<S::Error>;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        if self.injector.fails_spi() {
+            return Err(FaultyBusError::Injected);
+        }
+        self.spi.write(words).map_err(FaultyBusError::Bus)
+    }
+}
+
+impl<S: Transfer<u8>> Transfer<u8> for FaultySpi<'_, S> {
+    type Error = FaultyBusError<S::Error>;
+
+    fn transfer<'w>(
+        &mut self,
+        words: &'w mut [u8],
+    ) -> Result<&'w [u8], Self::Error> {
+        if self.injector.fails_spi() {
+            return Err(FaultyBusError::Injected);
+        }
+        self.spi.transfer(words).map_err(FaultyBusError::Bus)?;
+        self.injector.corrupt_received(words);
+        Ok(words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fault, FaultInjector, FaultyBusError};
+    use crate::ctrl_reg_1_value::ODR;
+    use crate::ctrl_reg_5_value::CtrlReg5Value;
+    use crate::enabled_enum::OnOff;
+    use crate::fifo_ctrl_reg_value::{FifoCtrlRegValue, FifoMode};
+    use crate::sample_queue::SampleQueue;
+    use crate::simulator::Simulator;
+    use crate::{Error, Lis3dh, RegisterAddresses};
+    use micromath::vector::I32x3;
+
+    #[test]
+    fn chip_select_errors_stop_writing_settings() {
+        let simulator = Simulator::new();
+        let injector = FaultInjector::new();
+        let mut cs = injector.chip_select(simulator.chip_select());
+        let mut spi = injector.spi(simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(crate::ctrl_reg_1_value::ODR::Hz100);
+        injector.schedule(0, Fault::ChipSelectError).unwrap();
+        assert!(matches!(
+            lis3dh.write_all_settings(&mut cs, &mut spi),
+            Err(Error::ChipSelectError(FaultyBusError::Injected))
+        ));
+        assert!(lis3dh
+            .dirty_registers()
+            .contains(RegisterAddresses::CtrlReg1));
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+        assert!(lis3dh.dirty_registers().is_empty());
+    }
+
+    #[test]
+    fn spi_errors_are_propagated_and_release_chip_select() {
+        let simulator = Simulator::new();
+        let injector = FaultInjector::new();
+        let mut cs = injector.chip_select(simulator.chip_select());
+        let mut spi = injector.spi(simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        injector.schedule(2, Fault::SpiError).unwrap();
+        assert!(matches!(
+            lis3dh.get_accel_norm(&mut cs, &mut spi),
+            Err(Error::SpiError(FaultyBusError::Injected))
+        ));
+        assert!(!injector.is_chip_selected());
+        assert!(lis3dh.get_accel_norm(&mut cs, &mut spi).is_ok());
+    }
+
+    #[test]
+    fn corrupted_settings_are_not_reported_as_written() {
+        let simulator = Simulator::new();
+        let injector = FaultInjector::new();
+        let mut cs = injector.chip_select(simulator.chip_select());
+        let mut spi = injector.spi(simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+        injector.reset();
+        let ctrl_reg1_byte = RegisterAddresses::CtrlReg1 as usize
+            - RegisterAddresses::CtrlReg0 as usize;
+        injector
+            .schedule(
+                0,
+                Fault::BitFlip {
+                    byte: ctrl_reg1_byte,
+                    mask: 0x10,
+                },
+            )
+            .unwrap();
+        assert!(!lis3dh
+            .check_if_settings_are_written_correctly(&mut cs, &mut spi)
+            .unwrap());
+        assert!(lis3dh
+            .check_if_settings_are_written_correctly(&mut cs, &mut spi)
+            .unwrap());
+    }
+
+    #[test]
+    fn stuck_miso_lines_are_detected() {
+        let simulator = Simulator::new();
+        let injector = FaultInjector::new();
+        let mut cs = injector.chip_select(simulator.chip_select());
+        let mut spi = injector.spi(simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        injector.schedule(0, Fault::StuckMiso(0xff)).unwrap();
+        assert!(matches!(
+            lis3dh.get_accel_norm(&mut cs, &mut spi),
+            Err(Error::InvalidRegisterValue(_))
+        ));
+        injector.reset();
+        injector.schedule(2, Fault::StuckMiso(0xff)).unwrap();
+        assert!(matches!(
+            lis3dh.get_accel_norm(&mut cs, &mut spi),
+            Err(Error::InvalidRegisterValue(_))
+        ));
+        injector.reset();
+        injector.schedule(0, Fault::StuckMiso(0x00)).unwrap();
+        assert!(matches!(
+            lis3dh.get_ctrl_reg_0_value(&mut cs, &mut spi),
+            Err(Error::InvalidRegisterValue(_))
+        ));
+    }
+
+    #[test]
+    fn corrupted_unused_sample_bits_are_detected() {
+        let simulator = Simulator::new();
+        let injector = FaultInjector::new();
+        let mut cs = injector.chip_select(simulator.chip_select());
+        let mut spi = injector.spi(simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(crate::ctrl_reg_1_value::ODR::Hz100);
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+        simulator.inject_sample(I32x3 {
+            x: 0,
+            y: 0,
+            z: 1000,
+        });
+        injector.reset();
+        injector
+            .schedule(
+                2,
+                Fault::BitFlip {
+                    byte: 4,
+                    mask: 0x01,
+                },
+            )
+            .unwrap();
+        assert!(matches!(
+            lis3dh.get_accel_norm(&mut cs, &mut spi),
+            Err(Error::InvalidRegisterValue(_))
+        ));
+    }
+
+    #[test]
+    fn corrupted_fifo_samples_do_not_discard_the_others() {
+        let simulator = Simulator::new();
+        let injector = FaultInjector::new();
+        let mut cs = injector.chip_select(simulator.chip_select());
+        let mut spi = injector.spi(simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(ODR::Hz100);
+        let mut ctrl_reg5 = CtrlReg5Value::default();
+        ctrl_reg5.set_fifo(OnOff::Enabled);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut fifo_ctrl = FifoCtrlRegValue::default();
+        fifo_ctrl.set_fifo_mode(FifoMode::Stream);
+        lis3dh.write(&mut cs, &mut spi, fifo_ctrl).unwrap();
+        let sample = |z| I32x3 { x: 0, y: 0, z };
+        for z in 1..=3 {
+            simulator.inject_sample(sample(16 * z));
+        }
+        // CTRL_REG1, CTRL_REG4 and FIFO_SRC_REG are read before the samples.
+        let second_sample = 4;
+        let unused_bit = Fault::BitFlip {
+            byte: 0,
+            mask: 0x01,
+        };
+        injector.reset();
+        injector.schedule(second_sample, unused_bit).unwrap();
+        let mut samples = [sample(0); 4];
+        assert!(matches!(
+            lis3dh.get_accel_norm_from_fifo(&mut cs, &mut spi, &mut samples),
+            Err(Error::CorruptedFifoSamples {
+                read: 3,
+                corrupted: 0b010
+            })
+        ));
+        assert_eq!(simulator.fifo_len(), 0);
+        assert_eq!((samples[0], samples[2]), (sample(16), sample(48)));
+
+        let mut queue = SampleQueue::<8>::new();
+        let (mut producer, mut consumer) = queue.split();
+        for z in 4..=6 {
+            simulator.inject_sample(sample(16 * z));
+        }
+        injector.reset();
+        injector.schedule(second_sample, unused_bit).unwrap();
+        assert!(matches!(
+            producer.on_watermark(&mut lis3dh, &mut cs, &mut spi),
+            Err(Error::CorruptedFifoSamples { .. })
+        ));
+        let batch = consumer.read_batch(&mut samples);
+        assert_eq!((batch.samples(), batch.dropped()), (2, 0));
+        assert_eq!(&samples[..2], &[sample(64), sample(96)]);
+    }
+}
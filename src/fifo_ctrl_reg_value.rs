@@ -0,0 +1,135 @@
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+
+const FTH_MASK: u8 = 0b1_1111;
+const TR_BIT_OFFSET: u8 = 5;
+const FM_BIT_OFFSET: u8 = 6;
+
+/// FIFO mode, only effective while the FIFO is enabled in CTRL_REG5.
+#[derive(FromPrimitive)]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum FifoMode {
+    /// The FIFO is not used and cleared.
+    #[default]
+    Bypass,
+    /// Samples are collected until the FIFO is full.
+    Fifo,
+    /// The oldest sample is discarded when the FIFO is full.
+    Stream,
+    /// Stream mode until the trigger interrupt fires, then FIFO mode.
+    StreamToFifo,
+}
+
+/// Interrupt generator triggering the switch to FIFO mode in
+/// [`FifoMode::StreamToFifo`].
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum TriggerSelection {
+    #[default]
+    Int1,
+    Int2,
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct FifoCtrlRegValue {
+    fifo_mode: FifoMode,
+    trigger_selection: TriggerSelection,
+    threshold: u8,
+}
+
+impl FifoCtrlRegValue {
+    pub fn set_fifo_mode(&mut self, fifo_mode: FifoMode) {
+        self.fifo_mode = fifo_mode;
+    }
+    pub fn set_trigger_selection(
+        &mut self,
+        trigger_selection: TriggerSelection,
+    ) {
+        self.trigger_selection = trigger_selection;
+    }
+    /// Sets the watermark level. The watermark flag in FIFO_SRC_REG is set
+    /// while the FIFO holds more samples than this.
+    pub fn set_threshold(&mut self, threshold: u8) -> Result<(), &'static str> {
+        if threshold & !FTH_MASK != 0 {
+            return Err("FIFO threshold can only contain 5 bit");
+        }
+        self.threshold = threshold;
+        Ok(())
+    }
+    pub fn fifo_mode(&self) -> FifoMode {
+        self.fifo_mode
+    }
+    pub fn trigger_selection(&self) -> TriggerSelection {
+        self.trigger_selection
+    }
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.fifo_mode as u8) << FM_BIT_OFFSET
+            | (self.trigger_selection as u8) << TR_BIT_OFFSET
+            | self.threshold
+    }
+}
+
+impl TryFrom<u8> for FifoCtrlRegValue {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(FifoCtrlRegValue {
+            fifo_mode: FifoMode::from_u8(value >> FM_BIT_OFFSET)
+                .ok_or("FIFO mode has only 2 bit")?,
+            trigger_selection: if super::check_if_bit_is_set(
+                value,
+                TR_BIT_OFFSET,
+            ) {
+                TriggerSelection::Int2
+            } else {
+                TriggerSelection::Int1
+            },
+            threshold: value & FTH_MASK,
+        })
+    }
+}
+
+impl Register for FifoCtrlRegValue {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::FifoCtrlReg;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("fth", FTH_MASK),
+        RegisterField::new("tr", 1 << TR_BIT_OFFSET),
+        RegisterField::new("fm", 0b11 << FM_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FifoMode, TriggerSelection};
+
+    #[test]
+    fn conversion_from_raw_value_works() {
+        let fifo_ctrl = super::FifoCtrlRegValue::try_from(0b1110_1010).unwrap();
+        assert_eq!(fifo_ctrl.fifo_mode(), FifoMode::StreamToFifo);
+        assert_eq!(fifo_ctrl.trigger_selection(), TriggerSelection::Int2);
+        assert_eq!(fifo_ctrl.threshold(), 10);
+        assert_eq!(fifo_ctrl.get_raw_value(), 0b1110_1010);
+    }
+
+    #[test]
+    fn setting_threshold_fails_if_it_exceeds_5_bit() {
+        let mut fifo_ctrl = super::FifoCtrlRegValue::default();
+        assert!(fifo_ctrl.set_threshold(32).is_err());
+        fifo_ctrl.set_threshold(31).unwrap();
+        fifo_ctrl.set_fifo_mode(FifoMode::Stream);
+        assert_eq!(fifo_ctrl.get_raw_value(), 0b1001_1111);
+    }
+}
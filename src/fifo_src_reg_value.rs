@@ -0,0 +1,112 @@
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+
+/// Number of samples the FIFO can hold.
+pub const FIFO_DEPTH: u8 = 32;
+
+const EMPTY_BIT_OFFSET: u8 = 5;
+const OVRN_FIFO_BIT_OFFSET: u8 = 6;
+const WTM_BIT_OFFSET: u8 = 7;
+const FSS_MASK: u8 = 0b1_1111;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FifoSrcRegValue {
+    watermark_reached: bool,
+    overrun: bool,
+    empty: bool,
+    stored_samples: u8,
+}
+
+impl Default for FifoSrcRegValue {
+    fn default() -> Self {
+        FifoSrcRegValue {
+            watermark_reached: false,
+            overrun: false,
+            empty: true,
+            stored_samples: 0,
+        }
+    }
+}
+
+impl FifoSrcRegValue {
+    pub fn is_watermark_reached(&self) -> bool {
+        self.watermark_reached
+    }
+    pub fn is_overrun(&self) -> bool {
+        self.overrun
+    }
+    pub fn is_empty(&self) -> bool {
+        self.empty
+    }
+    /// Content of the FSS field. It wraps to 0 when all 32 slots are filled.
+    pub fn stored_samples(&self) -> u8 {
+        self.stored_samples
+    }
+    /// Number of samples that can be read from the FIFO.
+    pub fn unread_samples(&self) -> u8 {
+        if self.overrun {
+            FIFO_DEPTH
+        } else {
+            self.stored_samples
+        }
+    }
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.watermark_reached as u8) << WTM_BIT_OFFSET
+            | (self.overrun as u8) << OVRN_FIFO_BIT_OFFSET
+            | (self.empty as u8) << EMPTY_BIT_OFFSET
+            | self.stored_samples & FSS_MASK
+    }
+}
+
+impl TryFrom<u8> for FifoSrcRegValue {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(FifoSrcRegValue {
+            watermark_reached: super::check_if_bit_is_set(
+                value,
+                WTM_BIT_OFFSET,
+            ),
+            overrun: super::check_if_bit_is_set(value, OVRN_FIFO_BIT_OFFSET),
+            empty: super::check_if_bit_is_set(value, EMPTY_BIT_OFFSET),
+            stored_samples: value & FSS_MASK,
+        })
+    }
+}
+
+impl Register for FifoSrcRegValue {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::FifSrcReg;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadOnly;
+    const RESET_VALUE: u8 = 1 << EMPTY_BIT_OFFSET;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("fss", FSS_MASK),
+        RegisterField::new("empty", 1 << EMPTY_BIT_OFFSET),
+        RegisterField::new("ovrn_fifo", 1 << OVRN_FIFO_BIT_OFFSET),
+        RegisterField::new("wtm", 1 << WTM_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn conversion_from_raw_value_works() {
+        let fifo_src = super::FifoSrcRegValue::try_from(0b1000_1010).unwrap();
+        assert!(fifo_src.is_watermark_reached());
+        assert!(!fifo_src.is_overrun());
+        assert!(!fifo_src.is_empty());
+        assert_eq!(fifo_src.unread_samples(), 10);
+        assert_eq!(fifo_src.get_raw_value(), 0b1000_1010);
+    }
+
+    #[test]
+    fn full_fifo_reports_all_samples_as_unread() {
+        let fifo_src = super::FifoSrcRegValue::try_from(0b1100_0000).unwrap();
+        assert_eq!(fifo_src.stored_samples(), 0);
+        assert_eq!(fifo_src.unread_samples(), super::FIFO_DEPTH);
+    }
+}
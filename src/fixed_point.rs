@@ -0,0 +1,344 @@
+use crate::axis_map::AxisMap;
+use crate::calibration::Calibration;
+pub use crate::math::isqrt;
+use micromath::vector::I32x3;
+use micromath::F32Ext;
+
+/// Acceleration at rest in mg.
+const GRAVITY_MG: u32 = 1000;
+/// Default deviation of |a| from 1 g up to which a tilt is considered valid.
+pub const DEFAULT_TOLERANCE_MG: u32 = 100;
+
+/// `atan(2^-i)` in millidegrees.
+const CORDIC_ANGLES: [i64; 17] = [
+    45000, 26565, 14036, 7125, 3576, 1790, 895, 448, 224, 112, 56, 28, 14, 7,
+    3, 2, 1,
+];
+/// Inputs are scaled up by this many bits before the CORDIC iterations.
+const CORDIC_SCALE_BITS: u32 = 20;
+
+/// Magnitude of a sample in mg, rounded down. It is less than 1 mg below
+/// the exact value.
+pub fn magnitude(sample: I32x3) -> u32 {
+    let sum = sum_of_squares(sample);
+    if sum > u64::MAX >> (2 * FRACTION_BITS) {
+        return isqrt(sum) as u32;
+    }
+    (isqrt(sum << (2 * FRACTION_BITS)) >> FRACTION_BITS) as u32
+}
+
+/// Fraction bits of the magnitudes used as angle arguments, so flooring the
+/// square root does not distort angles of small vectors.
+const FRACTION_BITS: u32 = 8;
+/// Bits of the largest component of the samples the angles are calculated
+/// from, so the scaled values fit into `i32`.
+const ANGLE_INPUT_BITS: u32 = 22;
+
+fn sum_of_squares(sample: I32x3) -> u64 {
+    let square = |value: i32| (value as i64 * value as i64) as u64;
+    square(sample.x) + square(sample.y) + square(sample.z)
+}
+
+/// Magnitude with [`FRACTION_BITS`] fraction bits of a sample with
+/// components of at most [`ANGLE_INPUT_BITS`] bits.
+fn scaled_magnitude(sample: I32x3) -> u64 {
+    isqrt(sum_of_squares(sample) << (2 * FRACTION_BITS))
+}
+
+/// Shifts all components of a sample right until they have at most
+/// [`ANGLE_INPUT_BITS`] bits, keeping its direction.
+fn reduce(sample: I32x3) -> I32x3 {
+    let largest = sample
+        .x
+        .unsigned_abs()
+        .max(sample.y.unsigned_abs())
+        .max(sample.z.unsigned_abs());
+    let shift =
+        (u32::BITS - largest.leading_zeros()).saturating_sub(ANGLE_INPUT_BITS);
+    I32x3 {
+        x: sample.x >> shift,
+        y: sample.y >> shift,
+        z: sample.z >> shift,
+    }
+}
+
+/// Four-quadrant arctangent in centidegrees, -18000 to 18000, calculated
+/// with CORDIC. It deviates less than 0.02° from the exact angle.
+pub fn atan2(y: i32, x: i32) -> i32 {
+    if x == 0 && y == 0 {
+        return 0;
+    }
+    let (mut x, mut y, mut angle) = if x < 0 {
+        let half_turn = if y < 0 { -180_000 } else { 180_000 };
+        (-(x as i64), -(y as i64), half_turn)
+    } else {
+        (x as i64, y as i64, 0)
+    };
+    x <<= CORDIC_SCALE_BITS;
+    y <<= CORDIC_SCALE_BITS;
+    for (shift, step) in CORDIC_ANGLES.iter().enumerate() {
+        let (dx, dy) = (y >> shift, x >> shift);
+        if y > 0 {
+            x += dx;
+            y -= dy;
+            angle += step;
+        } else {
+            x -= dx;
+            y += dy;
+            angle -= step;
+        }
+    }
+    if angle > 180_000 {
+        angle -= 360_000;
+    } else if angle < -180_000 {
+        angle += 360_000;
+    }
+    let rounding = if angle < 0 { -5 } else { 5 };
+    ((angle + rounding) / 10) as i32
+}
+
+/// Fraction bits of the coefficients of [`SampleTransform`].
+const TRANSFORM_FRACTION_BITS: u32 = 16;
+
+/// Axis map and calibration combined into `matrix * sample + offset` with
+/// [`TRANSFORM_FRACTION_BITS`] fraction bits, so samples are transformed
+/// with integers only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SampleTransform {
+    matrix: [[i32; 3]; 3],
+    offset: [i32; 3],
+}
+
+impl Default for SampleTransform {
+    fn default() -> Self {
+        SampleTransform::new(&AxisMap::default(), None)
+    }
+}
+
+impl SampleTransform {
+    /// Combines the axis map and the calibration applied after it.
+    /// Right-angle rotations without calibration are converted with
+    /// integers; matrices and calibrations are converted once with floating
+    /// point, [`SampleTransform::apply`] never uses it.
+    pub fn new(axis_map: &AxisMap, calibration: Option<&Calibration>) -> Self {
+        let one = 1 << TRANSFORM_FRACTION_BITS;
+        let (rows, calibration) = match (axis_map.matrix_rows(), calibration) {
+            (None, None) => {
+                let matrix = axis_map.nearest_axes().map(|axis| {
+                    let mut row = [0; 3];
+                    row[axis.index()] =
+                        if axis.is_negative() { -one } else { one };
+                    row
+                });
+                return SampleTransform {
+                    matrix,
+                    offset: [0; 3],
+                };
+            }
+            (rows, calibration) => (
+                rows.unwrap_or_else(|| {
+                    axis_map.nearest_axes().map(|axis| {
+                        let mut row = [0.0; 3];
+                        row[axis.index()] =
+                            if axis.is_negative() { -1.0 } else { 1.0 };
+                        row
+                    })
+                }),
+                calibration.copied().unwrap_or_default(),
+            ),
+        };
+        let scale = calibration.scale();
+        let correction = calibration.cross_axis_correction();
+        // correction * diag(scale), applied after the axis map.
+        let calibrated = correction
+            .map(|row| [0, 1, 2].map(|column| row[column] * scale[column]));
+        let matrix = calibrated.map(|row| {
+            [0, 1, 2].map(|column| {
+                to_fixed((0..3).map(|k| row[k] * rows[k][column]).sum())
+            })
+        });
+        let offset = calibration.offset();
+        let offset = calibrated.map(|row| {
+            to_fixed(-(0..3).map(|k| row[k] * offset[k]).sum::<f32>())
+        });
+        SampleTransform { matrix, offset }
+    }
+    /// Transforms a sample in mg from sensor axes to calibrated board axes.
+    /// The result may differ by 1 mg from the floating point path.
+    pub fn apply(&self, sample: I32x3) -> I32x3 {
+        let values = [sample.x, sample.y, sample.z].map(i64::from);
+        let [x, y, z] = [0, 1, 2].map(|row| {
+            let sum = (0..3)
+                .map(|column| self.matrix[row][column] as i64 * values[column])
+                .sum::<i64>()
+                + self.offset[row] as i64;
+            ((sum + (1 << (TRANSFORM_FRACTION_BITS - 1)))
+                >> TRANSFORM_FRACTION_BITS) as i32
+        });
+        I32x3 { x, y, z }
+    }
+}
+
+fn to_fixed(value: f32) -> i32 {
+    F32Ext::round(value * (1 << TRANSFORM_FRACTION_BITS) as f32) as i32
+}
+
+/// Tilt calculated without floating point, angles in centidegrees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedTilt {
+    roll: i32,
+    pitch: i32,
+    inclination: i32,
+    magnitude: u32,
+    valid: bool,
+}
+
+impl FixedTilt {
+    /// Calculates the tilt of a sample in mg. The angles deviate less than
+    /// 0.03° and the magnitude less than 1 mg from
+    /// [`crate::tilt::TiltCalculator::tilt`].
+    pub fn new(sample: I32x3, tolerance_mg: u32) -> Self {
+        let magnitude = magnitude(sample);
+        let sample = reduce(sample);
+        let horizontal = scaled_magnitude(I32x3 {
+            x: sample.x,
+            y: sample.y,
+            z: 0,
+        });
+        let lateral = scaled_magnitude(I32x3 {
+            x: 0,
+            y: sample.y,
+            z: sample.z,
+        });
+        FixedTilt {
+            roll: atan2(sample.y, sample.z),
+            pitch: atan2(-sample.x << FRACTION_BITS, lateral as i32),
+            inclination: atan2(horizontal as i32, sample.z << FRACTION_BITS),
+            magnitude,
+            valid: magnitude.abs_diff(GRAVITY_MG) <= tolerance_mg,
+        }
+    }
+    /// Rotation around the X axis in centidegrees, -18000 to 18000.
+    pub fn roll(&self) -> i32 {
+        self.roll
+    }
+    /// Rotation around the Y axis in centidegrees, -9000 to 9000.
+    pub fn pitch(&self) -> i32 {
+        self.pitch
+    }
+    /// Angle between the Z axis and the vertical in centidegrees, 0 to
+    /// 18000.
+    pub fn inclination(&self) -> i32 {
+        self.inclination
+    }
+    /// Magnitude of the measured acceleration in mg.
+    pub fn magnitude(&self) -> u32 {
+        self.magnitude
+    }
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tilt::TiltCalculator;
+    use micromath::vector::I32x3;
+    use micromath::F32Ext;
+
+    #[test]
+    fn integer_square_root_rounds_down() {
+        assert_eq!(super::isqrt(0), 0);
+        assert_eq!(super::isqrt(15), 3);
+        assert_eq!(super::isqrt(16), 4);
+        assert_eq!(super::isqrt(u64::MAX), u32::MAX as u64);
+    }
+
+    #[test]
+    fn large_samples_do_not_overflow() {
+        let sample = I32x3 {
+            x: i32::MIN,
+            y: 0,
+            z: i32::MAX,
+        };
+        let tilt = super::FixedTilt::new(sample, 100);
+        assert_eq!((tilt.roll(), tilt.pitch()), (0, 4500));
+        assert_eq!(tilt.inclination(), 4500);
+        assert_eq!(tilt.magnitude(), 3_037_000_499);
+        assert!(!tilt.is_valid());
+    }
+
+    #[test]
+    fn transform_matches_axis_map_and_calibration() {
+        use crate::axis_map::{AxisMap, SignedAxis};
+        use crate::calibration::Calibration;
+        let rotation =
+            AxisMap::rotation(SignedAxis::Y, SignedAxis::MinusX, SignedAxis::Z)
+                .unwrap();
+        let (sin, cos) = F32Ext::sin_cos(0.5_f32);
+        let matrix = AxisMap::matrix([
+            [cos, sin, 0.0],
+            [-sin, cos, 0.0],
+            [0.0, 0.0, 1.0],
+        ])
+        .unwrap();
+        let calibration =
+            Calibration::new([12.0, -30.5, 7.0], [1.02, 0.97, 1.0]);
+        let sample = I32x3 {
+            x: -431,
+            y: 250,
+            z: 1017,
+        };
+        for axis_map in [AxisMap::default(), rotation, matrix] {
+            let transform = super::SampleTransform::new(&axis_map, None);
+            assert_eq!(transform.apply(sample), axis_map.apply(sample));
+            let transform =
+                super::SampleTransform::new(&axis_map, Some(&calibration));
+            let fixed = transform.apply(sample);
+            let float = calibration.apply(axis_map.apply(sample));
+            for (fixed, float) in
+                [(fixed.x, float.x), (fixed.y, float.y), (fixed.z, float.z)]
+            {
+                assert!(fixed.abs_diff(float) <= 1, "{} {}", fixed, float);
+            }
+        }
+    }
+
+    #[test]
+    fn arctangent_covers_all_quadrants() {
+        assert_eq!(super::atan2(1000, 1000), 4500);
+        assert_eq!(super::atan2(1000, -1000), 13500);
+        assert_eq!(super::atan2(-1000, -1000), -13500);
+        assert_eq!(super::atan2(-1000, 0), -9000);
+        assert_eq!(super::atan2(0, -1000), 18000);
+    }
+
+    #[test]
+    fn tilt_matches_the_float_path_within_the_documented_bounds() {
+        let calculator = TiltCalculator::default();
+        for x in (-1600..=1600).step_by(97) {
+            for y in (-1600..=1600).step_by(89) {
+                for z in [-1500, -999, -17, 0, 3, 640, 1000, 1500] {
+                    let sample = I32x3 { x, y, z };
+                    let fixed = super::FixedTilt::new(sample, 100);
+                    let float = calculator.tilt(sample);
+                    let difference = |fixed: i32, float: f32| {
+                        let difference =
+                            F32Ext::abs(fixed as f32 / 100.0 - float);
+                        F32Ext::abs(difference - 360.0).min(difference)
+                    };
+                    assert!(difference(fixed.roll(), float.roll()) < 0.03);
+                    assert!(difference(fixed.pitch(), float.pitch()) < 0.03);
+                    assert!(
+                        difference(fixed.inclination(), float.inclination())
+                            < 0.03
+                    );
+                    let magnitude_error =
+                        float.magnitude() - fixed.magnitude() as f32;
+                    assert!((-0.01..1.0).contains(&magnitude_error));
+                    assert_eq!(fixed.is_valid(), float.is_valid());
+                }
+            }
+        }
+    }
+}
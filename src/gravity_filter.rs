@@ -0,0 +1,263 @@
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+use crate::math::isqrt;
+use micromath::vector::I32x3;
+
+/// Fraction bits of the filter state and coefficient.
+pub(crate) const FRACTION_BITS: u32 = 16;
+/// Length of [`FilteredSample::gravity_direction`].
+pub const DIRECTION_SCALE: i32 = 1000;
+
+/// Sample split into its gravity and linear acceleration parts, in mg.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FilteredSample {
+    gravity: I32x3,
+    linear: I32x3,
+}
+
+impl FilteredSample {
+    pub fn gravity(&self) -> I32x3 {
+        self.gravity
+    }
+    /// Acceleration caused by motion, i.e. the sample minus gravity.
+    pub fn linear(&self) -> I32x3 {
+        self.linear
+    }
+    /// Gravity scaled to a length of [`DIRECTION_SCALE`], `None` while the
+    /// estimated gravity is zero.
+    pub fn gravity_direction(&self) -> Option<I32x3> {
+        let square = |value: i32| (value as i64 * value as i64) as u64;
+        let length = isqrt(
+            square(self.gravity.x)
+                + square(self.gravity.y)
+                + square(self.gravity.z),
+        ) as i64;
+        let scale = |value: i32| {
+            (value as i64 * DIRECTION_SCALE as i64 / length) as i32
+        };
+        (length > 0).then(|| I32x3 {
+            x: scale(self.gravity.x),
+            y: scale(self.gravity.y),
+            z: scale(self.gravity.z),
+        })
+    }
+}
+
+/// Separates gravity from linear acceleration with a first-order low-pass
+/// filter tracking the gravity vector.
+///
+/// Changes of the orientation slower than the time constant are followed
+/// as gravity, faster changes appear as linear acceleration. The filter
+/// uses integer arithmetic only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GravityFilter {
+    gravity: Option<[i64; 3]>,
+    time_constant_ms: u32,
+    sample_rate_millihertz: u32,
+    coefficient: i64,
+}
+
+impl GravityFilter {
+    pub fn new(
+        time_constant_ms: u32,
+        sample_rate_hz: u32,
+    ) -> Result<Self, &'static str> {
+        GravityFilter::new_millihertz(
+            time_constant_ms,
+            sample_rate_hz.saturating_mul(1000),
+        )
+    }
+    /// Like [`GravityFilter::new`] for a fractional sample rate, e.g.
+    /// [`crate::rate_estimator::RateEstimate::millihertz`].
+    pub fn new_millihertz(
+        time_constant_ms: u32,
+        sample_rate_millihertz: u32,
+    ) -> Result<Self, &'static str> {
+        if sample_rate_millihertz == 0 {
+            return Err("Sample rate must not be zero");
+        }
+        Ok(GravityFilter {
+            gravity: None,
+            time_constant_ms,
+            sample_rate_millihertz,
+            coefficient: coefficient(time_constant_ms, sample_rate_millihertz),
+        })
+    }
+    /// Creates a filter for the sample rate configured in CTRL_REG1, e.g.
+    /// `GravityFilter::for_settings(500, &lis3dh.ctrl_reg1_setting())`.
+    pub fn for_settings(
+        time_constant_ms: u32,
+        ctrl_reg1: &CtrlReg1Value,
+    ) -> Result<Self, &'static str> {
+        GravityFilter::new(time_constant_ms, ctrl_reg1.sample_rate_hz()?)
+    }
+    pub fn time_constant_ms(&self) -> u32 {
+        self.time_constant_ms
+    }
+    /// Sample rate rounded to whole Hz.
+    pub fn sample_rate_hz(&self) -> u32 {
+        rounded_hz(self.sample_rate_millihertz)
+    }
+    pub fn sample_rate_millihertz(&self) -> u32 {
+        self.sample_rate_millihertz
+    }
+    /// Adapts the filter to a new sample rate, keeping the gravity
+    /// estimate.
+    pub fn set_sample_rate_hz(
+        &mut self,
+        sample_rate_hz: u32,
+    ) -> Result<(), &'static str> {
+        self.set_sample_rate_millihertz(sample_rate_hz.saturating_mul(1000))
+    }
+    /// Like [`GravityFilter::set_sample_rate_hz`] for a fractional sample
+    /// rate.
+    pub fn set_sample_rate_millihertz(
+        &mut self,
+        sample_rate_millihertz: u32,
+    ) -> Result<(), &'static str> {
+        let gravity = self.gravity;
+        *self = GravityFilter::new_millihertz(
+            self.time_constant_ms,
+            sample_rate_millihertz,
+        )?;
+        self.gravity = gravity;
+        Ok(())
+    }
+    /// Current gravity estimate in mg, `None` before the first sample.
+    pub fn gravity(&self) -> Option<I32x3> {
+        self.gravity.map(to_mg)
+    }
+    /// Forgets the gravity estimate. The next sample is taken as gravity.
+    pub fn reset(&mut self) {
+        self.gravity = None;
+    }
+    /// Feeds a sample in mg, e.g. from [`crate::Lis3dh::get_accel_norm`].
+    pub fn update(&mut self, sample: I32x3) -> FilteredSample {
+        let input = [sample.x, sample.y, sample.z]
+            .map(|value| (value as i64) << FRACTION_BITS);
+        let gravity = match self.gravity {
+            None => input,
+            Some(mut gravity) => {
+                for (estimate, value) in gravity.iter_mut().zip(input) {
+                    *estimate += ((value - *estimate) * self.coefficient)
+                        >> FRACTION_BITS;
+                }
+                gravity
+            }
+        };
+        self.gravity = Some(gravity);
+        let gravity = to_mg(gravity);
+        FilteredSample {
+            gravity,
+            linear: I32x3 {
+                x: sample.x - gravity.x,
+                y: sample.y - gravity.y,
+                z: sample.z - gravity.z,
+            },
+        }
+    }
+}
+
+/// Smoothing factor `dt / (tau + dt)` with `FRACTION_BITS` fraction bits.
+pub(crate) fn coefficient(
+    time_constant_ms: u32,
+    sample_rate_millihertz: u32,
+) -> i64 {
+    let one = 1_i64 << FRACTION_BITS;
+    let denominator =
+        1_000_000 + time_constant_ms as i64 * sample_rate_millihertz as i64;
+    (one * 1_000_000 / denominator).max(1)
+}
+
+/// Sample rate in millihertz rounded to whole Hz.
+pub(crate) fn rounded_hz(sample_rate_millihertz: u32) -> u32 {
+    ((sample_rate_millihertz as u64 + 500) / 1000) as u32
+}
+
+fn to_mg(value: [i64; 3]) -> I32x3 {
+    let round = |value: i64| {
+        ((value + (1 << (FRACTION_BITS - 1))) >> FRACTION_BITS) as i32
+    };
+    I32x3 {
+        x: round(value[0]),
+        y: round(value[1]),
+        z: round(value[2]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctrl_reg_1_value::{CtrlReg1Value, ODR};
+    use micromath::vector::I32x3;
+
+    const AT_REST: I32x3 = I32x3 {
+        x: 0,
+        y: 0,
+        z: 1000,
+    };
+
+    #[test]
+    fn linear_acceleration_is_separated_from_gravity() {
+        let mut filter = super::GravityFilter::new(500, 100).unwrap();
+        assert_eq!(filter.update(AT_REST).linear(), I32x3 { x: 0, y: 0, z: 0 });
+        let pushed = I32x3 {
+            x: 300,
+            y: 0,
+            z: 1000,
+        };
+        let filtered = filter.update(pushed);
+        assert_eq!(
+            filtered.gravity(),
+            I32x3 {
+                x: 6,
+                y: 0,
+                z: 1000
+            }
+        );
+        assert_eq!(filtered.linear(), I32x3 { x: 294, y: 0, z: 0 });
+        filter.reset();
+        let weak = filter.update(I32x3 {
+            x: 0,
+            y: 300,
+            z: 400,
+        });
+        assert_eq!(
+            weak.gravity_direction(),
+            Some(I32x3 {
+                x: 0,
+                y: 600,
+                z: 800
+            })
+        );
+    }
+
+    #[test]
+    fn gravity_follows_within_the_time_constant() {
+        let mut filter = super::GravityFilter::new(500, 100).unwrap();
+        filter.update(AT_REST);
+        let tilted = I32x3 {
+            x: 1000,
+            y: 0,
+            z: 0,
+        };
+        for _ in 0..50 {
+            filter.update(tilted);
+        }
+        // After one time constant 1 - 1/e of the step is tracked.
+        let gravity = filter.gravity().unwrap();
+        assert!((620..=640).contains(&gravity.x), "{}", gravity.x);
+        for _ in 0..500 {
+            filter.update(tilted);
+        }
+        assert_eq!(filter.update(tilted).linear(), I32x3 { x: 0, y: 0, z: 0 });
+    }
+
+    #[test]
+    fn sample_rate_is_taken_from_the_output_data_rate() {
+        let mut ctrl_reg1 = CtrlReg1Value::default();
+        assert!(super::GravityFilter::for_settings(500, &ctrl_reg1).is_err());
+        ctrl_reg1.set_output_data_rate(ODR::Hz400);
+        let filter =
+            super::GravityFilter::for_settings(500, &ctrl_reg1).unwrap();
+        assert_eq!(filter.sample_rate_hz(), 400);
+    }
+}
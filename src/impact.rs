@@ -0,0 +1,427 @@
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+use crate::enabled_enum::OnOff;
+use crate::gravity_filter::rounded_hz;
+use crate::int_cfg::IntCfg;
+use crate::int_duration_value::IntDuration;
+use crate::int_ths_value::IntThs;
+use crate::math::isqrt;
+use crate::Lis3dh;
+use micromath::vector::{I16x3, I32x3};
+
+/// Number of samples recorded around an impact.
+pub const WAVEFORM_LENGTH: usize = 32;
+
+/// Impact recorded by an [`ImpactRecorder`]. Accelerations are in mg.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImpactEvent {
+    sample_index: u32,
+    peak: I16x3,
+    peak_magnitude: u32,
+    duration_ms: u32,
+    waveform: [I16x3; WAVEFORM_LENGTH],
+    trigger_position: usize,
+}
+
+impl ImpactEvent {
+    /// Number of samples the recorder received before the one exceeding
+    /// the threshold.
+    pub fn sample_index(&self) -> u32 {
+        self.sample_index
+    }
+    /// Acceleration with the largest absolute value per axis.
+    pub fn peak(&self) -> I16x3 {
+        self.peak
+    }
+    /// Largest magnitude of a sample.
+    pub fn peak_magnitude(&self) -> u32 {
+        self.peak_magnitude
+    }
+    /// Time any axis exceeded the threshold.
+    pub fn duration_ms(&self) -> u32 {
+        self.duration_ms
+    }
+    /// Samples before and after the impact, starting with the oldest.
+    pub fn waveform(&self) -> &[I16x3; WAVEFORM_LENGTH] {
+        &self.waveform
+    }
+    /// Index of the first sample exceeding the threshold in the waveform.
+    pub fn trigger_position(&self) -> usize {
+        self.trigger_position
+    }
+}
+
+/// Records impacts from the stream of samples in mg, e.g. read from the
+/// FIFO with [`crate::Lis3dh::get_accel_norm_from_fifo`].
+///
+/// The interrupt generator 1 configured with
+/// [`ImpactRecorder::configure_interrupt`] signals impacts on INT1, so the
+/// samples only need to be read when it fires. The recorder applies the
+/// same threshold to the samples to capture the waveform, peaks and
+/// duration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImpactRecorder {
+    threshold_mg: u32,
+    pre_event_samples: usize,
+    sample_rate_millihertz: u32,
+    history: [I16x3; WAVEFORM_LENGTH],
+    samples: u32,
+    capture: Option<ImpactEvent>,
+    recorded: usize,
+    samples_above: u32,
+}
+
+impl ImpactRecorder {
+    /// Records impacts where an axis exceeds `threshold_mg`, keeping
+    /// `pre_event_samples` samples before the impact in the waveform.
+    pub fn new(
+        threshold_mg: u32,
+        pre_event_samples: usize,
+        sample_rate_hz: u32,
+    ) -> Result<Self, &'static str> {
+        ImpactRecorder::new_millihertz(
+            threshold_mg,
+            pre_event_samples,
+            sample_rate_hz.saturating_mul(1000),
+        )
+    }
+    /// Like [`ImpactRecorder::new`] for a fractional sample rate, e.g.
+    /// [`crate::rate_estimator::RateEstimate::millihertz`].
+    pub fn new_millihertz(
+        threshold_mg: u32,
+        pre_event_samples: usize,
+        sample_rate_millihertz: u32,
+    ) -> Result<Self, &'static str> {
+        if pre_event_samples >= WAVEFORM_LENGTH {
+            return Err("Pre-event samples must be fewer than the waveform");
+        }
+        if sample_rate_millihertz == 0 {
+            return Err("Sample rate must not be zero");
+        }
+        Ok(ImpactRecorder {
+            threshold_mg,
+            pre_event_samples,
+            sample_rate_millihertz,
+            history: [I16x3 { x: 0, y: 0, z: 0 }; WAVEFORM_LENGTH],
+            samples: 0,
+            capture: None,
+            recorded: 0,
+            samples_above: 0,
+        })
+    }
+    /// Creates a recorder for the sample rate configured in CTRL_REG1.
+    pub fn for_settings(
+        threshold_mg: u32,
+        pre_event_samples: usize,
+        ctrl_reg1: &CtrlReg1Value,
+    ) -> Result<Self, &'static str> {
+        ImpactRecorder::new(
+            threshold_mg,
+            pre_event_samples,
+            ctrl_reg1.sample_rate_hz()?,
+        )
+    }
+    pub fn threshold_mg(&self) -> u32 {
+        self.threshold_mg
+    }
+    pub fn pre_event_samples(&self) -> usize {
+        self.pre_event_samples
+    }
+    /// Sample rate rounded to whole Hz.
+    pub fn sample_rate_hz(&self) -> u32 {
+        rounded_hz(self.sample_rate_millihertz)
+    }
+    pub fn sample_rate_millihertz(&self) -> u32 {
+        self.sample_rate_millihertz
+    }
+    /// Sets the sample rate used for the impact durations, e.g. the rate
+    /// measured with a [`crate::rate_estimator::RateEstimator`].
+    pub fn set_sample_rate_millihertz(
+        &mut self,
+        sample_rate_millihertz: u32,
+    ) -> Result<(), &'static str> {
+        if sample_rate_millihertz == 0 {
+            return Err("Sample rate must not be zero");
+        }
+        self.sample_rate_millihertz = sample_rate_millihertz;
+        Ok(())
+    }
+    /// True while an impact is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.capture.is_some()
+    }
+    /// Sets up interrupt generator 1 to signal samples exceeding the
+    /// threshold on any axis on INT1, latched until INT1_SRC is read. The
+    /// threshold is rounded to the register resolution of the configured
+    /// full scale and the recorder adopts it. Write the settings with
+    /// [`crate::Lis3dh::apply_changes`].
+    pub fn configure_interrupt(
+        &mut self,
+        lis3dh: &mut Lis3dh,
+    ) -> Result<(), &'static str> {
+        let full_scale = lis3dh.ctrl_reg4_setting().fs();
+        let int1_ths = IntThs::from_mg(self.threshold_mg, full_scale)?;
+        let mut int1_cfg = IntCfg::default();
+        int1_cfg.set_xhie(OnOff::Enabled);
+        int1_cfg.set_yhie(OnOff::Enabled);
+        int1_cfg.set_zhie(OnOff::Enabled);
+        let mut ctrl_reg3 = lis3dh.ctrl_reg3_setting();
+        ctrl_reg3.set_interrupt_1_ia1(OnOff::Enabled);
+        let mut ctrl_reg5 = lis3dh.ctrl_reg5_setting();
+        ctrl_reg5.set_latch_int_on_int_1_src(OnOff::Enabled);
+        lis3dh.set_int1_cfg_setting(int1_cfg)?;
+        lis3dh.set_int1_ths_setting(int1_ths);
+        lis3dh.set_int1_duration_setting(IntDuration::default());
+        lis3dh.set_ctrl_reg3_setting(ctrl_reg3);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        self.threshold_mg = int1_ths.threshold_mg(full_scale);
+        Ok(())
+    }
+    /// Feeds a sample in mg. Returns the impact once the waveform is
+    /// complete and no axis exceeds the threshold any more.
+    pub fn update(&mut self, sample: I32x3) -> Option<ImpactEvent> {
+        let stored = saturate(sample);
+        let threshold = self.threshold_mg as i64;
+        let above = [sample.x, sample.y, sample.z]
+            .iter()
+            .any(|value| (*value as i64).abs() > threshold);
+        let index = self.samples;
+        self.samples = self.samples.wrapping_add(1);
+        self.history[index as usize % WAVEFORM_LENGTH] = stored;
+        if self.capture.is_none() {
+            if !above {
+                return None;
+            }
+            self.start_capture(index);
+        }
+        let event = self.capture.as_mut()?;
+        if self.recorded < WAVEFORM_LENGTH {
+            event.waveform[self.recorded] = stored;
+            self.recorded += 1;
+        }
+        let larger = |peak: i16, value: i16| {
+            if value.unsigned_abs() > peak.unsigned_abs() {
+                value
+            } else {
+                peak
+            }
+        };
+        event.peak = I16x3 {
+            x: larger(event.peak.x, stored.x),
+            y: larger(event.peak.y, stored.y),
+            z: larger(event.peak.z, stored.z),
+        };
+        event.peak_magnitude = event.peak_magnitude.max(magnitude(sample));
+        if above {
+            self.samples_above += 1;
+            return None;
+        }
+        if self.recorded < WAVEFORM_LENGTH {
+            return None;
+        }
+        let mut event = self.capture.take()?;
+        event.duration_ms = (self.samples_above as u64 * 1_000_000
+            / self.sample_rate_millihertz as u64)
+            as u32;
+        Some(event)
+    }
+    fn start_capture(&mut self, index: u32) {
+        let available = (index as usize).min(self.pre_event_samples);
+        let mut waveform = [I16x3 { x: 0, y: 0, z: 0 }; WAVEFORM_LENGTH];
+        for (offset, slot) in waveform[..available].iter_mut().enumerate() {
+            let age = (available - offset) as u32;
+            *slot = self.history
+                [index.wrapping_sub(age) as usize % WAVEFORM_LENGTH];
+        }
+        self.recorded = available;
+        self.samples_above = 0;
+        self.capture = Some(ImpactEvent {
+            sample_index: index,
+            peak: I16x3 { x: 0, y: 0, z: 0 },
+            peak_magnitude: 0,
+            duration_ms: 0,
+            waveform,
+            trigger_position: available,
+        });
+    }
+}
+
+fn saturate(sample: I32x3) -> I16x3 {
+    let clamp =
+        |value: i32| value.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+    I16x3 {
+        x: clamp(sample.x),
+        y: clamp(sample.y),
+        z: clamp(sample.z),
+    }
+}
+
+fn magnitude(sample: I32x3) -> u32 {
+    let square = |value: i32| (value as i64 * value as i64) as u64;
+    isqrt(square(sample.x) + square(sample.y) + square(sample.z)) as u32
+}
+
+/// Log of the last `N` impacts. Older impacts are overwritten once it is
+/// full.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImpactLog<const N: usize> {
+    events: [Option<ImpactEvent>; N],
+    next: usize,
+    overwritten: u32,
+}
+
+impl<const N: usize> Default for ImpactLog<N> {
+    fn default() -> Self {
+        ImpactLog::new()
+    }
+}
+
+impl<const N: usize> ImpactLog<N> {
+    pub fn new() -> Self {
+        ImpactLog {
+            events: [None; N],
+            next: 0,
+            overwritten: 0,
+        }
+    }
+    pub fn push(&mut self, event: ImpactEvent) {
+        if N == 0 {
+            self.overwritten = self.overwritten.saturating_add(1);
+            return;
+        }
+        if self.events[self.next].replace(event).is_some() {
+            self.overwritten = self.overwritten.saturating_add(1);
+        }
+        self.next = (self.next + 1) % N;
+    }
+    pub fn len(&self) -> usize {
+        self.events.iter().filter(|event| event.is_some()).count()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Number of impacts lost because the log was full.
+    pub fn overwritten(&self) -> u32 {
+        self.overwritten
+    }
+    /// Logged impacts, starting with the oldest.
+    pub fn iter(&self) -> impl Iterator<Item = &ImpactEvent> {
+        let (newer, older) = self.events.split_at(self.next);
+        older.iter().chain(newer).flatten()
+    }
+    pub fn clear(&mut self) {
+        *self = ImpactLog::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImpactLog, ImpactRecorder, WAVEFORM_LENGTH};
+    use crate::ctrl_reg_4_value::FullScaleSelection;
+    use crate::simulator::Simulator;
+    use crate::Lis3dh;
+    use micromath::vector::{I16x3, I32x3};
+
+    const AT_REST: I32x3 = I32x3 {
+        x: 0,
+        y: 0,
+        z: 1000,
+    };
+
+    fn drop_test(recorder: &mut ImpactRecorder) -> super::ImpactEvent {
+        for _ in 0..10 {
+            assert!(recorder.update(AT_REST).is_none());
+        }
+        for z in [1800, 3100, 2500] {
+            assert!(recorder.update(I32x3 { x: -900, y: 100, z }).is_none());
+        }
+        for _ in 0..WAVEFORM_LENGTH {
+            if let Some(event) = recorder.update(AT_REST) {
+                return event;
+            }
+        }
+        panic!("Impact was not completed");
+    }
+
+    #[test]
+    fn impact_is_recorded_with_pre_and_post_event_waveform() {
+        let mut recorder = ImpactRecorder::new(1500, 4, 100).unwrap();
+        let event = drop_test(&mut recorder);
+        assert_eq!(event.sample_index(), 10);
+        assert_eq!(event.trigger_position(), 4);
+        assert_eq!(
+            event.peak(),
+            I16x3 {
+                x: -900,
+                y: 100,
+                z: 3100
+            }
+        );
+        assert_eq!(event.peak_magnitude(), 3229);
+        assert_eq!(event.duration_ms(), 30);
+        let waveform = event.waveform();
+        assert_eq!(
+            waveform[3],
+            I16x3 {
+                x: 0,
+                y: 0,
+                z: 1000
+            }
+        );
+        assert_eq!(waveform[5].z, 3100);
+        assert_eq!(waveform[WAVEFORM_LENGTH - 1].z, 1000);
+        assert!(!recorder.is_recording());
+        assert!(ImpactRecorder::new(1500, WAVEFORM_LENGTH, 100).is_err());
+    }
+
+    #[test]
+    fn log_keeps_the_latest_events() {
+        let mut recorder = ImpactRecorder::new(1500, 4, 100).unwrap();
+        let mut log = ImpactLog::<2>::new();
+        assert!(log.is_empty());
+        for _ in 0..3 {
+            log.push(drop_test(&mut recorder));
+        }
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.overwritten(), 1);
+        let first = log.iter().next().unwrap().sample_index();
+        let second = log.iter().nth(1).unwrap().sample_index();
+        assert!(first < second);
+        log.clear();
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn interrupt_signals_the_impact_to_capture() {
+        let simulator = Simulator::new();
+        let mut cs = simulator.chip_select();
+        let mut spi = simulator.spi();
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(crate::ctrl_reg_1_value::ODR::Hz400);
+        let mut ctrl_reg4 = lis3dh.ctrl_reg4_setting();
+        ctrl_reg4.set_fs(FullScaleSelection::Gravity4G);
+        lis3dh.set_ctrl_reg4_setting(ctrl_reg4);
+        let mut recorder =
+            ImpactRecorder::for_settings(2000, 2, &lis3dh.ctrl_reg1_setting())
+                .unwrap();
+        recorder.configure_interrupt(&mut lis3dh).unwrap();
+        assert_eq!(recorder.threshold_mg(), 2016);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        simulator.inject_sample(AT_REST);
+        assert!(!lis3dh
+            .get_int_1_src_values(&mut cs, &mut spi)
+            .unwrap()
+            .is_interrupt_active());
+        simulator.inject_sample(I32x3 {
+            x: 0,
+            y: 0,
+            z: 2500,
+        });
+        assert!(lis3dh
+            .get_int_1_src_values(&mut cs, &mut spi)
+            .unwrap()
+            .is_interrupt_active());
+        let sample = lis3dh.get_accel_norm(&mut cs, &mut spi).unwrap();
+        assert!(recorder.update(sample).is_none());
+        assert!(recorder.is_recording());
+    }
+}
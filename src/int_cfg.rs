@@ -1,5 +1,8 @@
 use crate::enabled_enum;
 use crate::enabled_enum::OnOff;
+use crate::register::{AccessMode, InterruptGeneratorRegister, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
 
 const XLIE_BIT_OFFSET: u8 = 0;
 const XHIE_BIT_OFFSET: u8 = 1;
@@ -10,7 +13,7 @@ const ZHIE_BIT_OFFSET: u8 = 5;
 const SIXD_BIT_OFFSET: u8 = 6;
 const AOI_BIT_OFFSET: u8 = 7;
 
-#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct IntCfg {
     aoi: OnOff,
     six_d: OnOff,
@@ -71,8 +74,23 @@ impl IntCfg {
     pub fn xlie(&self) -> OnOff {
         self.xlie
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
-        IntCfg {
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.aoi as u8) << AOI_BIT_OFFSET
+            | (self.six_d as u8) << SIXD_BIT_OFFSET
+            | (self.zhie as u8) << ZHIE_BIT_OFFSET
+            | (self.zlie as u8) << ZLIE_BIT_OFFSET
+            | (self.yhie as u8) << YHIE_BIT_OFFSET
+            | (self.ylie as u8) << YLIE_BIT_OFFSET
+            | (self.xhie as u8) << XHIE_BIT_OFFSET
+            | (self.xlie as u8) << XLIE_BIT_OFFSET
+    }
+}
+
+impl TryFrom<u8> for IntCfg {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(IntCfg {
             aoi: enabled_enum::get_state_from_bit_value(
                 value >> AOI_BIT_OFFSET,
             ),
@@ -97,27 +115,41 @@ impl IntCfg {
             xlie: enabled_enum::get_state_from_bit_value(
                 value >> XLIE_BIT_OFFSET,
             ),
-        }
+        })
     }
-    pub(super) fn get_raw_value(&self) -> u8 {
-        (self.aoi as u8) << AOI_BIT_OFFSET
-            | (self.six_d as u8) << SIXD_BIT_OFFSET
-            | (self.zhie as u8) << ZHIE_BIT_OFFSET
-            | (self.zlie as u8) << ZLIE_BIT_OFFSET
-            | (self.yhie as u8) << YHIE_BIT_OFFSET
-            | (self.ylie as u8) << YLIE_BIT_OFFSET
-            | (self.xhie as u8) << XHIE_BIT_OFFSET
-            | (self.xlie as u8) << XLIE_BIT_OFFSET
+}
+
+impl Register for IntCfg {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::Int1Cfg;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("xlie", 1 << XLIE_BIT_OFFSET),
+        RegisterField::new("xhie", 1 << XHIE_BIT_OFFSET),
+        RegisterField::new("ylie", 1 << YLIE_BIT_OFFSET),
+        RegisterField::new("yhie", 1 << YHIE_BIT_OFFSET),
+        RegisterField::new("zlie", 1 << ZLIE_BIT_OFFSET),
+        RegisterField::new("zhie", 1 << ZHIE_BIT_OFFSET),
+        RegisterField::new("six_d", 1 << SIXD_BIT_OFFSET),
+        RegisterField::new("aoi", 1 << AOI_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
     }
 }
 
+impl InterruptGeneratorRegister for IntCfg {
+    const INT2_ADDRESS: RegisterAddresses = RegisterAddresses::Int2Cfg;
+}
+
 #[cfg(test)]
 mod tests {
 
     #[test]
     fn conversion_from_raw_value_works() {
         let int_cfg_raw = 0b1010_0101_u8;
-        let int_cfg = super::IntCfg::from_raw_value(int_cfg_raw);
+        let int_cfg = super::IntCfg::try_from(int_cfg_raw).unwrap();
         assert_eq!(int_cfg.aoi, super::OnOff::Enabled);
         assert_eq!(int_cfg.six_d, super::OnOff::Disabled);
         assert_eq!(int_cfg.zhie, super::OnOff::Enabled);
@@ -129,6 +161,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn conversion_to_raw_value_works() {
         let mut int_cfg = super::IntCfg::default();
         int_cfg.six_d = super::OnOff::Enabled;
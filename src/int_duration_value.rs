@@ -1,3 +1,7 @@
+use crate::register::{AccessMode, InterruptGeneratorRegister, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+
 /// Duration time is measured in N/ODR, where N is the content of the duration register.
 /// Duration time steps and maximum values depend on the ODR chosen.
 /// ODR is the data rate configuration set in CtrlReg4
@@ -8,6 +12,18 @@ pub struct IntDuration {
 }
 
 impl IntDuration {
+    /// Duration closest to `duration_ms` at the given sample rate.
+    pub fn from_ms(
+        duration_ms: u32,
+        sample_rate_hz: u32,
+    ) -> Result<Self, &'static str> {
+        let duration =
+            (duration_ms as u64 * sample_rate_hz as u64 + 500) / 1000;
+        if duration > 0b0111_1111 {
+            return Err("Interrupt duration exceeds 127 samples");
+        }
+        IntDuration::try_from(duration as u8)
+    }
     pub fn set_duration(&mut self, duration: u8) -> Result<(), &'static str> {
         if (duration & 0b1000_0000) == 0b1000_0000 {
             return Err("Interrupt duration can only contain 7 bit");
@@ -18,22 +34,43 @@ impl IntDuration {
     pub fn duration(&self) -> u8 {
         self.duration
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
-        IntDuration {
-            duration: (value & 0b0111_1111),
-        }
-    }
     pub(super) fn get_raw_value(&self) -> u8 {
         self.duration
     }
 }
 
+impl TryFrom<u8> for IntDuration {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        let mut int_duration = IntDuration::default();
+        int_duration.set_duration(value)?;
+        Ok(int_duration)
+    }
+}
+
+impl Register for IntDuration {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::Int1Duration;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] =
+        &[RegisterField::new("duration", 0b0111_1111)];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+impl InterruptGeneratorRegister for IntDuration {
+    const INT2_ADDRESS: RegisterAddresses = RegisterAddresses::Int2Duration;
+}
+
 #[cfg(test)]
 mod test {
     #[test]
     fn conversion_from_raw_value_works() {
         let raw_value = 0b101_0100_u8;
-        let int_duration = super::IntDuration::from_raw_value(raw_value);
+        let int_duration = super::IntDuration::try_from(raw_value).unwrap();
         assert_eq!(int_duration.duration, raw_value)
     }
 
@@ -44,9 +81,21 @@ mod test {
         assert_eq!(int_duration.get_raw_value(), 0b101_1010);
     }
 
+    #[test]
+    fn conversion_from_raw_value_fails_if_bit_8_is_set() {
+        assert!(super::IntDuration::try_from(0b1101_1010).is_err());
+    }
+
     #[test]
     fn setting_duration_fails_if_bit_8_is_set() {
         let mut int_duration = super::IntDuration::default();
         assert!(int_duration.set_duration(0b1101_1010).is_err());
     }
+
+    #[test]
+    fn duration_is_converted_from_ms() {
+        let int_duration = super::IntDuration::from_ms(50, 100).unwrap();
+        assert_eq!(int_duration.duration(), 5);
+        assert!(super::IntDuration::from_ms(2000, 100).is_err());
+    }
 }
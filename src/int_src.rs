@@ -1,3 +1,10 @@
+use crate::enabled_enum::OnOff;
+use crate::int_cfg::IntCfg;
+use crate::register::{AccessMode, InterruptGeneratorRegister, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+use micromath::vector::I32x3;
+
 const XL_BIT_OFFSET: u8 = 0;
 const XH_BIT_OFFSET: u8 = 1;
 const YL_BIT_OFFSET: u8 = 2;
@@ -5,8 +12,9 @@ const YH_BIT_OFFSET: u8 = 3;
 const ZL_BIT_OFFSET: u8 = 4;
 const ZH_BIT_OFFSET: u8 = 5;
 const IA_BIT_OFFSET: u8 = 6;
+const INTERRUPT_EVENT_BITS: u8 = 0b11_1111;
 
-#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct IntSrc {
     interrupt_active: bool,
     z_high: bool,
@@ -18,6 +26,30 @@ pub struct IntSrc {
 }
 
 impl IntSrc {
+    /// Source flags an interrupt generator configured with `cfg` and
+    /// `threshold_mg` reports for a sample in mg. Axes are compared by
+    /// their absolute value, 6D detection and the duration are not
+    /// evaluated.
+    pub fn evaluate(cfg: &IntCfg, threshold_mg: u32, sample: I32x3) -> Self {
+        let threshold = threshold_mg as i64;
+        let events = [sample.x, sample.y, sample.z].iter().enumerate().fold(
+            0_u8,
+            |events, (axis, value)| {
+                let value = (*value as i64).abs();
+                let low = (value < threshold) as u8;
+                let high = (value > threshold) as u8;
+                events | (low | high << 1) << (2 * axis)
+            },
+        );
+        let enabled = cfg.get_raw_value() & INTERRUPT_EVENT_BITS;
+        let active = if cfg.aoi() == OnOff::Enabled {
+            enabled != 0 && events & enabled == enabled
+        } else {
+            events & enabled != 0
+        };
+        IntSrc::try_from((active as u8) << IA_BIT_OFFSET | events)
+            .unwrap_or_default()
+    }
     pub fn is_interrupt_active(&self) -> bool {
         self.interrupt_active
     }
@@ -39,8 +71,25 @@ impl IntSrc {
     pub fn is_x_low(&self) -> bool {
         self.x_low
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
-        IntSrc {
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.interrupt_active as u8) << IA_BIT_OFFSET
+            | (self.z_high as u8) << ZH_BIT_OFFSET
+            | (self.z_low as u8) << ZL_BIT_OFFSET
+            | (self.y_high as u8) << YH_BIT_OFFSET
+            | (self.y_low as u8) << YL_BIT_OFFSET
+            | (self.x_high as u8) << XH_BIT_OFFSET
+            | (self.x_low as u8) << XL_BIT_OFFSET
+    }
+}
+
+impl TryFrom<u8> for IntSrc {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & 0b1000_0000 != 0 {
+            return Err("INT_SRC bit 7 has to be 0");
+        }
+        Ok(IntSrc {
             interrupt_active: (value >> IA_BIT_OFFSET) & 1 == 1,
             z_high: (value >> ZH_BIT_OFFSET) & 1 == 1,
             z_low: (value >> ZL_BIT_OFFSET) & 1 == 1,
@@ -48,18 +97,66 @@ impl IntSrc {
             y_low: (value >> YL_BIT_OFFSET) & 1 == 1,
             x_high: (value >> XH_BIT_OFFSET) & 1 == 1,
             x_low: (value >> XL_BIT_OFFSET) & 1 == 1,
-        }
+        })
+    }
+}
+
+impl Register for IntSrc {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::Int1Src;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadOnly;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("x_low", 1 << XL_BIT_OFFSET),
+        RegisterField::new("x_high", 1 << XH_BIT_OFFSET),
+        RegisterField::new("y_low", 1 << YL_BIT_OFFSET),
+        RegisterField::new("y_high", 1 << YH_BIT_OFFSET),
+        RegisterField::new("z_low", 1 << ZL_BIT_OFFSET),
+        RegisterField::new("z_high", 1 << ZH_BIT_OFFSET),
+        RegisterField::new("interrupt_active", 1 << IA_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
     }
 }
 
+impl InterruptGeneratorRegister for IntSrc {
+    const INT2_ADDRESS: RegisterAddresses = RegisterAddresses::Int2Src;
+}
+
 #[cfg(test)]
 mod test {
     #[test]
     fn conversion_from_raw_value_works() {
         let raw_value = 0b0101_0100_u8;
-        let int_src = super::IntSrc::from_raw_value(raw_value);
+        let int_src = super::IntSrc::try_from(raw_value).unwrap();
         assert!(int_src.is_y_low());
         assert!(int_src.is_z_low());
         assert!(int_src.is_interrupt_active());
     }
+
+    #[test]
+    fn conversion_from_raw_value_fails_if_bit_7_is_set() {
+        assert!(super::IntSrc::try_from(0b1101_0100).is_err());
+    }
+
+    #[test]
+    fn evaluation_combines_the_enabled_events() {
+        use crate::enabled_enum::OnOff;
+        use micromath::vector::I32x3;
+        let mut cfg = crate::int_cfg::IntCfg::default();
+        cfg.set_xhie(OnOff::Enabled);
+        cfg.set_zlie(OnOff::Enabled);
+        let sample = I32x3 {
+            x: -700,
+            y: 0,
+            z: 1000,
+        };
+        let src = super::IntSrc::evaluate(&cfg, 500, sample);
+        assert!(src.is_interrupt_active());
+        assert!(src.is_x_high() && src.is_y_low() && !src.is_z_low());
+        cfg.set_aoi(OnOff::Enabled);
+        let src = super::IntSrc::evaluate(&cfg, 500, sample);
+        assert!(!src.is_interrupt_active());
+    }
 }
@@ -1,3 +1,8 @@
+use crate::ctrl_reg_4_value::FullScaleSelection;
+use crate::register::{AccessMode, InterruptGeneratorRegister, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+
 /// The value passed to the threshold is multiplied with a factor that is dependant from the
 /// the FullScaleSelection set in CtrlReg4.
 ///
@@ -17,7 +22,33 @@ pub struct IntThs {
     threshold: u8,
 }
 
+/// Threshold in mg represented by one LSB of the threshold register.
+pub fn threshold_step_mg(full_scale: FullScaleSelection) -> u32 {
+    match full_scale {
+        FullScaleSelection::Gravity2G => 16,
+        FullScaleSelection::Gravity4G => 32,
+        FullScaleSelection::Gravity8G => 62,
+        FullScaleSelection::Gravity16G => 186,
+    }
+}
+
 impl IntThs {
+    /// Threshold closest to `threshold_mg` for the given full scale.
+    pub fn from_mg(
+        threshold_mg: u32,
+        full_scale: FullScaleSelection,
+    ) -> Result<Self, &'static str> {
+        let step = threshold_step_mg(full_scale);
+        let threshold = threshold_mg.saturating_add(step / 2) / step;
+        if threshold > 0b0111_1111 {
+            return Err("Interrupt threshold exceeds the full scale");
+        }
+        IntThs::try_from(threshold as u8)
+    }
+    /// Threshold in mg for the given full scale.
+    pub fn threshold_mg(&self, full_scale: FullScaleSelection) -> u32 {
+        self.threshold as u32 * threshold_step_mg(full_scale)
+    }
     pub fn set_threshold(&mut self, threshold: u8) -> Result<(), &'static str> {
         if (threshold & 0b1000_0000) == 0b1000_0000 {
             return Err("Interrupt threshold can only contain 7 bit");
@@ -28,22 +59,43 @@ impl IntThs {
     pub fn threshold(&self) -> u8 {
         self.threshold
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
-        IntThs {
-            threshold: (value & 0b0111_1111),
-        }
-    }
     pub(super) fn get_raw_value(&self) -> u8 {
         self.threshold
     }
 }
 
+impl TryFrom<u8> for IntThs {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        let mut int_ths = IntThs::default();
+        int_ths.set_threshold(value)?;
+        Ok(int_ths)
+    }
+}
+
+impl Register for IntThs {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::Int1Threshold;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] =
+        &[RegisterField::new("threshold", 0b0111_1111)];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+impl InterruptGeneratorRegister for IntThs {
+    const INT2_ADDRESS: RegisterAddresses = RegisterAddresses::Int2Threshold;
+}
+
 #[cfg(test)]
 mod test {
     #[test]
     fn conversion_from_raw_value_works() {
         let raw_value = 0b101_0100_u8;
-        let int_ths = super::IntThs::from_raw_value(raw_value);
+        let int_ths = super::IntThs::try_from(raw_value).unwrap();
         assert_eq!(int_ths.threshold(), raw_value)
     }
 
@@ -54,9 +106,29 @@ mod test {
         assert_eq!(int_ths.get_raw_value(), 0b101_1010);
     }
 
+    #[test]
+    fn conversion_from_raw_value_fails_if_bit_8_is_set() {
+        assert!(super::IntThs::try_from(0b1101_1010).is_err());
+    }
+
     #[test]
     fn setting_threshold_fails_if_bit_8_is_set() {
         let mut intths = super::IntThs::default();
         assert!(intths.set_threshold(0b1101_1010).is_err());
     }
+
+    #[test]
+    fn threshold_is_converted_from_mg() {
+        use crate::ctrl_reg_4_value::FullScaleSelection;
+        let int_ths =
+            super::IntThs::from_mg(250, FullScaleSelection::Gravity2G).unwrap();
+        assert_eq!(int_ths.threshold(), 0x10);
+        assert_eq!(int_ths.threshold_mg(FullScaleSelection::Gravity2G), 256);
+        let int_ths =
+            super::IntThs::from_mg(3000, FullScaleSelection::Gravity16G)
+                .unwrap();
+        assert_eq!(int_ths.threshold(), 16);
+        assert!(super::IntThs::from_mg(2100, FullScaleSelection::Gravity2G)
+            .is_err());
+    }
 }
@@ -0,0 +1,320 @@
+use crate::calibration::Orientation;
+use crate::click_src::ClickSrc;
+use crate::ctrl_reg_3_value::CtrlReg3Value;
+use crate::ctrl_reg_6_value::CtrlReg6Value;
+use crate::enabled_enum::OnOff;
+use crate::fifo_src_reg_value::FifoSrcRegValue;
+use crate::int_src::IntSrc;
+use crate::status_reg_value::StatusRegValue;
+
+/// Cause of an interrupt reported by [`crate::Lis3dh::service_interrupt`].
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterruptEvent {
+    /// A new X, Y and Z sample is available.
+    DataReady,
+    /// The FIFO holds more samples than its threshold.
+    Watermark,
+    /// The FIFO is full.
+    Overrun,
+    /// Interrupt generator 1 is active.
+    Ia1,
+    /// Interrupt generator 2 is active.
+    Ia2,
+    Click,
+    /// Wake up by the sleep-to-wake function. It has no source register,
+    /// see [`ServicedInterrupt::is_activity_inferred`].
+    Activity,
+    /// An interrupt generator in 6D mode detected a position or movement.
+    Position,
+}
+
+impl InterruptEvent {
+    pub const ALL: [InterruptEvent; 8] = [
+        InterruptEvent::DataReady,
+        InterruptEvent::Watermark,
+        InterruptEvent::Overrun,
+        InterruptEvent::Ia1,
+        InterruptEvent::Ia2,
+        InterruptEvent::Click,
+        InterruptEvent::Activity,
+        InterruptEvent::Position,
+    ];
+}
+
+/// Interrupt pin of the sensor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterruptPin {
+    Int1,
+    Int2,
+}
+
+/// Set of [`InterruptEvent`]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InterruptEvents(u8);
+
+impl InterruptEvents {
+    pub fn insert(&mut self, event: InterruptEvent) {
+        self.0 |= 1 << event as u8;
+    }
+    pub fn contains(&self, event: InterruptEvent) -> bool {
+        self.0 & 1 << event as u8 != 0
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    pub fn iter(&self) -> impl Iterator<Item = InterruptEvent> + '_ {
+        InterruptEvent::ALL
+            .into_iter()
+            .filter(|event| self.contains(*event))
+    }
+    /// Events that can raise an interrupt pin with the given routing.
+    pub fn routed(
+        ctrl_reg3: &CtrlReg3Value,
+        ctrl_reg6: &CtrlReg6Value,
+    ) -> Self {
+        let int1 = Self::routed_to(InterruptPin::Int1, ctrl_reg3, ctrl_reg6);
+        let int2 = Self::routed_to(InterruptPin::Int2, ctrl_reg3, ctrl_reg6);
+        InterruptEvents(int1.0 | int2.0)
+    }
+    /// Events that can raise `pin` with the given routing.
+    pub fn routed_to(
+        pin: InterruptPin,
+        ctrl_reg3: &CtrlReg3Value,
+        ctrl_reg6: &CtrlReg6Value,
+    ) -> Self {
+        let mut routed = InterruptEvents::default();
+        let mut route = |on_off: OnOff, event| {
+            if on_off == OnOff::Enabled {
+                routed.insert(event);
+            }
+        };
+        match pin {
+            InterruptPin::Int1 => {
+                route(
+                    ctrl_reg3.interrupt_1_zyx_da(),
+                    InterruptEvent::DataReady,
+                );
+                route(
+                    ctrl_reg3.interrupt_1_fifo_watermark(),
+                    InterruptEvent::Watermark,
+                );
+                route(
+                    ctrl_reg3.interrupt_1_fifo_overrun(),
+                    InterruptEvent::Overrun,
+                );
+                route(ctrl_reg3.interrupt_1_ia1(), InterruptEvent::Ia1);
+                route(ctrl_reg3.interrupt_1_ia2(), InterruptEvent::Ia2);
+                route(ctrl_reg3.interrupt_1_click(), InterruptEvent::Click);
+            }
+            InterruptPin::Int2 => {
+                route(ctrl_reg6.interrupt_2_ia1(), InterruptEvent::Ia1);
+                route(ctrl_reg6.interrupt_2_ia2(), InterruptEvent::Ia2);
+                route(ctrl_reg6.interrupt_2_click(), InterruptEvent::Click);
+                route(
+                    ctrl_reg6.interrupt_2_activity(),
+                    InterruptEvent::Activity,
+                );
+            }
+        }
+        routed
+    }
+}
+
+/// Events and source registers read by
+/// [`crate::Lis3dh::service_interrupt`]. Sources that are not routed to the
+/// serviced interrupt pin are not read and `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ServicedInterrupt {
+    pub(crate) events: InterruptEvents,
+    pub(crate) status: Option<StatusRegValue>,
+    pub(crate) fifo_src: Option<FifoSrcRegValue>,
+    pub(crate) int1_src: Option<IntSrc>,
+    pub(crate) int2_src: Option<IntSrc>,
+    pub(crate) click_src: Option<ClickSrc>,
+    pub(crate) position: Option<Orientation>,
+    pub(crate) activity_inferred: bool,
+}
+
+impl ServicedInterrupt {
+    pub fn events(&self) -> InterruptEvents {
+        self.events
+    }
+    pub fn status(&self) -> Option<StatusRegValue> {
+        self.status
+    }
+    pub fn fifo_src(&self) -> Option<FifoSrcRegValue> {
+        self.fifo_src
+    }
+    pub fn int1_src(&self) -> Option<IntSrc> {
+        self.int1_src
+    }
+    pub fn int2_src(&self) -> Option<IntSrc> {
+        self.int2_src
+    }
+    pub fn click_src(&self) -> Option<ClickSrc> {
+        self.click_src
+    }
+    /// Position reported by an interrupt generator in 6D mode.
+    pub fn position(&self) -> Option<Orientation> {
+        self.position
+    }
+    /// True if activity is routed to the serviced pin and no other event
+    /// routed to it is active, so the pin was presumably raised by the
+    /// sleep-to-wake function. Only meaningful if the pin was raised.
+    pub fn is_activity_inferred(&self) -> bool {
+        self.activity_inferred
+    }
+}
+
+/// Axis direction an interrupt generator in 6D mode reports.
+pub(crate) fn position(src: &IntSrc) -> Option<Orientation> {
+    [
+        (src.is_x_high(), Orientation::XUp),
+        (src.is_x_low(), Orientation::XDown),
+        (src.is_y_high(), Orientation::YUp),
+        (src.is_y_low(), Orientation::YDown),
+        (src.is_z_high(), Orientation::ZUp),
+        (src.is_z_low(), Orientation::ZDown),
+    ]
+    .into_iter()
+    .find_map(|(set, orientation)| set.then_some(orientation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InterruptEvent, InterruptEvents, InterruptPin};
+    use crate::calibration::Orientation;
+    use crate::ctrl_reg_1_value::ODR;
+    use crate::ctrl_reg_3_value::CtrlReg3Value;
+    use crate::ctrl_reg_5_value::CtrlReg5Value;
+    use crate::ctrl_reg_6_value::CtrlReg6Value;
+    use crate::enabled_enum::OnOff;
+    use crate::fifo_ctrl_reg_value::{FifoCtrlRegValue, FifoMode};
+    use crate::int_cfg::IntCfg;
+    use crate::simulator::Simulator;
+    use crate::{Lis3dh, RegisterAddresses};
+    use micromath::vector::I32x3;
+
+    const AT_REST: I32x3 = I32x3 {
+        x: 0,
+        y: 0,
+        z: 1000,
+    };
+
+    fn events(list: &[InterruptEvent]) -> InterruptEvents {
+        let mut events = InterruptEvents::default();
+        for event in list {
+            events.insert(*event);
+        }
+        events
+    }
+
+    #[test]
+    fn only_routed_sources_are_read() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(ODR::Hz100);
+        let mut ctrl_reg3 = CtrlReg3Value::default();
+        ctrl_reg3.set_interrupt_1_zyx_da(OnOff::Enabled);
+        ctrl_reg3.set_interrupt_1_fifo_watermark(OnOff::Enabled);
+        lis3dh.set_ctrl_reg3_setting(ctrl_reg3);
+        let mut ctrl_reg5 = CtrlReg5Value::default();
+        ctrl_reg5.set_fifo(OnOff::Enabled);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut fifo_ctrl = FifoCtrlRegValue::default();
+        fifo_ctrl.set_fifo_mode(FifoMode::Stream);
+        fifo_ctrl.set_threshold(3).unwrap();
+        lis3dh.write(&mut cs, &mut spi, fifo_ctrl).unwrap();
+        // A click that is not routed stays unnoticed.
+        simulator.set_register(RegisterAddresses::ClickSrc, 0b0101_0100);
+
+        simulator.inject_sample(AT_REST);
+        let serviced = lis3dh
+            .service_interrupt(&mut cs, &mut spi, InterruptPin::Int1)
+            .unwrap();
+        assert_eq!(serviced.events(), events(&[InterruptEvent::DataReady]));
+        assert!(serviced.fifo_src().is_some());
+        assert_eq!(serviced.click_src(), None);
+        assert_eq!(serviced.int1_src(), None);
+
+        for _ in 0..3 {
+            simulator.inject_sample(AT_REST);
+        }
+        let serviced = lis3dh
+            .service_interrupt(&mut cs, &mut spi, InterruptPin::Int1)
+            .unwrap();
+        assert_eq!(
+            serviced.events(),
+            events(&[InterruptEvent::DataReady, InterruptEvent::Watermark])
+        );
+        assert!(!serviced.events().contains(InterruptEvent::Overrun));
+    }
+
+    #[test]
+    fn six_d_interrupt_reports_the_position() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        let mut int1_cfg = IntCfg::default();
+        int1_cfg.set_six_d(OnOff::Enabled);
+        int1_cfg.set_zhie(OnOff::Enabled);
+        lis3dh.set_int1_cfg_setting(int1_cfg).unwrap();
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut ctrl_reg6 = CtrlReg6Value::default();
+        ctrl_reg6.set_interrupt_2_ia1(OnOff::Enabled);
+        ctrl_reg6.set_interrupt_2_click(OnOff::Enabled);
+        lis3dh.write(&mut cs, &mut spi, ctrl_reg6).unwrap();
+        // The simulator does not model 6D detection.
+        simulator.set_register(RegisterAddresses::Int1Src, 0b0110_0000);
+
+        let serviced = lis3dh
+            .service_interrupt(&mut cs, &mut spi, InterruptPin::Int2)
+            .unwrap();
+        assert_eq!(
+            serviced.events(),
+            events(&[InterruptEvent::Ia1, InterruptEvent::Position])
+        );
+        assert_eq!(serviced.position(), Some(Orientation::ZUp));
+        assert!(serviced.int1_src().unwrap().is_z_high());
+        assert!(!serviced.click_src().unwrap().is_interrupt_active());
+        assert_eq!(serviced.status(), None);
+    }
+
+    #[test]
+    fn activity_is_only_inferred_for_int2() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(ODR::Hz100);
+        let mut ctrl_reg3 = CtrlReg3Value::default();
+        ctrl_reg3.set_interrupt_1_zyx_da(OnOff::Enabled);
+        lis3dh.set_ctrl_reg3_setting(ctrl_reg3);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut ctrl_reg6 = CtrlReg6Value::default();
+        ctrl_reg6.set_interrupt_2_activity(OnOff::Enabled);
+        ctrl_reg6.set_interrupt_2_click(OnOff::Enabled);
+        lis3dh.write(&mut cs, &mut spi, ctrl_reg6).unwrap();
+
+        simulator.inject_sample(AT_REST);
+        let serviced = lis3dh
+            .service_interrupt(&mut cs, &mut spi, InterruptPin::Int1)
+            .unwrap();
+        assert_eq!(serviced.events(), events(&[InterruptEvent::DataReady]));
+        assert!(!serviced.is_activity_inferred());
+        let serviced = lis3dh
+            .service_interrupt(&mut cs, &mut spi, InterruptPin::Int2)
+            .unwrap();
+        assert!(serviced.events().is_empty());
+        assert!(serviced.is_activity_inferred());
+        simulator.set_register(RegisterAddresses::ClickSrc, 0b0110_0001);
+        let serviced = lis3dh
+            .service_interrupt(&mut cs, &mut spi, InterruptPin::Int2)
+            .unwrap();
+        assert_eq!(serviced.events(), events(&[InterruptEvent::Click]));
+        assert!(!serviced.is_activity_inferred());
+        assert!(serviced.click_src().unwrap().is_double_click());
+    }
+}
@@ -1,22 +1,62 @@
 #![no_std]
+pub mod axis_map;
+pub mod calibration;
+pub mod click_cfg;
+pub mod click_src;
 pub mod ctrl_reg_0_value;
 pub mod ctrl_reg_1_value;
 pub mod ctrl_reg_2_value;
 pub mod ctrl_reg_3_value;
 pub mod ctrl_reg_4_value;
 pub mod ctrl_reg_5_value;
+pub mod ctrl_reg_6_value;
 pub mod enabled_enum;
+pub mod event_capture;
+#[cfg(any(test, feature = "fault-injection"))]
+pub mod fault_injection;
+pub mod fifo_ctrl_reg_value;
+pub mod fifo_src_reg_value;
+#[cfg(any(test, feature = "fixed-point"))]
+pub mod fixed_point;
+pub mod gravity_filter;
+pub mod impact;
 pub mod int_cfg;
 pub mod int_duration_value;
 pub mod int_src;
 pub mod int_ths_value;
-mod mode;
-mod status_reg_aux_value;
-mod temp_cfg_reg_value;
+pub mod interrupt_events;
+mod math;
+pub mod mode;
+pub mod pedometer;
+pub mod rate_estimator;
+pub mod register;
+pub mod register_field;
+pub mod register_set;
+pub mod register_snapshot;
+pub mod sample_format;
+pub mod sample_queue;
+pub mod sensor_array;
+#[cfg(any(test, feature = "critical-section"))]
+pub mod shared;
+#[cfg(any(test, feature = "simulator"))]
+pub mod simulator;
+pub mod status_reg_aux_value;
+pub mod status_reg_value;
+pub mod temp_cfg_reg_value;
+pub mod three_wire;
+pub mod tilt;
+pub mod timestamp;
+pub mod verification;
+pub mod vibration;
 
 #[macro_use]
 extern crate num_derive;
 extern crate embedded_hal as hal;
+use crate::axis_map::AxisMap;
+use crate::calibration::Calibration;
+use crate::click_cfg::ClickCfg;
+use crate::click_src::ClickSrc;
+use crate::enabled_enum::OnOff;
 use crate::int_cfg::IntCfg;
 use crate::int_duration_value::IntDuration;
 use crate::int_src::IntSrc;
@@ -27,18 +67,33 @@ use ctrl_reg_2_value::CtrlReg2Value;
 use ctrl_reg_3_value::CtrlReg3Value;
 use ctrl_reg_4_value::CtrlReg4Value;
 use ctrl_reg_5_value::CtrlReg5Value;
+use ctrl_reg_6_value::CtrlReg6Value;
+use fifo_ctrl_reg_value::FifoCtrlRegValue;
+use fifo_src_reg_value::{FifoSrcRegValue, FIFO_DEPTH};
 use hal::{
     blocking::spi::{Transfer, Write},
     digital::v2::OutputPin,
 };
 use int_ths_value::IntThs;
+use interrupt_events::{
+    InterruptEvent, InterruptEvents, InterruptPin, ServicedInterrupt,
+};
 use micromath::vector::{I16x3, I32x3};
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
+use register::{AccessMode, Int2, Register};
+use register_set::RegisterSet;
+use register_snapshot::{RegisterSnapshot, SNAPSHOT_LENGTH};
+use sample_format::{SampleFormat, SAMPLE_SIZE};
 use status_reg_aux_value::StatusRegAuxValue;
+use status_reg_value::StatusRegValue;
 use temp_cfg_reg_value::TempCfgRegValue;
+use tilt::{Tilt, TiltCalculator};
+use timestamp::{Clock, TimestampedSample};
+use verification::VerificationReport;
 
 pub const SPI_READ_BIT: u8 = 0x80;
+pub const SPI_AUTO_INCREMENT_BIT: u8 = 0x40;
 
 #[derive(Copy, Clone)]
 pub struct AngleAnd1GOffset {
@@ -63,10 +118,36 @@ pub enum Error<CsE, SpiE> {
     ChipSelectError(CsE),
     SpiError(SpiE),
     AttemptToWriteToReadOnlyRegister,
+    AttemptToAccessReservedRegister,
+    InvalidRegisterValue(&'static str),
+    /// An argument passed by the caller is not usable, e.g. a buffer that
+    /// is too short.
+    InvalidArgument(&'static str),
+    /// Samples read from the FIFO failed [`SampleFormat::check_raw`]. The
+    /// FIFO was drained anyway: `read` samples were written to the buffer,
+    /// bit `n` of `corrupted` is set if the sample at index `n` is corrupted.
+    CorruptedFifoSamples {
+        read: usize,
+        corrupted: u32,
+    },
+}
+
+/// Splits [`Error::CorruptedFifoSamples`] off the result of a FIFO read into
+/// the number of samples read and the mask of the corrupted ones.
+pub(crate) fn split_corrupted<CsE, SpiE>(
+    result: Result<usize, Error<CsE, SpiE>>,
+) -> Result<(usize, u32), Error<CsE, SpiE>> {
+    match result {
+        Ok(read) => Ok((read, 0)),
+        Err(Error::CorruptedFifoSamples { read, corrupted }) => {
+            Ok((read, corrupted))
+        }
+        Err(error) => Err(error),
+    }
 }
 
 #[repr(u8)]
-#[derive(FromPrimitive, PartialOrd, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, FromPrimitive, PartialOrd, PartialEq, Eq)]
 pub enum RegisterAddresses {
     StatusRegAux = 0x07,
     OutAdc1L,
@@ -112,11 +193,18 @@ pub enum RegisterAddresses {
     ActivationDuration,
 }
 
+fn is_reserved(address: u8) -> bool {
+    RegisterAddresses::from_u8(address).is_none()
+}
+
 fn is_read_only(address: u8) -> bool {
-    let register = RegisterAddresses::from_u8(address).unwrap();
-    (RegisterAddresses::StatusRegAux..RegisterAddresses::WhoAmI)
+    let register = match RegisterAddresses::from_u8(address) {
+        Some(register) => register,
+        None => return false,
+    };
+    (RegisterAddresses::StatusRegAux..=RegisterAddresses::WhoAmI)
         .contains(&register)
-        || (RegisterAddresses::StatusReg..RegisterAddresses::OutZH)
+        || (RegisterAddresses::StatusReg..=RegisterAddresses::OutZH)
             .contains(&register)
         || RegisterAddresses::FifSrcReg.eq(&register)
         || RegisterAddresses::Int1Src.eq(&register)
@@ -124,6 +212,45 @@ fn is_read_only(address: u8) -> bool {
         || RegisterAddresses::ClickSrc.eq(&register)
 }
 
+/// Registers whose settings are kept by [`Lis3dh`], in the order they are
+/// written to the device.
+pub const MANAGED_REGISTERS: [RegisterAddresses; 10] = [
+    RegisterAddresses::CtrlReg0,
+    RegisterAddresses::CtrlReg1,
+    RegisterAddresses::CtrlReg2,
+    RegisterAddresses::CtrlReg3,
+    RegisterAddresses::CtrlReg4,
+    RegisterAddresses::CtrlReg5,
+    RegisterAddresses::TempCfgReg,
+    RegisterAddresses::Int1Threshold,
+    RegisterAddresses::Int1Duration,
+    RegisterAddresses::Int1Cfg,
+];
+
+/// Registers with per-axis settings that are only kept once written with
+/// [`Lis3dh::write_int2_cfg`], [`Lis3dh::write_click_cfg`] or
+/// [`Lis3dh::write`], so they can be remapped by [`Lis3dh::set_axis_map`].
+const REMAPPED_REGISTERS: [RegisterAddresses; 2] =
+    [RegisterAddresses::Int2Cfg, RegisterAddresses::ClickCfg];
+
+/// Runs `transfer` with the chip selected. Chip select is released even if
+/// the SPI bus fails, so a failed transaction does not leave the sensor
+/// selected.
+fn spi_transaction<CS, SPI, CsE, SpiE, F>(
+    cs: &mut CS,
+    spi: &mut SPI,
+    transfer: F,
+) -> Result<(), Error<CsE, SpiE>>
+where
+    CS: OutputPin<Error = CsE>,
+    F: FnOnce(&mut SPI) -> Result<(), SpiE>,
+{
+    cs.set_low().map_err(Error::ChipSelectError)?;
+    let result = transfer(spi).map_err(Error::SpiError);
+    let released = cs.set_high().map_err(Error::ChipSelectError);
+    result.and(released)
+}
+
 fn check_if_bit_is_set(value: u8, bit_position: u8) -> bool {
     ((value >> bit_position) & 0b1).eq(&0b1)
 }
@@ -140,6 +267,13 @@ pub struct Lis3dh {
     int1_ths: IntThs,
     int1_duration: IntDuration,
     int1_cfg: IntCfg,
+    int2_cfg: Option<IntCfg>,
+    click_cfg: Option<ClickCfg>,
+    dirty_registers: RegisterSet,
+    calibration: Option<Calibration>,
+    axis_map: AxisMap,
+    #[cfg(any(test, feature = "fixed-point"))]
+    sample_transform: fixed_point::SampleTransform,
 }
 
 impl Lis3dh {
@@ -170,48 +304,269 @@ impl Lis3dh {
     pub fn int_1_duration_setting(&self) -> IntDuration {
         self.int1_duration
     }
+    /// Interrupt generator 1 configuration in board axes, see
+    /// [`Lis3dh::set_axis_map`].
     pub fn int_1_cfg_setting(&self) -> IntCfg {
-        self.int1_cfg
+        self.axis_map.int_cfg_to_board(&self.int1_cfg)
+    }
+    pub(crate) fn setting_raw_value(
+        &self,
+        register: RegisterAddresses,
+    ) -> Option<u8> {
+        match register {
+            RegisterAddresses::CtrlReg0 => Some(self.ctrl_reg0.get_raw_value()),
+            RegisterAddresses::CtrlReg1 => Some(self.ctrl_reg1.get_raw_value()),
+            RegisterAddresses::CtrlReg2 => Some(self.ctrl_reg2.get_raw_value()),
+            RegisterAddresses::CtrlReg3 => Some(self.ctrl_reg3.get_raw_value()),
+            RegisterAddresses::CtrlReg4 => Some(self.ctrl_reg4.get_raw_value()),
+            RegisterAddresses::CtrlReg5 => Some(self.ctrl_reg5.get_raw_value()),
+            RegisterAddresses::TempCfgReg => {
+                Some(self.temp_cfg_reg.get_raw_value())
+            }
+            RegisterAddresses::Int1Threshold => {
+                Some(self.int1_ths.get_raw_value())
+            }
+            RegisterAddresses::Int1Duration => {
+                Some(self.int1_duration.get_raw_value())
+            }
+            RegisterAddresses::Int1Cfg => Some(self.int1_cfg.get_raw_value()),
+            RegisterAddresses::Int2Cfg => {
+                self.int2_cfg.map(|cfg| cfg.get_raw_value())
+            }
+            RegisterAddresses::ClickCfg => {
+                self.click_cfg.map(|cfg| cfg.get_raw_value())
+            }
+            _ => None,
+        }
+    }
+    fn store_setting(&mut self, register: RegisterAddresses, raw_value: u8) {
+        let stored = match register {
+            RegisterAddresses::CtrlReg0 => CtrlReg0Value::try_from(raw_value)
+                .map(|value| self.ctrl_reg0 = value),
+            RegisterAddresses::TempCfgReg => {
+                TempCfgRegValue::try_from(raw_value)
+                    .map(|value| self.temp_cfg_reg = value)
+            }
+            RegisterAddresses::CtrlReg1 => CtrlReg1Value::try_from(raw_value)
+                .map(|value| self.ctrl_reg1 = value),
+            RegisterAddresses::CtrlReg2 => CtrlReg2Value::try_from(raw_value)
+                .map(|value| self.ctrl_reg2 = value),
+            RegisterAddresses::CtrlReg3 => CtrlReg3Value::try_from(raw_value)
+                .map(|value| self.ctrl_reg3 = value),
+            RegisterAddresses::CtrlReg4 => CtrlReg4Value::try_from(raw_value)
+                .map(|value| self.ctrl_reg4 = value),
+            RegisterAddresses::CtrlReg5 => CtrlReg5Value::try_from(raw_value)
+                .map(|value| self.ctrl_reg5 = value),
+            RegisterAddresses::Int1Threshold => {
+                IntThs::try_from(raw_value).map(|value| self.int1_ths = value)
+            }
+            RegisterAddresses::Int1Duration => IntDuration::try_from(raw_value)
+                .map(|value| self.int1_duration = value),
+            RegisterAddresses::Int1Cfg => {
+                IntCfg::try_from(raw_value).map(|value| self.int1_cfg = value)
+            }
+            RegisterAddresses::Int2Cfg => IntCfg::try_from(raw_value)
+                .map(|value| self.int2_cfg = Some(value)),
+            RegisterAddresses::ClickCfg => ClickCfg::try_from(raw_value)
+                .map(|value| self.click_cfg = Some(value)),
+            _ => Ok(()),
+        };
+        if stored.is_ok() {
+            self.dirty_registers.remove(register);
+        }
     }
     pub fn set_ctrl_reg0_setting(&mut self, ctrl_reg0: CtrlReg0Value) {
+        if self.ctrl_reg0 != ctrl_reg0 {
+            self.dirty_registers.insert(RegisterAddresses::CtrlReg0);
+        }
         self.ctrl_reg0 = ctrl_reg0;
     }
     pub fn set_temp_cfg_reg_setting(&mut self, temp_cfg_reg: TempCfgRegValue) {
+        if self.temp_cfg_reg != temp_cfg_reg {
+            self.dirty_registers.insert(RegisterAddresses::TempCfgReg);
+        }
         self.temp_cfg_reg = temp_cfg_reg;
     }
     pub fn set_ctrl_reg1_setting(&mut self, ctrl_reg1: CtrlReg1Value) {
+        if self.ctrl_reg1 != ctrl_reg1 {
+            self.dirty_registers.insert(RegisterAddresses::CtrlReg1);
+        }
         self.ctrl_reg1 = ctrl_reg1;
     }
     pub fn set_ctrl_reg2_setting(&mut self, ctrl_reg2: CtrlReg2Value) {
+        if self.ctrl_reg2 != ctrl_reg2 {
+            self.dirty_registers.insert(RegisterAddresses::CtrlReg2);
+        }
         self.ctrl_reg2 = ctrl_reg2;
     }
     pub fn set_ctrl_reg3_setting(&mut self, ctrl_reg3: CtrlReg3Value) {
+        if self.ctrl_reg3 != ctrl_reg3 {
+            self.dirty_registers.insert(RegisterAddresses::CtrlReg3);
+        }
         self.ctrl_reg3 = ctrl_reg3;
     }
     pub fn set_ctrl_reg4_setting(&mut self, ctrl_reg4: CtrlReg4Value) {
+        if self.ctrl_reg4 != ctrl_reg4 {
+            self.dirty_registers.insert(RegisterAddresses::CtrlReg4);
+        }
         self.ctrl_reg4 = ctrl_reg4;
     }
-
     pub fn set_ctrl_reg5_setting(&mut self, ctrl_reg5: CtrlReg5Value) {
+        if self.ctrl_reg5 != ctrl_reg5 {
+            self.dirty_registers.insert(RegisterAddresses::CtrlReg5);
+        }
         self.ctrl_reg5 = ctrl_reg5;
     }
     pub fn set_int1_ths_setting(&mut self, int1_ths: IntThs) {
+        if self.int1_ths != int1_ths {
+            self.dirty_registers
+                .insert(RegisterAddresses::Int1Threshold);
+        }
         self.int1_ths = int1_ths;
     }
     pub fn set_int1_duration_setting(&mut self, int1_duration: IntDuration) {
+        if self.int1_duration != int1_duration {
+            self.dirty_registers.insert(RegisterAddresses::Int1Duration);
+        }
         self.int1_duration = int1_duration;
     }
-    pub fn set_int1_cfg_setting(&mut self, int1_cfg: IntCfg) {
+    /// Sets the interrupt generator 1 configuration with the per-axis
+    /// enables given in board axes, see [`Lis3dh::set_axis_map`]. Fails
+    /// if an axis is enabled and the board axes do not have distinct
+    /// nearest sensor axes.
+    pub fn set_int1_cfg_setting(
+        &mut self,
+        int1_cfg: IntCfg,
+    ) -> Result<(), &'static str> {
+        let int1_cfg = self.axis_map.int_cfg_to_sensor(&int1_cfg)?;
+        if self.int1_cfg != int1_cfg {
+            self.dirty_registers.insert(RegisterAddresses::Int1Cfg);
+        }
         self.int1_cfg = int1_cfg;
+        Ok(())
     }
     pub fn set_output_data_rate(
         &mut self,
         output_data_rate: ctrl_reg_1_value::ODR,
     ) {
-        self.ctrl_reg1.set_output_data_rate(output_data_rate);
+        let mut ctrl_reg1 = self.ctrl_reg1;
+        ctrl_reg1.set_output_data_rate(output_data_rate);
+        self.set_ctrl_reg1_setting(ctrl_reg1);
     }
     pub fn set_l_p_en(&mut self, l_p_en: ctrl_reg_1_value::LPEn) {
-        self.ctrl_reg1.set_l_p_en(l_p_en);
+        let mut ctrl_reg1 = self.ctrl_reg1;
+        ctrl_reg1.set_l_p_en(l_p_en);
+        self.set_ctrl_reg1_setting(ctrl_reg1);
+    }
+    /// Switches the sensor from 4-wire to 3-wire SPI mode.
+    ///
+    /// Pass the transport used for the shared data line, e.g.
+    /// [`three_wire::ThreeWireSpi`]. CTRL_REG4 is written without reading
+    /// it first, as writes only use SDI and are understood in both modes.
+    /// Afterwards the register is read back over the shared line to verify
+    /// the switch. All further communication has to use this transport.
+    pub fn switch_to_three_wire_mode<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<(), Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let mut ctrl_reg4 = self.ctrl_reg4;
+        ctrl_reg4
+            .set_sim(ctrl_reg_4_value::SpiModeSelection::ThreeWireInterface);
+        self.set_ctrl_reg4_setting(ctrl_reg4);
+        self.write_setting(cs, spi, RegisterAddresses::CtrlReg4)?;
+        let written = self.get_ctrl_reg_4_value(cs, spi)?;
+        if written != self.ctrl_reg4 {
+            return Err(Error::InvalidRegisterValue(
+                "CTRL_REG4 read back does not match 3-wire mode setting",
+            ));
+        }
+        Ok(())
+    }
+    /// Sets the mapping from sensor to board axes. It is applied to every
+    /// acceleration sample returned by the driver before the calibration,
+    /// so calibrations are collected and applied in board axes. The
+    /// per-axis enables of [`Lis3dh::set_int1_cfg_setting`],
+    /// [`Lis3dh::write_int2_cfg`] and [`Lis3dh::write_click_cfg`] and the
+    /// sources returned by [`Lis3dh::service_interrupt`] refer to board
+    /// axes as well.
+    ///
+    /// The INT1 configuration and the INT2 and click configurations
+    /// written through the driver keep their meaning in board axes: they
+    /// are remapped, marked dirty if they change and rewritten by
+    /// [`Lis3dh::apply_changes`]. A mapping without distinct nearest axes,
+    /// see [`AxisMap::has_distinct_nearest_axes`], is refused while any of
+    /// them enables an axis.
+    pub fn set_axis_map(
+        &mut self,
+        axis_map: AxisMap,
+    ) -> Result<(), &'static str> {
+        let int1_cfg = axis_map.int_cfg_to_sensor(&self.int_1_cfg_setting())?;
+        let int2_cfg = self
+            .int2_cfg
+            .map(|cfg| {
+                axis_map
+                    .int_cfg_to_sensor(&self.axis_map.int_cfg_to_board(&cfg))
+            })
+            .transpose()?;
+        let click_cfg = self
+            .click_cfg
+            .map(|cfg| {
+                axis_map.click_cfg_to_sensor(
+                    &self.axis_map.click_cfg_to_board(&cfg),
+                )
+            })
+            .transpose()?;
+        if self.int1_cfg != int1_cfg {
+            self.dirty_registers.insert(RegisterAddresses::Int1Cfg);
+        }
+        if self.int2_cfg != int2_cfg {
+            self.dirty_registers.insert(RegisterAddresses::Int2Cfg);
+        }
+        if self.click_cfg != click_cfg {
+            self.dirty_registers.insert(RegisterAddresses::ClickCfg);
+        }
+        self.int1_cfg = int1_cfg;
+        self.int2_cfg = int2_cfg;
+        self.click_cfg = click_cfg;
+        self.axis_map = axis_map;
+        self.update_sample_transform();
+        Ok(())
+    }
+    pub fn axis_map(&self) -> AxisMap {
+        self.axis_map
+    }
+    /// Sets the calibration applied to every acceleration sample in mg
+    /// returned by the driver.
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) {
+        self.calibration = calibration;
+        self.update_sample_transform();
+    }
+    pub fn calibration(&self) -> Option<Calibration> {
+        self.calibration
+    }
+    fn update_sample_transform(&mut self) {
+        #[cfg(any(test, feature = "fixed-point"))]
+        {
+            self.sample_transform = fixed_point::SampleTransform::new(
+                &self.axis_map,
+                self.calibration.as_ref(),
+            );
+        }
+    }
+    fn calibrate(&self, sample: I32x3) -> I32x3 {
+        match &self.calibration {
+            Some(calibration) => calibration.apply(sample),
+            None => sample,
+        }
+    }
+    /// Registers whose settings were modified since they were last written.
+    pub fn dirty_registers(&self) -> RegisterSet {
+        self.dirty_registers
     }
     pub fn write_all_settings<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -222,126 +577,104 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::CtrlReg0 as u8,
-                self.ctrl_reg0.get_raw_value(),
-            ],
-        )?;
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::CtrlReg1 as u8,
-                self.ctrl_reg1.get_raw_value(),
-            ],
-        )?;
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::CtrlReg2 as u8,
-                self.ctrl_reg2.get_raw_value(),
-            ],
-        )?;
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::CtrlReg3 as u8,
-                self.ctrl_reg3.get_raw_value(),
-            ],
-        )?;
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::CtrlReg4 as u8,
-                self.ctrl_reg4.get_raw_value(),
-            ],
-        )?;
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::CtrlReg5 as u8,
-                self.ctrl_reg5.get_raw_value(),
-            ],
-        )?;
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::TempCfgReg as u8,
-                self.temp_cfg_reg.get_raw_value(),
-            ],
-        )?;
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::Int1Threshold as u8,
-                self.int1_ths.get_raw_value(),
-            ],
-        )?;
-        self.write_to_spi(
+        for register in MANAGED_REGISTERS.into_iter().chain(REMAPPED_REGISTERS)
+        {
+            self.write_setting(cs, spi, register)?;
+        }
+        Ok(())
+    }
+    /// Writes only the registers whose settings were modified since they
+    /// were last written and returns them.
+    pub fn apply_changes<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<RegisterSet, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let mut written_registers = RegisterSet::empty();
+        for register in MANAGED_REGISTERS.into_iter().chain(REMAPPED_REGISTERS)
+        {
+            if self.dirty_registers.contains(register) {
+                self.write_setting(cs, spi, register)?;
+                written_registers.insert(register);
+            }
+        }
+        Ok(written_registers)
+    }
+    pub fn check_if_settings_are_written_correctly<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<bool, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        Ok(self.verify_settings(cs, spi)?.is_ok())
+    }
+    /// Reads back every register managed by the driver and compares it with
+    /// the settings, field by field.
+    ///
+    /// Only the managed registers are read, so pending interrupts and
+    /// samples are left untouched: CTRL_REG0 to CTRL_REG5 in one burst and
+    /// the INT1 registers one by one, skipping INT1_SRC.
+    pub fn verify_settings<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<VerificationReport, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        const FIRST: u8 = RegisterAddresses::CtrlReg0 as u8;
+        const LAST: u8 = RegisterAddresses::CtrlReg5 as u8;
+        let mut control_registers = [0xff; (LAST - FIRST + 1) as usize];
+        self.read_multiple_bytes_from_spi(
             cs,
             spi,
-            [
-                RegisterAddresses::Int1Duration as u8,
-                self.int1_duration.get_raw_value(),
-            ],
+            FIRST,
+            &mut control_registers,
         )?;
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::Int1Cfg as u8,
-                self.int1_cfg.get_raw_value(),
-            ],
-        )
+        let mut values = [0xff; MANAGED_REGISTERS.len()];
+        for (value, register) in values.iter_mut().zip(MANAGED_REGISTERS) {
+            *value = match register as u8 {
+                address @ FIRST..=LAST => {
+                    control_registers[(address - FIRST) as usize]
+                }
+                address => self.read_single_byte_from_spi(cs, spi, address)?,
+            };
+        }
+        Ok(VerificationReport::from_values(self, values))
     }
-    pub fn check_if_settings_are_written_correctly<CS, SPI, CsE, SpiE>(
+    /// Writes the expected value of every register that did not match in
+    /// the given report.
+    pub fn rewrite_mismatched_settings<CS, SPI, CsE, SpiE>(
         &mut self,
         cs: &mut CS,
         spi: &mut SPI,
-    ) -> Result<bool, Error<CsE, SpiE>>
+        report: &VerificationReport,
+    ) -> Result<(), Error<CsE, SpiE>>
     where
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        Ok(self
-            .ctrl_reg0_setting()
-            .eq(&self.get_ctrl_reg_0_value(cs, spi)?)
-            && self
-                .ctrl_reg1_setting()
-                .eq(&self.get_ctrl_reg_1_value(cs, spi)?)
-            && self
-                .ctrl_reg2_setting()
-                .eq(&self.get_ctrl_reg_2_value(cs, spi)?)
-            && self
-                .ctrl_reg3_setting()
-                .eq(&self.get_ctrl_reg_3_value(cs, spi)?)
-            && self
-                .ctrl_reg4_setting()
-                .eq(&self.get_ctrl_reg_4_value(cs, spi)?)
-            && self
-                .ctrl_reg5_setting()
-                .eq(&self.get_ctrl_reg_5_value(cs, spi)?)
-            && self
-                .temp_cfg_reg_setting()
-                .eq(&self.get_temp_cfg_reg(cs, spi)?)
-            && self
-                .int_1_ths_setting()
-                .eq(&self.get_int_1_ths_values(cs, spi)?)
-            && self
-                .int_1_duration_setting()
-                .eq(&self.get_int_1_duration_values(cs, spi)?)
-            && self
-                .int_1_cfg_setting()
-                .eq(&self.get_int_1_cfg_values(cs, spi)?))
+        for register in report.mismatches() {
+            self.write_to_spi(
+                cs,
+                spi,
+                [register.register() as u8, register.expected()],
+            )?;
+            if self.setting_raw_value(register.register())
+                == Some(register.expected())
+            {
+                self.dirty_registers.remove(register.register());
+            }
+        }
+        Ok(())
     }
     pub fn rewrite_int1_settings<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -352,14 +685,71 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        self.write_to_spi(
-            cs,
-            spi,
-            [
-                RegisterAddresses::Int1Cfg as u8,
-                self.int1_cfg.get_raw_value(),
-            ],
-        )
+        self.write_setting(cs, spi, RegisterAddresses::Int1Cfg)
+    }
+    /// Reads and decodes the register `R`, e.g.
+    /// `let ctrl_reg1: CtrlReg1Value = lis3dh.read(&mut cs, &mut spi)?;`
+    ///
+    /// Values are returned as stored on the device, so per-axis fields such
+    /// as the enables of [`IntCfg`] refer to sensor axes; the axis map is
+    /// only applied by the dedicated methods, e.g.
+    /// [`Lis3dh::get_int_1_cfg_values`].
+    pub fn read<R, CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<R, Error<CsE, SpiE>>
+    where
+        R: Register,
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let value =
+            self.read_single_byte_from_spi(cs, spi, R::ADDRESS as u8)?;
+        R::try_from(value).map_err(Error::InvalidRegisterValue)
+    }
+    /// Writes the register `R`. If the register is managed by the driver
+    /// its setting is updated as well.
+    ///
+    /// Per-axis fields are taken in sensor axes, see [`Lis3dh::read`];
+    /// use e.g. [`Lis3dh::set_int1_cfg_setting`] for board axes.
+    pub fn write<R, CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        value: R,
+    ) -> Result<(), Error<CsE, SpiE>>
+    where
+        R: Register,
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        if R::ACCESS_MODE == AccessMode::ReadOnly {
+            return Err(Error::AttemptToWriteToReadOnlyRegister);
+        }
+        let raw_value = value.raw_value();
+        self.write_to_spi(cs, spi, [R::ADDRESS as u8, raw_value])?;
+        self.store_setting(R::ADDRESS, raw_value);
+        Ok(())
+    }
+    /// Reads the register `R`, lets `modify` change it and writes it back.
+    /// Per-axis fields are in sensor axes, see [`Lis3dh::read`].
+    pub fn modify<R, F, CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        modify: F,
+    ) -> Result<R, Error<CsE, SpiE>>
+    where
+        R: Register,
+        F: FnOnce(&mut R),
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let mut value: R = self.read(cs, spi)?;
+        modify(&mut value);
+        self.write(cs, spi, value)?;
+        Ok(value)
     }
     pub fn get_ctrl_reg_5_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -370,12 +760,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::CtrlReg5 as u8,
-        )?;
-        Ok(CtrlReg5Value::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_ctrl_reg_4_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -386,12 +771,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::CtrlReg4 as u8,
-        )?;
-        Ok(CtrlReg4Value::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_ctrl_reg_3_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -402,12 +782,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::CtrlReg3 as u8,
-        )?;
-        Ok(CtrlReg3Value::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_ctrl_reg_2_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -418,12 +793,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::CtrlReg2 as u8,
-        )?;
-        Ok(CtrlReg2Value::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_temp_cfg_reg<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -434,12 +804,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::TempCfgReg as u8,
-        )?;
-        Ok(TempCfgRegValue::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_ctrl_reg_0_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -450,12 +815,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::CtrlReg0 as u8,
-        )?;
-        Ok(CtrlReg0Value::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_ctrl_reg_1_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -466,12 +826,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::CtrlReg1 as u8,
-        )?;
-        Ok(CtrlReg1Value::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_status_reg_aux_values<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -482,12 +837,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::StatusRegAux as u8,
-        )?;
-        Ok(StatusRegAuxValue::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_int_1_ths_values<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -498,12 +848,7 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::Int1Threshold as u8,
-        )?;
-        Ok(IntThs::from_raw_value(value))
+        self.read(cs, spi)
     }
     pub fn get_int_1_duration_values<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -514,13 +859,10 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::Int1Duration as u8,
-        )?;
-        Ok(IntDuration::from_raw_value(value))
+        self.read(cs, spi)
     }
+    /// Reads INT1_CFG with the per-axis enables in board axes, see
+    /// [`Lis3dh::set_axis_map`].
     pub fn get_int_1_cfg_values<CS, SPI, CsE, SpiE>(
         &mut self,
         cs: &mut CS,
@@ -530,13 +872,11 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::Int1Cfg as u8,
-        )?;
-        Ok(IntCfg::from_raw_value(value))
+        let cfg: IntCfg = self.read(cs, spi)?;
+        Ok(self.axis_map.int_cfg_to_board(&cfg))
     }
+    /// Reads INT1_SRC with the per-axis flags in board axes, see
+    /// [`Lis3dh::set_axis_map`].
     pub fn get_int_1_src_values<CS, SPI, CsE, SpiE>(
         &mut self,
         cs: &mut CS,
@@ -546,12 +886,8 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let value = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::Int1Src as u8,
-        )?;
-        Ok(IntSrc::from_raw_value(value))
+        let src: IntSrc = self.read(cs, spi)?;
+        Ok(self.axis_map.int_src_to_board(&src, &self.int1_cfg))
     }
     pub fn get_adc1_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -625,81 +961,124 @@ impl Lis3dh {
 
         Ok(AngleAnd1GOffset::new(angle_to_z as u16, offset as u16))
     }
-    pub fn get_accel_norm<CS, SPI, CsE, SpiE>(
+    /// Integer counterpart of [`Lis3dh::get_angle_and_gravity_offset`] for
+    /// microcontrollers without FPU. The inclination is rounded to whole
+    /// degrees instead of truncated.
+    #[cfg(any(test, feature = "fixed-point"))]
+    pub fn get_angle_and_gravity_offset_fixed<CS, SPI, CsE, SpiE>(
         &mut self,
         cs: &mut CS,
         spi: &mut SPI,
-    ) -> Result<I32x3, Error<CsE, SpiE>>
+    ) -> Result<AngleAnd1GOffset, Error<CsE, SpiE>>
     where
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let mode = self.get_mode(cs, spi)?;
-        let range = self.get_ctrl_reg_4_value(cs, spi)?.fs();
-
-        let multiplier = match (mode, range) {
-            (
-                mode::Mode::HighResolution,
-                ctrl_reg_4_value::FullScaleSelection::Gravity2G,
-            ) => 1,
-            (
-                mode::Mode::HighResolution,
-                ctrl_reg_4_value::FullScaleSelection::Gravity4G,
-            ) => 1,
-            (
-                mode::Mode::HighResolution,
-                ctrl_reg_4_value::FullScaleSelection::Gravity8G,
-            ) => 4,
-            (
-                mode::Mode::HighResolution,
-                ctrl_reg_4_value::FullScaleSelection::Gravity16G,
-            ) => 12,
-            (
-                mode::Mode::Normal,
-                ctrl_reg_4_value::FullScaleSelection::Gravity2G,
-            ) => 4,
-            (
-                mode::Mode::Normal,
-                ctrl_reg_4_value::FullScaleSelection::Gravity4G,
-            ) => 8,
-            (
-                mode::Mode::Normal,
-                ctrl_reg_4_value::FullScaleSelection::Gravity8G,
-            ) => 16,
-            (
-                mode::Mode::Normal,
-                ctrl_reg_4_value::FullScaleSelection::Gravity16G,
-            ) => 48,
-            (
-                mode::Mode::LowPower,
-                ctrl_reg_4_value::FullScaleSelection::Gravity2G,
-            ) => 16,
-            (
-                mode::Mode::LowPower,
-                ctrl_reg_4_value::FullScaleSelection::Gravity4G,
-            ) => 32,
-            (
-                mode::Mode::LowPower,
-                ctrl_reg_4_value::FullScaleSelection::Gravity8G,
-            ) => 64,
-            (
-                mode::Mode::LowPower,
-                ctrl_reg_4_value::FullScaleSelection::Gravity16G,
-            ) => 192,
-        };
+        let tilt = self.get_tilt_fixed(cs, spi)?;
+        let angle_to_z = (tilt.inclination() + 50) / 100;
+        let offset = tilt.magnitude().abs_diff(1000);
 
-        let shift: u8 = match mode {
-            mode::Mode::HighResolution => 4, // High Resolution:  12-bit
-            mode::Mode::Normal => 6,         // Normal:           10-bit
-            mode::Mode::LowPower => 8,       // Low Power:         8-bit
-        };
+        Ok(AngleAnd1GOffset::new(angle_to_z as u16, offset as u16))
+    }
+    /// Returns the calibrated acceleration in mg like
+    /// [`Lis3dh::get_accel_norm`], transformed to board axes and calibrated
+    /// with integers only, see [`fixed_point::SampleTransform`].
+    #[cfg(any(test, feature = "fixed-point"))]
+    pub fn get_accel_norm_fixed<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<I32x3, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let format = self.get_sample_format(cs, spi)?;
+        let acc_raw = self.get_accel_raw(cs, spi, &format)?;
 
-        let acc_raw = self.get_accel_raw(cs, spi)?;
-        let x = (acc_raw.x >> shift) as i32 * multiplier;
-        let y = (acc_raw.y >> shift) as i32 * multiplier;
-        let z = (acc_raw.z >> shift) as i32 * multiplier;
+        Ok(self.sample_transform.apply(format.normalize(acc_raw)))
+    }
+    /// Returns roll, pitch and inclination of the calibrated acceleration
+    /// calculated with integers only.
+    #[cfg(any(test, feature = "fixed-point"))]
+    pub fn get_tilt_fixed<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<fixed_point::FixedTilt, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let sample = self.get_accel_norm_fixed(cs, spi)?;
+        Ok(fixed_point::FixedTilt::new(
+            sample,
+            fixed_point::DEFAULT_TOLERANCE_MG,
+        ))
+    }
+    /// Returns roll, pitch and inclination of the calibrated acceleration,
+    /// relative to the orientation tared in `calculator`.
+    pub fn get_tilt<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        calculator: &TiltCalculator,
+    ) -> Result<Tilt, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let sample = self.get_accel_norm(cs, spi)?;
+        Ok(calculator.tilt(sample))
+    }
+    /// Returns the calibrated acceleration in mg. Samples corrupted on the
+    /// bus are only refused if they set bits the sensor leaves zero, see
+    /// [`SampleFormat::check_raw`]; flipped data bits and a MISO line stuck
+    /// low are returned as valid samples.
+    pub fn get_accel_norm<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<I32x3, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let sample = self.get_accel_norm_uncalibrated(cs, spi)?;
+        Ok(self.calibrate(sample))
+    }
+    /// Returns the calibrated acceleration with the time of `clock` at the
+    /// start of the read.
+    pub fn get_accel_norm_timestamped<CS, SPI, CsE, SpiE, C>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        clock: &mut C,
+    ) -> Result<TimestampedSample, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+        C: Clock,
+    {
+        let timestamp_us = clock.now_us();
+        let sample = self.get_accel_norm(cs, spi)?;
+        Ok(TimestampedSample::new(sample, timestamp_us))
+    }
+    /// Returns the acceleration in mg without applying the calibration,
+    /// e.g. to collect samples for a new calibration.
+    pub fn get_accel_norm_uncalibrated<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<I32x3, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let format = self.get_sample_format(cs, spi)?;
+        let acc_raw = self.get_accel_raw(cs, spi, &format)?;
 
-        Ok(I32x3 { x, y, z })
+        Ok(self.axis_map.apply(format.normalize(acc_raw)))
     }
     fn get_adc_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -712,12 +1091,13 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let low_byte =
+        let endianness = self.get_ctrl_reg_4_value(cs, spi)?.ble();
+        let lower_address_byte =
             self.read_single_byte_from_spi(cs, spi, low_byte_address)?;
-        let high_byte =
+        let higher_address_byte =
             self.read_single_byte_from_spi(cs, spi, high_byte_address)?;
 
-        Ok(((high_byte as u16) << 8) | low_byte as u16)
+        Ok(endianness.decode_u16(lower_address_byte, higher_address_byte))
     }
     pub fn get_register_raw_value<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -731,6 +1111,55 @@ impl Lis3dh {
     {
         self.read_single_byte_from_spi(cs, spi, address as u8)
     }
+    /// Writes a raw value to a register. Read-only registers are refused,
+    /// as are values that would change bits the datasheet requires to keep
+    /// fixed, e.g. the lower 7 bit of CTRL_REG0 or bit 7 of TIME_LIMIT.
+    pub fn set_register_raw_value<CS, SPI, CsE, SpiE>(
+        &mut self,
+        address: RegisterAddresses,
+        value: u8,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<(), Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        if is_read_only(address as u8) {
+            return Err(Error::AttemptToWriteToReadOnlyRegister);
+        }
+        register::check_raw_value(address, value)
+            .map_err(Error::InvalidRegisterValue)?;
+        self.write_to_spi(cs, spi, [address as u8, value])?;
+        self.store_setting(address, value);
+        Ok(())
+    }
+    /// Reads every register from STATUS_REG_AUX (0x07) to ACT_DUR (0x3F)
+    /// in one burst.
+    ///
+    /// The burst has the side effects of reading these registers: latched
+    /// interrupts in INT1_SRC, INT2_SRC and CLICK_SRC are cleared, and
+    /// reading OUT_X_L to OUT_Z_H consumes a sample, popping it from the
+    /// FIFO if enabled. Use [`Lis3dh::verify_settings`] to check the
+    /// settings without these side effects.
+    pub fn get_register_snapshot<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<RegisterSnapshot, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let mut values = [0xff; SNAPSHOT_LENGTH];
+        self.read_multiple_bytes_from_spi(
+            cs,
+            spi,
+            register_snapshot::SNAPSHOT_FIRST_REGISTER as u8,
+            &mut values,
+        )?;
+        Ok(RegisterSnapshot::new(values))
+    }
     pub fn get_who_am_i<CS, SPI, CsE, SpiE>(
         &mut self,
         cs: &mut CS,
@@ -752,11 +1181,52 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let mut read_buffer = [address_to_read | SPI_READ_BIT, 0xff];
-        cs.set_low().map_err(Error::ChipSelectError)?;
-        spi.transfer(&mut read_buffer).map_err(Error::SpiError)?;
-        cs.set_high().map_err(Error::ChipSelectError)?;
-        Ok(read_buffer[1])
+        if is_reserved(address_to_read) {
+            return Err(Error::AttemptToAccessReservedRegister);
+        }
+        let mut read_buffer = [0xff];
+        spi_transaction(cs, spi, |spi| {
+            spi.write(&[address_to_read | SPI_READ_BIT])?;
+            spi.transfer(&mut read_buffer).map(drop)
+        })?;
+        Ok(read_buffer[0])
+    }
+    fn write_setting<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        register: RegisterAddresses,
+    ) -> Result<(), Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        if let Some(value) = self.setting_raw_value(register) {
+            self.write_to_spi(cs, spi, [register as u8, value])?;
+            self.dirty_registers.remove(register);
+        }
+        Ok(())
+    }
+    fn read_multiple_bytes_from_spi<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        first_address_to_read: u8,
+        buffer: &mut [u8],
+    ) -> Result<(), Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        if is_reserved(first_address_to_read) {
+            return Err(Error::AttemptToAccessReservedRegister);
+        }
+        spi_transaction(cs, spi, |spi| {
+            spi.write(&[first_address_to_read
+                | SPI_READ_BIT
+                | SPI_AUTO_INCREMENT_BIT])?;
+            spi.transfer(buffer).map(drop)
+        })
     }
     fn write_to_spi<CS, SPI, CsE, SpiE>(
         &mut self,
@@ -768,36 +1238,288 @@ impl Lis3dh {
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        if is_read_only(*data.first().unwrap()) {
+        if is_reserved(data[0]) {
+            return Err(Error::AttemptToAccessReservedRegister);
+        }
+        if is_read_only(data[0]) {
             return Err(Error::AttemptToWriteToReadOnlyRegister);
         }
-        cs.set_low().map_err(Error::ChipSelectError)?;
-        spi.write(&data).map_err(Error::SpiError)?;
-        cs.set_high().map_err(Error::ChipSelectError)?;
-        Ok(())
+        spi_transaction(cs, spi, |spi| spi.write(&data))
     }
-    fn get_mode<CS, SPI, CsE, SpiE>(
+    /// Reads CTRL_REG1 and CTRL_REG4 to determine how the acceleration
+    /// output registers have to be decoded.
+    pub fn get_sample_format<CS, SPI, CsE, SpiE>(
         &mut self,
         cs: &mut CS,
         spi: &mut SPI,
-    ) -> Result<mode::Mode, Error<CsE, SpiE>>
+    ) -> Result<SampleFormat, Error<CsE, SpiE>>
     where
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let low_power_set = self.get_ctrl_reg_1_value(cs, spi)?.l_p_en()
-            == ctrl_reg_1_value::LPEn::LowPowerEnabled;
-        let high_resolution_output_set =
-            self.get_ctrl_reg_4_value(cs, spi)?.hr()
-                == enabled_enum::OnOff::Enabled;
-
-        let mode = match (low_power_set, high_resolution_output_set) {
-            (true, false) => mode::Mode::LowPower,
-            (false, false) => mode::Mode::Normal,
-            (false, true) => mode::Mode::HighResolution,
-            _ => panic!("impossible mode"),
+        let ctrl_reg1 = self.get_ctrl_reg_1_value(cs, spi)?;
+        let ctrl_reg4 = self.get_ctrl_reg_4_value(cs, spi)?;
+        SampleFormat::from_settings(&ctrl_reg1, &ctrl_reg4)
+            .map_err(Error::InvalidRegisterValue)
+    }
+    pub fn get_fifo_src_value<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<FifoSrcRegValue, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        self.read(cs, spi)
+    }
+    /// Reads the unread calibrated FIFO samples in mg into `samples` and
+    /// returns the number of samples read. Samples not fitting into
+    /// `samples` stay in the FIFO. Samples failing the plausibility check
+    /// do not stop the read, they are reported with
+    /// [`Error::CorruptedFifoSamples`] once the others were read.
+    pub fn get_accel_norm_from_fifo<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        samples: &mut [I32x3],
+    ) -> Result<usize, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let format = self.get_sample_format(cs, spi)?;
+        let unread_samples =
+            self.get_fifo_src_value(cs, spi)?.unread_samples() as usize;
+        let count = unread_samples.min(samples.len());
+        let mut corrupted = 0_u32;
+        for (index, sample) in samples.iter_mut().take(count).enumerate() {
+            let mut bytes = [0xff; SAMPLE_SIZE];
+            self.read_multiple_bytes_from_spi(
+                cs,
+                spi,
+                RegisterAddresses::OutXL as u8,
+                &mut bytes,
+            )?;
+            let raw = format.decode_raw(&bytes);
+            if format.check_raw(raw).is_err() {
+                corrupted |= 1 << index;
+            }
+            *sample =
+                self.calibrate(self.axis_map.apply(format.normalize(raw)));
+        }
+        if corrupted != 0 {
+            return Err(Error::CorruptedFifoSamples {
+                read: count,
+                corrupted,
+            });
+        }
+        Ok(count)
+    }
+    /// Reads all unread calibrated FIFO samples like
+    /// [`Lis3dh::get_accel_norm_from_fifo`] and timestamps them.
+    ///
+    /// `watermark_time_us` is the time the watermark interrupt fired, i.e.
+    /// when the sample with the index of the FIFO threshold was taken. The
+    /// times of the other samples are calculated from it with the period of
+    /// `sample_rate_millihertz`, e.g. measured with
+    /// [`rate_estimator::RateEstimator`], or of the data rate configured in
+    /// CTRL_REG1 if it is `None`.
+    ///
+    /// The times are only right if the FIFO is empty after every call, so
+    /// nothing is read if `samples` is shorter than the unread samples.
+    /// Drain the FIFO before it overruns in Stream mode as well, otherwise
+    /// discarded samples shift the times.
+    pub fn get_accel_norm_from_fifo_timestamped<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        watermark_time_us: u64,
+        sample_rate_millihertz: Option<u32>,
+        samples: &mut [TimestampedSample],
+    ) -> Result<usize, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let sample_rate_millihertz = match sample_rate_millihertz {
+            Some(0) => {
+                return Err(Error::InvalidArgument(
+                    "Sample rate must not be zero",
+                ))
+            }
+            Some(millihertz) => millihertz,
+            None => {
+                self.get_ctrl_reg_1_value(cs, spi)?
+                    .sample_rate_hz()
+                    .map_err(Error::InvalidRegisterValue)?
+                    * 1000
+            }
         };
-        Ok(mode)
+        let fifo_ctrl: FifoCtrlRegValue = self.read(cs, spi)?;
+        let unread_samples =
+            self.get_fifo_src_value(cs, spi)?.unread_samples() as usize;
+        if samples.len() < unread_samples {
+            return Err(Error::InvalidArgument(
+                "Buffer is shorter than the unread FIFO samples",
+            ));
+        }
+        let mut buffer = [I32x3 { x: 0, y: 0, z: 0 }; FIFO_DEPTH as usize];
+        let (count, corrupted) =
+            split_corrupted(self.get_accel_norm_from_fifo(
+                cs,
+                spi,
+                &mut buffer[..unread_samples],
+            ))?;
+        for (index, (timestamped, sample)) in
+            samples.iter_mut().zip(&buffer[..count]).enumerate()
+        {
+            let timestamp_us = timestamp::fifo_sample_time_us(
+                watermark_time_us,
+                fifo_ctrl.threshold() as usize,
+                index,
+                sample_rate_millihertz,
+            );
+            *timestamped = TimestampedSample::new(*sample, timestamp_us);
+        }
+        if corrupted != 0 {
+            return Err(Error::CorruptedFifoSamples {
+                read: count,
+                corrupted,
+            });
+        }
+        Ok(count)
+    }
+    /// Writes INT2_CFG with the per-axis enables given in board axes, see
+    /// [`Lis3dh::set_axis_map`].
+    pub fn write_int2_cfg<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        int2_cfg: IntCfg,
+    ) -> Result<(), Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let int2_cfg = self
+            .axis_map
+            .int_cfg_to_sensor(&int2_cfg)
+            .map_err(Error::InvalidArgument)?;
+        self.write(cs, spi, Int2(int2_cfg))
+    }
+    /// Writes CLICK_CFG with the click axes given in board axes, see
+    /// [`Lis3dh::set_axis_map`].
+    pub fn write_click_cfg<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        click_cfg: ClickCfg,
+    ) -> Result<(), Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let click_cfg = self
+            .axis_map
+            .click_cfg_to_sensor(&click_cfg)
+            .map_err(Error::InvalidArgument)?;
+        self.write(cs, spi, click_cfg)
+    }
+    /// Finds out why `pin` was raised. Only the source registers of the
+    /// events routed to `pin` in CTRL_REG3 or CTRL_REG6 are read, which
+    /// clears latched interrupt generator and click interrupts.
+    ///
+    /// Call it only after `pin` was actually raised. The activity function
+    /// has no source register, so it is never among the events; instead
+    /// [`ServicedInterrupt::is_activity_inferred`] is set if `pin` is INT2,
+    /// activity is routed to it and no other event routed to INT2 is
+    /// active. Polling without an interrupt, or after a latched source was
+    /// already cleared, sets it as well.
+    pub fn service_interrupt<CS, SPI, CsE, SpiE>(
+        &mut self,
+        cs: &mut CS,
+        spi: &mut SPI,
+        pin: InterruptPin,
+    ) -> Result<ServicedInterrupt, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let ctrl_reg3: CtrlReg3Value = self.read(cs, spi)?;
+        let ctrl_reg6: CtrlReg6Value = self.read(cs, spi)?;
+        let routed = InterruptEvents::routed_to(pin, &ctrl_reg3, &ctrl_reg6);
+        let mut serviced = ServicedInterrupt::default();
+        if routed.contains(InterruptEvent::DataReady) {
+            let status: StatusRegValue = self.read(cs, spi)?;
+            if status.has_zyx_new_data() {
+                serviced.events.insert(InterruptEvent::DataReady);
+            }
+            serviced.status = Some(status);
+        }
+        if routed.contains(InterruptEvent::Watermark)
+            || routed.contains(InterruptEvent::Overrun)
+        {
+            let fifo_src = self.get_fifo_src_value(cs, spi)?;
+            if routed.contains(InterruptEvent::Watermark)
+                && fifo_src.is_watermark_reached()
+            {
+                serviced.events.insert(InterruptEvent::Watermark);
+            }
+            if routed.contains(InterruptEvent::Overrun) && fifo_src.is_overrun()
+            {
+                serviced.events.insert(InterruptEvent::Overrun);
+            }
+            serviced.fifo_src = Some(fifo_src);
+        }
+        let int1 = if routed.contains(InterruptEvent::Ia1) {
+            Some((
+                self.read::<IntCfg, _, _, _, _>(cs, spi)?,
+                self.read::<IntSrc, _, _, _, _>(cs, spi)?,
+            ))
+        } else {
+            None
+        };
+        let int2 = if routed.contains(InterruptEvent::Ia2) {
+            Some((
+                self.read::<Int2<IntCfg>, _, _, _, _>(cs, spi)?.0,
+                self.read::<Int2<IntSrc>, _, _, _, _>(cs, spi)?.0,
+            ))
+        } else {
+            None
+        };
+        for (generator, event) in
+            [(int1, InterruptEvent::Ia1), (int2, InterruptEvent::Ia2)]
+        {
+            let (cfg, src) = match generator {
+                Some((cfg, src)) if src.is_interrupt_active() => (cfg, src),
+                _ => continue,
+            };
+            serviced.events.insert(event);
+            if cfg.six_d() == OnOff::Enabled {
+                serviced.events.insert(InterruptEvent::Position);
+                serviced.position = interrupt_events::position(
+                    &self.axis_map.int_src_to_board(&src, &cfg),
+                );
+            }
+        }
+        let axis_map = self.axis_map;
+        let to_board = |(cfg, src): (IntCfg, IntSrc)| {
+            axis_map.int_src_to_board(&src, &cfg)
+        };
+        serviced.int1_src = int1.map(to_board);
+        serviced.int2_src = int2.map(to_board);
+        if routed.contains(InterruptEvent::Click) {
+            let click_src: ClickSrc = self.read(cs, spi)?;
+            if click_src.is_interrupt_active() {
+                serviced.events.insert(InterruptEvent::Click);
+            }
+            serviced.click_src =
+                Some(self.axis_map.click_src_to_board(&click_src));
+        }
+        serviced.activity_inferred = routed.contains(InterruptEvent::Activity)
+            && serviced.events.is_empty();
+        Ok(serviced)
     }
 
     pub fn get_reference_value<CS, SPI, CsE, SpiE>(
@@ -805,11 +1527,11 @@ impl Lis3dh {
         cs: &mut CS,
         spi: &mut SPI,
     ) -> Result<u8, Error<CsE, SpiE>>
-        where
-            CS: OutputPin<Error = CsE>,
-            SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-       self.read_single_byte_from_spi(
+        self.read_single_byte_from_spi(
             cs,
             spi,
             RegisterAddresses::Reference as u8,
@@ -820,47 +1542,23 @@ impl Lis3dh {
         &mut self,
         cs: &mut CS,
         spi: &mut SPI,
+        format: &SampleFormat,
     ) -> Result<I16x3, Error<CsE, SpiE>>
     where
         CS: OutputPin<Error = CsE>,
         SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
     {
-        let x_lo = self.read_single_byte_from_spi(
+        let mut bytes = [0xff; SAMPLE_SIZE];
+        self.read_multiple_bytes_from_spi(
             cs,
             spi,
             RegisterAddresses::OutXL as u8,
+            &mut bytes,
         )?;
-        let x_hi = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::OutXH as u8,
-        )?;
-        let y_lo = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::OutYL as u8,
-        )?;
-        let y_hi = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::OutYH as u8,
-        )?;
-        let z_lo = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::OutZL as u8,
-        )?;
-        let z_hi = self.read_single_byte_from_spi(
-            cs,
-            spi,
-            RegisterAddresses::OutZH as u8,
-        )?;
+        let raw = format.decode_raw(&bytes);
+        format.check_raw(raw).map_err(Error::InvalidRegisterValue)?;
 
-        let x = i16::from_le_bytes([x_lo, x_hi]);
-        let y = i16::from_le_bytes([y_lo, y_hi]);
-        let z = i16::from_le_bytes([z_lo, z_hi]);
-
-        Ok(I16x3 { x, y, z })
+        Ok(raw)
     }
 }
 
@@ -881,5 +1579,42 @@ mod tests {
         assert!(!super::is_read_only(
             super::RegisterAddresses::CtrlReg1 as u8
         ));
+        assert!(super::is_read_only(super::RegisterAddresses::WhoAmI as u8));
+        assert!(super::is_read_only(super::RegisterAddresses::OutZH as u8));
+        assert!(!super::is_read_only(
+            super::RegisterAddresses::FifoCtrlReg as u8
+        ));
+    }
+
+    #[test]
+    fn setters_mark_modified_registers_as_dirty() {
+        let mut lis3dh = super::Lis3dh::default();
+        assert!(lis3dh.dirty_registers().is_empty());
+        lis3dh.set_ctrl_reg0_setting(super::CtrlReg0Value::default());
+        assert!(lis3dh.dirty_registers().is_empty());
+        lis3dh.set_output_data_rate(super::ctrl_reg_1_value::ODR::Hz100);
+        let mut int1_ths = super::IntThs::default();
+        int1_ths.set_threshold(0x10).unwrap();
+        lis3dh.set_int1_ths_setting(int1_ths);
+        let mut dirty_registers = lis3dh.dirty_registers().iter();
+        assert_eq!(
+            dirty_registers.next(),
+            Some(super::RegisterAddresses::CtrlReg1)
+        );
+        assert_eq!(
+            dirty_registers.next(),
+            Some(super::RegisterAddresses::Int1Threshold)
+        );
+        assert_eq!(dirty_registers.next(), None);
+    }
+
+    #[test]
+    fn reserved_addresses_are_detected() {
+        assert!(super::is_reserved(0x00));
+        assert!(super::is_reserved(0x0E));
+        assert!(super::is_reserved(0x1D));
+        assert!(super::is_reserved(0x40));
+        assert!(!super::is_reserved(super::RegisterAddresses::WhoAmI as u8));
+        assert!(!super::is_read_only(0x10));
     }
 }
@@ -0,0 +1,129 @@
+use core::f32::consts::{FRAC_PI_2, PI};
+use micromath::F32Ext;
+
+/// Square root accurate to a few ULP. The approximation of `micromath`
+/// deviates by up to 5 % and is refined with Newton iterations.
+pub(crate) fn sqrt(value: f32) -> f32 {
+    if value <= 0.0 {
+        return if value == 0.0 { 0.0 } else { f32::NAN };
+    }
+    let mut root = F32Ext::sqrt(value);
+    for _ in 0..3 {
+        root = 0.5 * (root + value / root);
+    }
+    root
+}
+
+/// Integer square root, rounded down.
+pub fn isqrt(value: u64) -> u64 {
+    let mut remainder = value;
+    let mut root = 0;
+    let mut bit = 1 << 62;
+    while bit > value {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if remainder >= root + bit {
+            remainder -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+/// Four-quadrant arctangent in radians with an error below 0.001°.
+pub(crate) fn atan2(y: f32, x: f32) -> f32 {
+    if x == 0.0 && y == 0.0 {
+        return 0.0;
+    }
+    let (abs_x, abs_y) = (F32Ext::abs(x), F32Ext::abs(y));
+    let mut angle = if abs_x >= abs_y {
+        atan_of_unit(abs_y / abs_x)
+    } else {
+        FRAC_PI_2 - atan_of_unit(abs_x / abs_y)
+    };
+    if x < 0.0 {
+        angle = PI - angle;
+    }
+    if y < 0.0 {
+        -angle
+    } else {
+        angle
+    }
+}
+
+/// Sine and cosine of an angle in radians with an error below 1e-6.
+pub(crate) fn sin_cos(angle: f32) -> (f32, f32) {
+    // Reduce to a quarter turn around a multiple of 90°.
+    let quarter = F32Ext::round(angle / FRAC_PI_2);
+    let x = angle - quarter * FRAC_PI_2;
+    let x2 = x * x;
+    let sin = x * (1.0 - x2 / 6.0 * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0)));
+    let cos = 1.0
+        - x2 / 2.0 * (1.0 - x2 / 12.0 * (1.0 - x2 / 30.0 * (1.0 - x2 / 56.0)));
+    match (quarter as i32).rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+/// Minimax polynomial approximation of `atan(z)` for `z` in `[0, 1]`.
+fn atan_of_unit(z: f32) -> f32 {
+    let z2 = z * z;
+    z * (0.999_977_3
+        + z2 * (-0.332_623_5
+            + z2 * (0.193_543_5
+                + z2 * (-0.116_432_9 + z2 * (0.052_653_3 - z2 * 0.011_721_2)))))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_6, PI};
+    use micromath::F32Ext;
+
+    #[test]
+    fn sine_and_cosine_are_accurate() {
+        let cases = [
+            (0.0, 0.0, 1.0),
+            (FRAC_PI_6, 0.5, 0.866_025),
+            (-4.0 * FRAC_PI_6, -0.866_025, -0.5),
+            (2.25 * PI, FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        ];
+        for (angle, sin, cos) in cases {
+            let (actual_sin, actual_cos) = super::sin_cos(angle);
+            assert!(F32Ext::abs(actual_sin - sin) < 1e-6);
+            assert!(F32Ext::abs(actual_cos - cos) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn square_root_is_accurate() {
+        for value in [2.0_f32, 1_000_000.0, 0.25, 1234.5] {
+            let root = super::sqrt(value);
+            assert!(F32Ext::abs(root * root - value) / value < 1e-6);
+        }
+        assert_eq!(super::sqrt(0.0), 0.0);
+        assert!(super::sqrt(-1.0).is_nan());
+    }
+
+    #[test]
+    fn arctangent_is_quadrant_correct() {
+        let degrees = |y, x| super::atan2(y, x).to_degrees();
+        for (y, x, expected) in [
+            (1.0, 1.0, 45.0),
+            (1.0, -1.0, 135.0),
+            (-1.0, -1.0, -135.0),
+            (-1.0, 1.0, -45.0),
+            (1.0, 0.0, 90.0),
+            (0.5, 0.866_025_4, 30.0),
+            (0.0, -1.0, 180.0),
+        ] {
+            assert!(F32Ext::abs(degrees(y, x) - expected) < 0.001);
+        }
+    }
+}
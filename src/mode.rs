@@ -1,3 +1,7 @@
+use crate::ctrl_reg_1_value::{CtrlReg1Value, LPEn};
+use crate::ctrl_reg_4_value::CtrlReg4Value;
+use crate::enabled_enum::OnOff;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(u8)]
 pub enum Mode {
@@ -8,3 +12,22 @@ pub enum Mode {
     /// Low-power mode (8-bit data output)
     LowPower,
 }
+
+impl Mode {
+    pub fn from_settings(
+        ctrl_reg1: &CtrlReg1Value,
+        ctrl_reg4: &CtrlReg4Value,
+    ) -> Result<Self, &'static str> {
+        let low_power_set = ctrl_reg1.l_p_en() == LPEn::LowPowerEnabled;
+        let high_resolution_output_set = ctrl_reg4.hr() == OnOff::Enabled;
+
+        match (low_power_set, high_resolution_output_set) {
+            (true, false) => Ok(Mode::LowPower),
+            (false, false) => Ok(Mode::Normal),
+            (false, true) => Ok(Mode::HighResolution),
+            (true, true) => Err(
+                "Low power and high resolution mode can not both be enabled",
+            ),
+        }
+    }
+}
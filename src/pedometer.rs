@@ -0,0 +1,347 @@
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+use crate::gravity_filter::{
+    coefficient, rounded_hz, GravityFilter, FRACTION_BITS,
+};
+use micromath::vector::I32x3;
+
+/// Time constant of the gravity estimate the steps are measured against.
+const GRAVITY_TIME_CONSTANT_MS: u32 = 1000;
+/// Time constant of the low-pass suppressing heel strike ringing.
+const SMOOTHING_TIME_CONSTANT_MS: u32 = 40;
+/// Lower bound of the adaptive step threshold.
+pub const MIN_THRESHOLD_MG: i32 = 80;
+/// Steps closer together than this (240 steps/min) are not walking.
+pub const MIN_STEP_INTERVAL_MS: u32 = 250;
+/// A longer pause than this (30 steps/min) ends a walk.
+pub const MAX_STEP_INTERVAL_MS: u32 = 2000;
+/// Number of regular steps in a row before steps are counted.
+pub const CONFIRMATION_STEPS: u32 = 4;
+
+/// Counts steps in acceleration samples in mg, e.g. from
+/// [`crate::Lis3dh::get_accel_norm_from_fifo`].
+///
+/// Steps are peaks of the acceleration along gravity above a threshold
+/// following half the recent peak height. Peaks are only counted once
+/// [`CONFIRMATION_STEPS`] of them followed each other at a regular walking
+/// pace, so shaking, single bumps and irregular movement are rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pedometer {
+    gravity: GravityFilter,
+    sample_rate_millihertz: u32,
+    smoothing: i64,
+    smoothed: i64,
+    amplitude: i32,
+    peak: Option<i32>,
+    samples: u32,
+    last_step: Option<u32>,
+    last_interval: Option<u32>,
+    pending_steps: u32,
+    walking: bool,
+    average_interval: u64,
+    step_count: u32,
+}
+
+impl Pedometer {
+    pub fn new(sample_rate_hz: u32) -> Result<Self, &'static str> {
+        Pedometer::new_millihertz(sample_rate_hz.saturating_mul(1000))
+    }
+    /// Like [`Pedometer::new`] for a fractional sample rate, e.g.
+    /// [`crate::rate_estimator::RateEstimate::millihertz`].
+    pub fn new_millihertz(
+        sample_rate_millihertz: u32,
+    ) -> Result<Self, &'static str> {
+        Ok(Pedometer {
+            gravity: GravityFilter::new_millihertz(
+                GRAVITY_TIME_CONSTANT_MS,
+                sample_rate_millihertz,
+            )?,
+            sample_rate_millihertz,
+            smoothing: coefficient(
+                SMOOTHING_TIME_CONSTANT_MS,
+                sample_rate_millihertz,
+            ),
+            smoothed: 0,
+            amplitude: 0,
+            peak: None,
+            samples: 0,
+            last_step: None,
+            last_interval: None,
+            pending_steps: 0,
+            walking: false,
+            average_interval: 0,
+            step_count: 0,
+        })
+    }
+    /// Creates a pedometer for the sample rate configured in CTRL_REG1.
+    pub fn for_settings(
+        ctrl_reg1: &CtrlReg1Value,
+    ) -> Result<Self, &'static str> {
+        Pedometer::new(ctrl_reg1.sample_rate_hz()?)
+    }
+    /// Adapts the pedometer to a new sample rate. The step count and a
+    /// walk in progress are kept, the step intervals seen so far are
+    /// rescaled to the new rate.
+    pub fn set_sample_rate_hz(
+        &mut self,
+        sample_rate_hz: u32,
+    ) -> Result<(), &'static str> {
+        self.set_sample_rate_millihertz(sample_rate_hz.saturating_mul(1000))
+    }
+    /// Like [`Pedometer::set_sample_rate_hz`] for a fractional sample rate,
+    /// e.g. [`crate::rate_estimator::RateEstimate::millihertz`] measured
+    /// with [`crate::rate_estimator::RateEstimator`].
+    pub fn set_sample_rate_millihertz(
+        &mut self,
+        sample_rate_millihertz: u32,
+    ) -> Result<(), &'static str> {
+        self.gravity
+            .set_sample_rate_millihertz(sample_rate_millihertz)?;
+        let previous = self.sample_rate_millihertz as u64;
+        let rescale =
+            |samples: u64| samples * sample_rate_millihertz as u64 / previous;
+        if self.last_step.is_some() {
+            let since_last_step = rescale(self.since_last_step() as u64);
+            self.last_step =
+                Some(self.samples.wrapping_sub(since_last_step as u32));
+        }
+        self.last_interval = self
+            .last_interval
+            .map(|interval| rescale(interval as u64) as u32);
+        self.average_interval = rescale(self.average_interval);
+        self.sample_rate_millihertz = sample_rate_millihertz;
+        self.smoothing =
+            coefficient(SMOOTHING_TIME_CONSTANT_MS, sample_rate_millihertz);
+        Ok(())
+    }
+    /// Sample rate rounded to whole Hz.
+    pub fn sample_rate_hz(&self) -> u32 {
+        rounded_hz(self.sample_rate_millihertz)
+    }
+    pub fn sample_rate_millihertz(&self) -> u32 {
+        self.sample_rate_millihertz
+    }
+    pub fn step_count(&self) -> u32 {
+        self.step_count
+    }
+    /// Steps per minute while walking, `None` otherwise.
+    pub fn cadence(&self) -> Option<u32> {
+        self.walking.then(|| {
+            ((60 * self.sample_rate_millihertz as u64) << FRACTION_BITS)
+                .checked_div(self.average_interval * 1000)
+                .unwrap_or(0) as u32
+        })
+    }
+    pub fn is_walking(&self) -> bool {
+        self.walking
+    }
+    /// Clears the step count and the walk in progress.
+    pub fn reset(&mut self) {
+        *self = Pedometer::new_millihertz(self.sample_rate_millihertz)
+            .expect("Sample rate was checked on construction");
+    }
+    /// Feeds a batch of samples in mg and returns the number of steps
+    /// counted in it.
+    pub fn update_batch(&mut self, samples: &[I32x3]) -> u32 {
+        samples.iter().map(|sample| self.update(*sample)).sum()
+    }
+    /// Feeds a sample in mg and returns the number of steps counted with
+    /// it. Confirming a walk counts all of its steps at once.
+    pub fn update(&mut self, sample: I32x3) -> u32 {
+        self.samples = self.samples.wrapping_add(1);
+        let filtered = self.gravity.update(sample);
+        let vertical = filtered.gravity_direction().map_or(0, |direction| {
+            let linear = filtered.linear();
+            (linear.x as i64 * direction.x as i64
+                + linear.y as i64 * direction.y as i64
+                + linear.z as i64 * direction.z as i64)
+                / 1000
+        });
+        self.smoothed += (((vertical << FRACTION_BITS) - self.smoothed)
+            * self.smoothing)
+            >> FRACTION_BITS;
+        let signal = (self.smoothed >> FRACTION_BITS) as i32;
+        if self.walking
+            && self.since_last_step() > self.samples_in(MAX_STEP_INTERVAL_MS)
+        {
+            self.stop_walking();
+        }
+        let threshold = (self.amplitude / 2).max(MIN_THRESHOLD_MG);
+        match self.peak {
+            None if signal > threshold => self.peak = Some(signal),
+            Some(peak) if signal > 0 => self.peak = Some(peak.max(signal)),
+            Some(peak) => {
+                self.peak = None;
+                self.amplitude = (self.amplitude * 3 + peak) / 4;
+                return self.detect_step();
+            }
+            None => (),
+        }
+        0
+    }
+    fn detect_step(&mut self) -> u32 {
+        let interval = self.since_last_step();
+        self.last_step = Some(self.samples);
+        let regular = match self.last_interval {
+            Some(last) => interval.abs_diff(last) <= last / 2,
+            None => true,
+        };
+        if interval < self.samples_in(MIN_STEP_INTERVAL_MS)
+            || interval > self.samples_in(MAX_STEP_INTERVAL_MS)
+            || !regular
+        {
+            // This peak may start a new walk.
+            self.stop_walking();
+            self.pending_steps = 1;
+            return 0;
+        }
+        self.last_interval = Some(interval);
+        let interval = (interval as u64) << FRACTION_BITS;
+        if self.walking {
+            self.average_interval = (self.average_interval * 3 + interval) / 4;
+            self.step_count += 1;
+            return 1;
+        }
+        self.average_interval = if self.pending_steps == 1 {
+            interval
+        } else {
+            (self.average_interval * 3 + interval) / 4
+        };
+        self.pending_steps += 1;
+        if self.pending_steps < CONFIRMATION_STEPS {
+            return 0;
+        }
+        self.walking = true;
+        self.step_count += self.pending_steps;
+        self.pending_steps
+    }
+    fn stop_walking(&mut self) {
+        self.walking = false;
+        self.pending_steps = 0;
+        self.last_interval = None;
+    }
+    fn since_last_step(&self) -> u32 {
+        self.last_step
+            .map_or(u32::MAX, |last| self.samples.wrapping_sub(last))
+    }
+    fn samples_in(&self, milliseconds: u32) -> u32 {
+        (self.sample_rate_millihertz as u64 * milliseconds as u64 / 1_000_000)
+            as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctrl_reg_1_value::{CtrlReg1Value, ODR};
+    use micromath::vector::I32x3;
+    use micromath::F32Ext;
+
+    /// Sensor worn upright, bouncing `amplitude` mg at `frequency` Hz.
+    fn bouncing(
+        sample_rate_hz: u32,
+        frequency: f32,
+        amplitude: f32,
+        count: u32,
+    ) -> impl Iterator<Item = I32x3> {
+        (0..count).map(move |index| {
+            let phase = 2.0 * core::f32::consts::PI * frequency * index as f32
+                / sample_rate_hz as f32;
+            I32x3 {
+                x: 50,
+                y: 0,
+                z: 1000 + F32Ext::round(amplitude * F32Ext::sin(phase)) as i32,
+            }
+        })
+    }
+
+    #[test]
+    fn steps_and_cadence_are_counted_while_walking() {
+        let mut pedometer = super::Pedometer::new(50).unwrap();
+        let samples: [I32x3; 500] = {
+            let mut samples = [I32x3 { x: 0, y: 0, z: 0 }; 500];
+            for (slot, sample) in
+                samples.iter_mut().zip(bouncing(50, 1.8, 300.0, 500))
+            {
+                *slot = sample;
+            }
+            samples
+        };
+        let counted = pedometer.update_batch(&samples);
+        assert_eq!(counted, pedometer.step_count());
+        assert!((17..=18).contains(&counted), "{}", counted);
+        let cadence = pedometer.cadence().unwrap();
+        assert!((105..=111).contains(&cadence), "{}", cadence);
+        for _ in 0..150 {
+            pedometer.update(I32x3 {
+                x: 50,
+                y: 0,
+                z: 1000,
+            });
+        }
+        assert_eq!(pedometer.cadence(), None);
+        assert_eq!(pedometer.step_count(), counted);
+    }
+
+    #[test]
+    fn walk_continues_across_sample_rate_updates() {
+        let mut pedometer = super::Pedometer::new(50).unwrap();
+        let mut samples = bouncing(50, 1.8, 300.0, 400);
+        for sample in samples.by_ref().take(300) {
+            pedometer.update(sample);
+        }
+        assert!(pedometer.is_walking());
+        let counted = pedometer.step_count();
+        pedometer.set_sample_rate_hz(51).unwrap();
+        assert!(pedometer.is_walking());
+        for sample in samples {
+            pedometer.update(sample);
+        }
+        let added = pedometer.step_count() - counted;
+        assert!((3..=4).contains(&added), "{}", added);
+    }
+
+    #[test]
+    fn shaking_and_single_bumps_are_not_steps() {
+        let mut pedometer = super::Pedometer::new(100).unwrap();
+        for sample in bouncing(100, 6.0, 800.0, 500) {
+            pedometer.update(sample);
+        }
+        assert_eq!(pedometer.step_count(), 0);
+        for sample in bouncing(100, 1.5, 300.0, 140) {
+            pedometer.update(sample);
+        }
+        assert_eq!(pedometer.step_count(), 0);
+        assert!(!pedometer.is_walking());
+    }
+
+    #[test]
+    fn sample_rate_is_taken_from_the_output_data_rate() {
+        let mut ctrl_reg1 = CtrlReg1Value::default();
+        assert!(super::Pedometer::for_settings(&ctrl_reg1).is_err());
+        ctrl_reg1.set_output_data_rate(ODR::Hz25);
+        let mut pedometer = super::Pedometer::for_settings(&ctrl_reg1).unwrap();
+        assert_eq!(pedometer.sample_rate_hz(), 25);
+        for sample in bouncing(25, 2.0, 300.0, 250) {
+            pedometer.update(sample);
+        }
+        let counted = pedometer.step_count();
+        assert!((18..=20).contains(&counted), "{}", counted);
+        pedometer.set_sample_rate_hz(100).unwrap();
+        assert_eq!(pedometer.step_count(), counted);
+        assert!(pedometer.set_sample_rate_hz(0).is_err());
+    }
+
+    #[test]
+    fn cadence_follows_the_measured_sample_rate() {
+        let mut nominal = super::Pedometer::new(50).unwrap();
+        let mut measured = super::Pedometer::new_millihertz(50_500).unwrap();
+        assert_eq!(measured.sample_rate_hz(), 51);
+        for sample in bouncing(50, 1.8, 300.0, 500) {
+            nominal.update(sample);
+            measured.update(sample);
+        }
+        assert_eq!(measured.step_count(), nominal.step_count());
+        // The sensor samples 1 % fast, so the steps come 1 % more often.
+        let cadence = nominal.cadence().unwrap();
+        assert_eq!(measured.cadence().unwrap(), cadence * 101 / 100);
+    }
+}
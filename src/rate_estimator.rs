@@ -0,0 +1,166 @@
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+
+/// Largest deviation in percent of an interval between two events from
+/// the nominal period before the measurement restarts, e.g. after a missed
+/// interrupt.
+pub const MAX_DEVIATION_PERCENT: u64 = 20;
+
+/// Measured sample rate of the sensor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateEstimate {
+    millihertz: u32,
+    uncertainty_millihertz: u32,
+    nominal_hz: u32,
+}
+
+impl RateEstimate {
+    /// Measured rate, e.g. to timestamp FIFO samples with
+    /// [`crate::Lis3dh::get_accel_norm_from_fifo_timestamped`] or for
+    /// `Pedometer::set_sample_rate_millihertz` and the other processing
+    /// taking the sample rate in millihertz.
+    pub fn millihertz(&self) -> u32 {
+        self.millihertz
+    }
+    /// Largest error of the estimate caused by the timestamp jitter. It
+    /// shrinks the longer the measurement runs.
+    pub fn uncertainty_millihertz(&self) -> u32 {
+        self.uncertainty_millihertz
+    }
+    /// Rate rounded to whole Hz, which loses the drift of the sensor
+    /// clock. Prefer [`RateEstimate::millihertz`].
+    pub fn sample_rate_hz(&self) -> u32 {
+        ((self.millihertz + 500) / 1000).max(1)
+    }
+    /// Deviation from the nominal data rate in parts per million, positive
+    /// if the sensor samples faster.
+    pub fn drift_ppm(&self) -> i32 {
+        let nominal = self.nominal_hz as i64 * 1000;
+        ((self.millihertz as i64 - nominal) * 1_000_000 / nominal) as i32
+    }
+}
+
+/// Estimates the real sample rate, which differs from the nominal data rate
+/// by the tolerance of the internal oscillator, from the times of data
+/// ready or FIFO watermark interrupts taken with the MCU clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateEstimator {
+    nominal_hz: u32,
+    jitter_us: u32,
+    start_us: Option<u64>,
+    last_us: u64,
+    samples: u64,
+}
+
+impl RateEstimator {
+    /// `jitter_us` is the largest error of a single timestamp, e.g. the
+    /// interrupt latency plus the clock resolution.
+    pub fn new(nominal_hz: u32, jitter_us: u32) -> Result<Self, &'static str> {
+        if nominal_hz == 0 {
+            return Err("Sample rate must not be zero");
+        }
+        Ok(RateEstimator {
+            nominal_hz,
+            jitter_us,
+            start_us: None,
+            last_us: 0,
+            samples: 0,
+        })
+    }
+    /// Creates an estimator for the data rate configured in CTRL_REG1.
+    pub fn for_settings(
+        jitter_us: u32,
+        ctrl_reg1: &CtrlReg1Value,
+    ) -> Result<Self, &'static str> {
+        RateEstimator::new(ctrl_reg1.sample_rate_hz()?, jitter_us)
+    }
+    pub fn nominal_hz(&self) -> u32 {
+        self.nominal_hz
+    }
+    pub fn reset(&mut self) {
+        self.start_us = None;
+        self.samples = 0;
+    }
+    /// Records an event at `timestamp_us` with `samples` taken since the
+    /// previous event, 1 for data ready or the number of samples drained
+    /// from the FIFO. The first event only starts the measurement.
+    ///
+    /// Returns false if the interval does not match the nominal period
+    /// within [`MAX_DEVIATION_PERCENT`]; the measurement then restarts at
+    /// this event.
+    pub fn record(&mut self, timestamp_us: u64, samples: u32) -> bool {
+        if self.start_us.is_none() {
+            self.restart(timestamp_us);
+            return true;
+        }
+        let interval = timestamp_us.saturating_sub(self.last_us);
+        let expected = samples as u64 * 1_000_000 / self.nominal_hz as u64;
+        if samples == 0
+            || timestamp_us <= self.last_us
+            || interval.abs_diff(expected) * 100
+                > expected * MAX_DEVIATION_PERCENT
+        {
+            self.restart(timestamp_us);
+            return false;
+        }
+        self.last_us = timestamp_us;
+        self.samples += samples as u64;
+        true
+    }
+    /// Current estimate, `None` until two events were recorded.
+    pub fn estimate(&self) -> Option<RateEstimate> {
+        let elapsed = (self.last_us - self.start_us?) as u128;
+        if self.samples == 0 {
+            return None;
+        }
+        let millihertz =
+            (self.samples as u128 * 1_000_000_000 + elapsed / 2) / elapsed;
+        // Both ends of the measurement may be off by the jitter.
+        let uncertainty =
+            (millihertz * 2 * self.jitter_us as u128).div_ceil(elapsed);
+        Some(RateEstimate {
+            millihertz: millihertz.min(u32::MAX as u128) as u32,
+            uncertainty_millihertz: uncertainty.min(u32::MAX as u128) as u32,
+            nominal_hz: self.nominal_hz,
+        })
+    }
+    fn restart(&mut self, timestamp_us: u64) {
+        self.start_us = Some(timestamp_us);
+        self.last_us = timestamp_us;
+        self.samples = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateEstimator;
+
+    #[test]
+    fn data_ready_times_reveal_a_fast_oscillator() {
+        let mut estimator = RateEstimator::new(100, 50).unwrap();
+        assert_eq!(estimator.estimate(), None);
+        // The sensor really samples at 102 Hz.
+        for index in 0..=1020u64 {
+            assert!(estimator.record(index * 1_000_000 / 102 + 3, 1));
+        }
+        let estimate = estimator.estimate().unwrap();
+        assert_eq!(estimate.millihertz(), 102_000);
+        assert_eq!(estimate.sample_rate_hz(), 102);
+        assert_eq!(estimate.drift_ppm(), 20_000);
+        assert_eq!(estimate.uncertainty_millihertz(), 2);
+    }
+
+    #[test]
+    fn missed_watermark_restarts_the_measurement() {
+        let mut estimator = RateEstimator::new(400, 0).unwrap();
+        // 20 samples per watermark at 392 Hz take 51_020 us.
+        assert!(estimator.record(0, 20));
+        assert!(estimator.record(51_020, 20));
+        assert!(!estimator.record(153_061, 20));
+        assert_eq!(estimator.estimate(), None);
+        assert!(estimator.record(204_082, 20));
+        assert!(estimator.record(255_102, 20));
+        let estimate = estimator.estimate().unwrap();
+        assert_eq!(estimate.sample_rate_hz(), 392);
+        assert_eq!(estimate.drift_ppm(), -20_002);
+    }
+}
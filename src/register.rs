@@ -0,0 +1,274 @@
+use crate::click_cfg::ClickCfg;
+use crate::click_src::ClickSrc;
+use crate::ctrl_reg_0_value::CtrlReg0Value;
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+use crate::ctrl_reg_2_value::CtrlReg2Value;
+use crate::ctrl_reg_3_value::CtrlReg3Value;
+use crate::ctrl_reg_4_value::CtrlReg4Value;
+use crate::ctrl_reg_5_value::CtrlReg5Value;
+use crate::ctrl_reg_6_value::CtrlReg6Value;
+use crate::fifo_ctrl_reg_value::FifoCtrlRegValue;
+use crate::fifo_src_reg_value::FifoSrcRegValue;
+use crate::int_cfg::IntCfg;
+use crate::int_duration_value::IntDuration;
+use crate::int_src::IntSrc;
+use crate::int_ths_value::IntThs;
+use crate::register_field::RegisterField;
+use crate::status_reg_aux_value::StatusRegAuxValue;
+use crate::status_reg_value::StatusRegValue;
+use crate::temp_cfg_reg_value::TempCfgRegValue;
+use crate::RegisterAddresses;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccessMode {
+    ReadOnly,
+    ReadWrite,
+}
+
+/// Typed value of a single LIS3DH register.
+///
+/// Implement this trait for your own type to read, write and modify a
+/// register the crate does not model with [`crate::Lis3dh::read`],
+/// [`crate::Lis3dh::write`] and [`crate::Lis3dh::modify`].
+pub trait Register: Copy + TryFrom<u8, Error = &'static str> {
+    const ADDRESS: RegisterAddresses;
+    const ACCESS_MODE: AccessMode;
+    /// Register content after power up or reboot.
+    const RESET_VALUE: u8;
+    const FIELDS: &'static [RegisterField] = &[];
+
+    fn raw_value(&self) -> u8;
+}
+
+/// Interrupt generator register with an identical counterpart for the
+/// second interrupt generator.
+pub trait InterruptGeneratorRegister: Register {
+    const INT2_ADDRESS: RegisterAddresses;
+}
+
+/// Accesses the interrupt generator 2 counterpart of an interrupt generator
+/// 1 register, e.g. `Int2<IntCfg>` for INT2_CFG.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Int2<R>(pub R);
+
+impl<R: InterruptGeneratorRegister> TryFrom<u8> for Int2<R> {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        R::try_from(value).map(Int2)
+    }
+}
+
+impl<R: InterruptGeneratorRegister> Register for Int2<R> {
+    const ADDRESS: RegisterAddresses = R::INT2_ADDRESS;
+    const ACCESS_MODE: AccessMode = R::ACCESS_MODE;
+    const RESET_VALUE: u8 = R::RESET_VALUE;
+    const FIELDS: &'static [RegisterField] = R::FIELDS;
+
+    fn raw_value(&self) -> u8 {
+        self.0.raw_value()
+    }
+}
+
+/// Returns the fields of the typed value decoding the given register.
+pub fn fields_of(register: RegisterAddresses) -> &'static [RegisterField] {
+    match register {
+        RegisterAddresses::StatusRegAux => StatusRegAuxValue::FIELDS,
+        RegisterAddresses::CtrlReg0 => CtrlReg0Value::FIELDS,
+        RegisterAddresses::TempCfgReg => TempCfgRegValue::FIELDS,
+        RegisterAddresses::CtrlReg1 => CtrlReg1Value::FIELDS,
+        RegisterAddresses::CtrlReg2 => CtrlReg2Value::FIELDS,
+        RegisterAddresses::CtrlReg3 => CtrlReg3Value::FIELDS,
+        RegisterAddresses::CtrlReg4 => CtrlReg4Value::FIELDS,
+        RegisterAddresses::CtrlReg5 => CtrlReg5Value::FIELDS,
+        RegisterAddresses::CtrlReg6 => CtrlReg6Value::FIELDS,
+        RegisterAddresses::StatusReg => StatusRegValue::FIELDS,
+        RegisterAddresses::Int1Cfg | RegisterAddresses::Int2Cfg => {
+            IntCfg::FIELDS
+        }
+        RegisterAddresses::Int1Src | RegisterAddresses::Int2Src => {
+            IntSrc::FIELDS
+        }
+        RegisterAddresses::Int1Threshold | RegisterAddresses::Int2Threshold => {
+            IntThs::FIELDS
+        }
+        RegisterAddresses::Int1Duration | RegisterAddresses::Int2Duration => {
+            IntDuration::FIELDS
+        }
+        RegisterAddresses::FifoCtrlReg => FifoCtrlRegValue::FIELDS,
+        RegisterAddresses::FifSrcReg => FifoSrcRegValue::FIELDS,
+        RegisterAddresses::ClickCfg => ClickCfg::FIELDS,
+        RegisterAddresses::ClickSrc => ClickSrc::FIELDS,
+        _ => &[],
+    }
+}
+
+/// Bits every writable register has to keep at a fixed value according to
+/// the datasheet, as `(register, mask, value)`.
+const FIXED_BITS: &[(RegisterAddresses, u8, u8)] = &[
+    (RegisterAddresses::CtrlReg0, 0b0111_1111, 0b0001_0000),
+    (RegisterAddresses::TempCfgReg, 0b0011_1111, 0),
+    (RegisterAddresses::CtrlReg1, 0, 0),
+    (RegisterAddresses::CtrlReg2, 0, 0),
+    (RegisterAddresses::CtrlReg3, 0b0000_0001, 0),
+    (RegisterAddresses::CtrlReg4, 0, 0),
+    (RegisterAddresses::CtrlReg5, 0b0011_0000, 0),
+    (RegisterAddresses::CtrlReg6, 0b0000_0101, 0),
+    (RegisterAddresses::Reference, 0, 0),
+    (RegisterAddresses::FifoCtrlReg, 0, 0),
+    (RegisterAddresses::Int1Cfg, 0, 0),
+    (RegisterAddresses::Int1Threshold, 0b1000_0000, 0),
+    (RegisterAddresses::Int1Duration, 0b1000_0000, 0),
+    (RegisterAddresses::Int2Cfg, 0, 0),
+    (RegisterAddresses::Int2Threshold, 0b1000_0000, 0),
+    (RegisterAddresses::Int2Duration, 0b1000_0000, 0),
+    (RegisterAddresses::ClickCfg, 0b1100_0000, 0),
+    (RegisterAddresses::ClickThreshold, 0, 0),
+    (RegisterAddresses::TimeLimit, 0b1000_0000, 0),
+    (RegisterAddresses::TimeLatency, 0, 0),
+    (RegisterAddresses::TimeWindow, 0, 0),
+    (RegisterAddresses::ActivationThreshold, 0b1000_0000, 0),
+    (RegisterAddresses::ActivationDuration, 0, 0),
+];
+
+/// Checks that a raw value keeps the fixed bits of the given register and,
+/// for registers with a typed value, that it decodes.
+pub fn check_raw_value(
+    register: RegisterAddresses,
+    value: u8,
+) -> Result<(), &'static str> {
+    let fixed_bits_kept = FIXED_BITS
+        .iter()
+        .filter(|(fixed_register, _, _)| *fixed_register == register)
+        .all(|(_, mask, fixed)| value & mask == *fixed);
+    if !fixed_bits_kept {
+        return Err("Value changes bits the datasheet requires to keep fixed");
+    }
+    match register {
+        RegisterAddresses::CtrlReg0 => CtrlReg0Value::try_from(value).map(drop),
+        RegisterAddresses::TempCfgReg => {
+            TempCfgRegValue::try_from(value).map(drop)
+        }
+        RegisterAddresses::CtrlReg1 => CtrlReg1Value::try_from(value).map(drop),
+        RegisterAddresses::CtrlReg2 => CtrlReg2Value::try_from(value).map(drop),
+        RegisterAddresses::CtrlReg3 => CtrlReg3Value::try_from(value).map(drop),
+        RegisterAddresses::CtrlReg4 => CtrlReg4Value::try_from(value).map(drop),
+        RegisterAddresses::CtrlReg5 => CtrlReg5Value::try_from(value).map(drop),
+        RegisterAddresses::CtrlReg6 => CtrlReg6Value::try_from(value).map(drop),
+        RegisterAddresses::Int1Cfg | RegisterAddresses::Int2Cfg => {
+            IntCfg::try_from(value).map(drop)
+        }
+        RegisterAddresses::Int1Threshold | RegisterAddresses::Int2Threshold => {
+            IntThs::try_from(value).map(drop)
+        }
+        RegisterAddresses::Int1Duration | RegisterAddresses::Int2Duration => {
+            IntDuration::try_from(value).map(drop)
+        }
+        RegisterAddresses::FifoCtrlReg => {
+            FifoCtrlRegValue::try_from(value).map(drop)
+        }
+        RegisterAddresses::ClickCfg => ClickCfg::try_from(value).map(drop),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Int2, Register};
+    use crate::click_cfg::ClickCfg;
+    use crate::click_src::ClickSrc;
+    use crate::ctrl_reg_0_value::CtrlReg0Value;
+    use crate::ctrl_reg_1_value::CtrlReg1Value;
+    use crate::ctrl_reg_2_value::CtrlReg2Value;
+    use crate::ctrl_reg_3_value::CtrlReg3Value;
+    use crate::ctrl_reg_4_value::CtrlReg4Value;
+    use crate::ctrl_reg_5_value::CtrlReg5Value;
+    use crate::ctrl_reg_6_value::CtrlReg6Value;
+    use crate::fifo_ctrl_reg_value::FifoCtrlRegValue;
+    use crate::fifo_src_reg_value::FifoSrcRegValue;
+    use crate::int_cfg::IntCfg;
+    use crate::int_duration_value::IntDuration;
+    use crate::int_src::IntSrc;
+    use crate::int_ths_value::IntThs;
+    use crate::status_reg_value::StatusRegValue;
+    use crate::temp_cfg_reg_value::TempCfgRegValue;
+    use crate::RegisterAddresses;
+
+    fn default_is_reset_value<R: Register + Default>() -> bool {
+        R::default().raw_value() == R::RESET_VALUE
+    }
+
+    #[test]
+    fn default_values_match_reset_values() {
+        assert!(default_is_reset_value::<CtrlReg0Value>());
+        assert!(default_is_reset_value::<TempCfgRegValue>());
+        assert!(default_is_reset_value::<CtrlReg1Value>());
+        assert!(default_is_reset_value::<CtrlReg2Value>());
+        assert!(default_is_reset_value::<CtrlReg3Value>());
+        assert!(default_is_reset_value::<CtrlReg4Value>());
+        assert!(default_is_reset_value::<CtrlReg5Value>());
+        assert!(default_is_reset_value::<CtrlReg6Value>());
+        assert!(default_is_reset_value::<StatusRegValue>());
+        assert!(default_is_reset_value::<ClickCfg>());
+        assert!(default_is_reset_value::<ClickSrc>());
+        assert!(default_is_reset_value::<IntCfg>());
+        assert!(default_is_reset_value::<IntThs>());
+        assert!(default_is_reset_value::<IntDuration>());
+        assert!(default_is_reset_value::<IntSrc>());
+        assert!(default_is_reset_value::<FifoCtrlRegValue>());
+        assert!(default_is_reset_value::<FifoSrcRegValue>());
+    }
+
+    #[test]
+    fn raw_values_changing_fixed_bits_are_refused() {
+        assert!(
+            super::check_raw_value(RegisterAddresses::CtrlReg0, 0x90).is_ok()
+        );
+        assert!(
+            super::check_raw_value(RegisterAddresses::CtrlReg0, 0x00).is_err()
+        );
+        assert!(
+            super::check_raw_value(RegisterAddresses::Int2Threshold, 0x80)
+                .is_err()
+        );
+        assert!(super::check_raw_value(RegisterAddresses::FifoCtrlReg, 0xff)
+            .is_ok());
+        assert!(
+            super::check_raw_value(RegisterAddresses::TimeLimit, 0x80).is_err()
+        );
+        assert!(super::check_raw_value(
+            RegisterAddresses::ActivationThreshold,
+            0x7f
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn every_writable_register_has_fixed_bits() {
+        use num_traits::FromPrimitive;
+        for address in 0..=RegisterAddresses::ActivationDuration as u8 {
+            let register = match RegisterAddresses::from_u8(address) {
+                Some(register) if !crate::is_read_only(address) => register,
+                _ => continue,
+            };
+            assert!(
+                super::FIXED_BITS
+                    .iter()
+                    .any(|(fixed_register, _, _)| *fixed_register == register),
+                "{:?}",
+                register
+            );
+        }
+    }
+
+    #[test]
+    fn interrupt_generator_2_registers_have_their_own_address() {
+        assert_eq!(<Int2<IntCfg>>::ADDRESS, RegisterAddresses::Int2Cfg);
+        assert_eq!(<Int2<IntSrc>>::ADDRESS, RegisterAddresses::Int2Src);
+        assert_eq!(<Int2<IntThs>>::ADDRESS, RegisterAddresses::Int2Threshold);
+        assert_eq!(
+            <Int2<IntDuration>>::ADDRESS,
+            RegisterAddresses::Int2Duration
+        );
+    }
+}
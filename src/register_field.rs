@@ -0,0 +1,34 @@
+/// Named group of bits inside a register, used to break down register
+/// differences into the fields of the typed register values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterField {
+    name: &'static str,
+    mask: u8,
+}
+
+impl RegisterField {
+    pub const fn new(name: &'static str, mask: u8) -> Self {
+        RegisterField { name, mask }
+    }
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+    /// Extracts the value of the field from a raw register value.
+    pub fn value_of(&self, raw_value: u8) -> u8 {
+        (raw_value & self.mask) >> self.mask.trailing_zeros()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn field_value_is_extracted_from_raw_value() {
+        let field = super::RegisterField::new("output_data_rate", 0xF0);
+        assert_eq!(field.value_of(0b1001_0111), 0b1001);
+        let field = super::RegisterField::new("y_en", 0b10);
+        assert_eq!(field.value_of(0b1001_0111), 1);
+    }
+}
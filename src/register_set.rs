@@ -0,0 +1,67 @@
+use crate::RegisterAddresses;
+use num_traits::FromPrimitive;
+
+/// Set of registers, e.g. the registers whose settings were modified since
+/// they were last written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RegisterSet {
+    bits: u64,
+}
+
+impl RegisterSet {
+    pub const fn empty() -> Self {
+        RegisterSet { bits: 0 }
+    }
+    pub fn insert(&mut self, register: RegisterAddresses) {
+        self.bits |= 1 << register as u8;
+    }
+    pub fn remove(&mut self, register: RegisterAddresses) {
+        self.bits &= !(1 << register as u8);
+    }
+    pub fn contains(&self, register: RegisterAddresses) -> bool {
+        (self.bits >> register as u8) & 1 == 1
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+    /// Iterates over the registers in ascending address order.
+    pub fn iter(&self) -> impl Iterator<Item = RegisterAddresses> {
+        let bits = self.bits;
+        (0..u64::BITS as u8)
+            .filter(move |address| (bits >> address) & 1 == 1)
+            .filter_map(RegisterAddresses::from_u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RegisterAddresses;
+
+    #[test]
+    fn registers_can_be_inserted_and_removed() {
+        let mut set = super::RegisterSet::empty();
+        assert!(set.is_empty());
+        set.insert(RegisterAddresses::Int1Cfg);
+        set.insert(RegisterAddresses::CtrlReg0);
+        set.insert(RegisterAddresses::ActivationDuration);
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(RegisterAddresses::CtrlReg0));
+        assert!(!set.contains(RegisterAddresses::CtrlReg1));
+        set.remove(RegisterAddresses::CtrlReg0);
+        assert!(!set.contains(RegisterAddresses::CtrlReg0));
+    }
+
+    #[test]
+    fn iteration_is_ordered_by_address() {
+        let mut set = super::RegisterSet::empty();
+        set.insert(RegisterAddresses::Int1Cfg);
+        set.insert(RegisterAddresses::CtrlReg0);
+        let mut registers = set.iter();
+        assert_eq!(registers.next(), Some(RegisterAddresses::CtrlReg0));
+        assert_eq!(registers.next(), Some(RegisterAddresses::Int1Cfg));
+        assert_eq!(registers.next(), None);
+    }
+}
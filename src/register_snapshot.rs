@@ -0,0 +1,252 @@
+use crate::click_cfg::ClickCfg;
+use crate::click_src::ClickSrc;
+use crate::ctrl_reg_0_value::CtrlReg0Value;
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+use crate::ctrl_reg_2_value::CtrlReg2Value;
+use crate::ctrl_reg_3_value::CtrlReg3Value;
+use crate::ctrl_reg_4_value::CtrlReg4Value;
+use crate::ctrl_reg_5_value::CtrlReg5Value;
+use crate::ctrl_reg_6_value::CtrlReg6Value;
+use crate::fifo_ctrl_reg_value::FifoCtrlRegValue;
+use crate::fifo_src_reg_value::FifoSrcRegValue;
+use crate::int_cfg::IntCfg;
+use crate::int_duration_value::IntDuration;
+use crate::int_src::IntSrc;
+use crate::int_ths_value::IntThs;
+use crate::status_reg_aux_value::StatusRegAuxValue;
+use crate::status_reg_value::StatusRegValue;
+use crate::temp_cfg_reg_value::TempCfgRegValue;
+use crate::{Lis3dh, RegisterAddresses};
+use num_traits::FromPrimitive;
+
+pub const SNAPSHOT_FIRST_REGISTER: RegisterAddresses =
+    RegisterAddresses::StatusRegAux;
+pub const SNAPSHOT_LENGTH: usize = RegisterAddresses::ActivationDuration
+    as usize
+    - SNAPSHOT_FIRST_REGISTER as usize
+    + 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterDifference {
+    register: RegisterAddresses,
+    expected: u8,
+    actual: u8,
+}
+
+impl RegisterDifference {
+    pub fn register(&self) -> RegisterAddresses {
+        self.register
+    }
+    pub fn expected(&self) -> u8 {
+        self.expected
+    }
+    pub fn actual(&self) -> u8 {
+        self.actual
+    }
+    pub fn differing_bits(&self) -> u8 {
+        self.expected ^ self.actual
+    }
+}
+
+/// Raw copy of every register from STATUS_REG_AUX (0x07) to ACT_DUR (0x3F)
+/// read in one auto incremented burst.
+///
+/// The reserved addresses in between are part of the burst but can not be
+/// accessed through the snapshot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterSnapshot {
+    values: [u8; SNAPSHOT_LENGTH],
+}
+
+impl RegisterSnapshot {
+    pub fn new(values: [u8; SNAPSHOT_LENGTH]) -> Self {
+        RegisterSnapshot { values }
+    }
+    pub fn raw_values(&self) -> [u8; SNAPSHOT_LENGTH] {
+        self.values
+    }
+    pub fn raw_value(&self, register: RegisterAddresses) -> u8 {
+        self.values[register as usize - SNAPSHOT_FIRST_REGISTER as usize]
+    }
+    pub fn status_reg_aux(&self) -> StatusRegAuxValue {
+        StatusRegAuxValue::from_raw_value(
+            self.raw_value(RegisterAddresses::StatusRegAux),
+        )
+    }
+    pub fn who_am_i(&self) -> u8 {
+        self.raw_value(RegisterAddresses::WhoAmI)
+    }
+    pub fn ctrl_reg0(&self) -> Result<CtrlReg0Value, &'static str> {
+        CtrlReg0Value::try_from(self.raw_value(RegisterAddresses::CtrlReg0))
+    }
+    pub fn temp_cfg_reg(&self) -> Result<TempCfgRegValue, &'static str> {
+        TempCfgRegValue::try_from(self.raw_value(RegisterAddresses::TempCfgReg))
+    }
+    pub fn ctrl_reg1(&self) -> Result<CtrlReg1Value, &'static str> {
+        CtrlReg1Value::try_from(self.raw_value(RegisterAddresses::CtrlReg1))
+    }
+    pub fn ctrl_reg2(&self) -> Result<CtrlReg2Value, &'static str> {
+        CtrlReg2Value::try_from(self.raw_value(RegisterAddresses::CtrlReg2))
+    }
+    pub fn ctrl_reg3(&self) -> Result<CtrlReg3Value, &'static str> {
+        CtrlReg3Value::try_from(self.raw_value(RegisterAddresses::CtrlReg3))
+    }
+    pub fn ctrl_reg4(&self) -> Result<CtrlReg4Value, &'static str> {
+        CtrlReg4Value::try_from(self.raw_value(RegisterAddresses::CtrlReg4))
+    }
+    pub fn ctrl_reg5(&self) -> Result<CtrlReg5Value, &'static str> {
+        CtrlReg5Value::try_from(self.raw_value(RegisterAddresses::CtrlReg5))
+    }
+    pub fn ctrl_reg6(&self) -> Result<CtrlReg6Value, &'static str> {
+        CtrlReg6Value::try_from(self.raw_value(RegisterAddresses::CtrlReg6))
+    }
+    pub fn status_reg(&self) -> Result<StatusRegValue, &'static str> {
+        StatusRegValue::try_from(self.raw_value(RegisterAddresses::StatusReg))
+    }
+    pub fn fifo_ctrl_reg(&self) -> Result<FifoCtrlRegValue, &'static str> {
+        FifoCtrlRegValue::try_from(
+            self.raw_value(RegisterAddresses::FifoCtrlReg),
+        )
+    }
+    pub fn fifo_src_reg(&self) -> Result<FifoSrcRegValue, &'static str> {
+        FifoSrcRegValue::try_from(self.raw_value(RegisterAddresses::FifSrcReg))
+    }
+    pub fn int1_cfg(&self) -> Result<IntCfg, &'static str> {
+        IntCfg::try_from(self.raw_value(RegisterAddresses::Int1Cfg))
+    }
+    pub fn int1_src(&self) -> Result<IntSrc, &'static str> {
+        IntSrc::try_from(self.raw_value(RegisterAddresses::Int1Src))
+    }
+    pub fn int1_ths(&self) -> Result<IntThs, &'static str> {
+        IntThs::try_from(self.raw_value(RegisterAddresses::Int1Threshold))
+    }
+    pub fn int1_duration(&self) -> Result<IntDuration, &'static str> {
+        IntDuration::try_from(self.raw_value(RegisterAddresses::Int1Duration))
+    }
+    pub fn int2_cfg(&self) -> Result<IntCfg, &'static str> {
+        IntCfg::try_from(self.raw_value(RegisterAddresses::Int2Cfg))
+    }
+    pub fn int2_src(&self) -> Result<IntSrc, &'static str> {
+        IntSrc::try_from(self.raw_value(RegisterAddresses::Int2Src))
+    }
+    pub fn int2_ths(&self) -> Result<IntThs, &'static str> {
+        IntThs::try_from(self.raw_value(RegisterAddresses::Int2Threshold))
+    }
+    pub fn int2_duration(&self) -> Result<IntDuration, &'static str> {
+        IntDuration::try_from(self.raw_value(RegisterAddresses::Int2Duration))
+    }
+    pub fn click_cfg(&self) -> Result<ClickCfg, &'static str> {
+        ClickCfg::try_from(self.raw_value(RegisterAddresses::ClickCfg))
+    }
+    pub fn click_src(&self) -> Result<ClickSrc, &'static str> {
+        ClickSrc::try_from(self.raw_value(RegisterAddresses::ClickSrc))
+    }
+    /// Lists every defined register whose value in this snapshot differs
+    /// from the value in `reference`.
+    pub fn diff<'a>(
+        &'a self,
+        reference: &'a RegisterSnapshot,
+    ) -> impl Iterator<Item = RegisterDifference> + 'a {
+        (0..SNAPSHOT_LENGTH).filter_map(move |index| {
+            let register = RegisterAddresses::from_usize(
+                index + SNAPSHOT_FIRST_REGISTER as usize,
+            )?;
+            let expected = reference.values[index];
+            let actual = self.values[index];
+            (expected != actual).then_some(RegisterDifference {
+                register,
+                expected,
+                actual,
+            })
+        })
+    }
+    /// Lists every register managed by the driver whose value in this
+    /// snapshot differs from the driver's settings.
+    pub fn diff_from_settings<'a>(
+        &'a self,
+        settings: &'a Lis3dh,
+    ) -> impl Iterator<Item = RegisterDifference> + 'a {
+        crate::MANAGED_REGISTERS.iter().filter_map(move |register| {
+            let expected = settings.setting_raw_value(*register)?;
+            let actual = self.raw_value(*register);
+            (expected != actual).then_some(RegisterDifference {
+                register: *register,
+                expected,
+                actual,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RegisterAddresses;
+
+    fn snapshot_with(
+        register: RegisterAddresses,
+        value: u8,
+    ) -> super::RegisterSnapshot {
+        let mut values = [0_u8; super::SNAPSHOT_LENGTH];
+        values[register as usize - 0x07] = value;
+        super::RegisterSnapshot::new(values)
+    }
+
+    #[test]
+    fn snapshot_covers_status_reg_aux_to_act_dur() {
+        assert_eq!(super::SNAPSHOT_LENGTH, 57);
+        let snapshot = snapshot_with(RegisterAddresses::ActivationDuration, 3);
+        assert_eq!(
+            snapshot.raw_value(RegisterAddresses::ActivationDuration),
+            3
+        );
+    }
+
+    #[test]
+    fn typed_values_are_decoded() {
+        let snapshot = snapshot_with(RegisterAddresses::CtrlReg1, 0b0101_0111);
+        assert_eq!(
+            snapshot.ctrl_reg1().unwrap().output_data_rate(),
+            crate::ctrl_reg_1_value::ODR::Hz100
+        );
+        let snapshot = snapshot_with(RegisterAddresses::CtrlReg1, 0b1111_0111);
+        assert!(snapshot.ctrl_reg1().is_err());
+        let snapshot = snapshot_with(RegisterAddresses::FifSrcReg, 0b1000_1111);
+        let fifo_src = snapshot.fifo_src_reg().unwrap();
+        assert!(fifo_src.is_watermark_reached());
+        assert_eq!(fifo_src.unread_samples(), 15);
+        let snapshot = snapshot_with(RegisterAddresses::ClickSrc, 0b0100_0001);
+        assert!(snapshot.click_src().unwrap().is_x());
+    }
+
+    #[test]
+    fn diff_only_reports_changed_registers() {
+        let reference = snapshot_with(RegisterAddresses::CtrlReg1, 0x07);
+        let snapshot = snapshot_with(RegisterAddresses::CtrlReg1, 0x57);
+        let mut differences = snapshot.diff(&reference);
+        let difference = differences.next().unwrap();
+        assert_eq!(difference.register(), RegisterAddresses::CtrlReg1);
+        assert_eq!(difference.expected(), 0x07);
+        assert_eq!(difference.actual(), 0x57);
+        assert_eq!(difference.differing_bits(), 0x50);
+        assert!(differences.next().is_none());
+    }
+
+    #[test]
+    fn diff_from_settings_reports_managed_registers() {
+        let settings = crate::Lis3dh::default();
+        let mut values = [0_u8; super::SNAPSHOT_LENGTH];
+        values[RegisterAddresses::CtrlReg0 as usize - 0x07] = 0b0001_0000;
+        values[RegisterAddresses::CtrlReg1 as usize - 0x07] = 0b0000_0111;
+        let snapshot = super::RegisterSnapshot::new(values);
+        assert_eq!(snapshot.diff_from_settings(&settings).count(), 0);
+
+        values[RegisterAddresses::Int1Cfg as usize - 0x07] = 0b1000_0000;
+        let snapshot = super::RegisterSnapshot::new(values);
+        let mut differences = snapshot.diff_from_settings(&settings);
+        assert_eq!(
+            differences.next().unwrap().register(),
+            RegisterAddresses::Int1Cfg
+        );
+        assert!(differences.next().is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+use crate::ctrl_reg_4_value::{CtrlReg4Value, Endianness, FullScaleSelection};
+use crate::mode::Mode;
+use micromath::vector::{I16x3, I32x3};
+
+/// Number of bytes of one acceleration sample, OUT_X_L to OUT_Z_H.
+pub const SAMPLE_SIZE: usize = 6;
+
+/// Resolution, range and byte order of the acceleration output registers as
+/// configured in CTRL_REG1 and CTRL_REG4.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SampleFormat {
+    mode: Mode,
+    full_scale: FullScaleSelection,
+    endianness: Endianness,
+}
+
+impl SampleFormat {
+    pub fn new(
+        mode: Mode,
+        full_scale: FullScaleSelection,
+        endianness: Endianness,
+    ) -> Self {
+        SampleFormat {
+            mode,
+            full_scale,
+            endianness,
+        }
+    }
+    pub fn from_settings(
+        ctrl_reg1: &CtrlReg1Value,
+        ctrl_reg4: &CtrlReg4Value,
+    ) -> Result<Self, &'static str> {
+        Ok(SampleFormat {
+            mode: Mode::from_settings(ctrl_reg1, ctrl_reg4)?,
+            full_scale: ctrl_reg4.fs(),
+            endianness: ctrl_reg4.ble(),
+        })
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    pub fn full_scale(&self) -> FullScaleSelection {
+        self.full_scale
+    }
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+    /// Decodes the bytes read from OUT_X_L to OUT_Z_H.
+    pub fn decode_raw(&self, bytes: &[u8; SAMPLE_SIZE]) -> I16x3 {
+        I16x3 {
+            x: self.endianness.decode_i16(bytes[0], bytes[1]),
+            y: self.endianness.decode_i16(bytes[2], bytes[3]),
+            z: self.endianness.decode_i16(bytes[4], bytes[5]),
+        }
+    }
+    /// Factor converting the shifted output value to mg and the number of
+    /// unused low bits of the left-justified output value.
+    fn scale(&self) -> (i32, u8) {
+        let multiplier = match (self.mode, self.full_scale) {
+            (Mode::HighResolution, FullScaleSelection::Gravity2G) => 1,
+            (Mode::HighResolution, FullScaleSelection::Gravity4G) => 1,
+            (Mode::HighResolution, FullScaleSelection::Gravity8G) => 4,
+            (Mode::HighResolution, FullScaleSelection::Gravity16G) => 12,
+            (Mode::Normal, FullScaleSelection::Gravity2G) => 4,
+            (Mode::Normal, FullScaleSelection::Gravity4G) => 8,
+            (Mode::Normal, FullScaleSelection::Gravity8G) => 16,
+            (Mode::Normal, FullScaleSelection::Gravity16G) => 48,
+            (Mode::LowPower, FullScaleSelection::Gravity2G) => 16,
+            (Mode::LowPower, FullScaleSelection::Gravity4G) => 32,
+            (Mode::LowPower, FullScaleSelection::Gravity8G) => 64,
+            (Mode::LowPower, FullScaleSelection::Gravity16G) => 192,
+        };
+
+        let shift: u8 = match self.mode {
+            Mode::HighResolution => 4, // High Resolution:  12-bit
+            Mode::Normal => 6,         // Normal:           10-bit
+            Mode::LowPower => 8,       // Low Power:         8-bit
+        };
+
+        (multiplier, shift)
+    }
+    /// Plausibility check that the unused low bits of the left-justified
+    /// output values are zero. The datasheet does not specify these bits,
+    /// the sensor leaves them zero in practice. This catches e.g. a MISO
+    /// line stuck high; the output registers have no checksum, so flipped
+    /// data bits and a line stuck low can not be detected.
+    pub fn check_raw(&self, raw: I16x3) -> Result<(), &'static str> {
+        let (_, shift) = self.scale();
+        let unused_bits = (1 << shift) - 1;
+        if (raw.x | raw.y | raw.z) & unused_bits != 0 {
+            return Err("Unused low bits of the output registers are set");
+        }
+        Ok(())
+    }
+    /// Converts a raw sample to mg.
+    pub fn normalize(&self, raw: I16x3) -> I32x3 {
+        let (multiplier, shift) = self.scale();
+        I32x3 {
+            x: (raw.x >> shift) as i32 * multiplier,
+            y: (raw.y >> shift) as i32 * multiplier,
+            z: (raw.z >> shift) as i32 * multiplier,
+        }
+    }
+    /// Converts a sample in mg to the closest raw value the sensor can
+    /// output, saturating at the full scale.
+    pub fn to_raw(&self, sample: I32x3) -> I16x3 {
+        let (multiplier, shift) = self.scale();
+        let limit = i16::MAX as i32 >> shift;
+        let convert = |value: i32| {
+            ((value / multiplier).clamp(-limit - 1, limit) << shift) as i16
+        };
+        I16x3 {
+            x: convert(sample.x),
+            y: convert(sample.y),
+            z: convert(sample.z),
+        }
+    }
+    /// Encodes a raw sample as it appears in OUT_X_L to OUT_Z_H.
+    pub fn encode_raw(&self, raw: I16x3) -> [u8; SAMPLE_SIZE] {
+        let x = self.endianness.encode_i16(raw.x);
+        let y = self.endianness.encode_i16(raw.y);
+        let z = self.endianness.encode_i16(raw.z);
+        [x[0], x[1], y[0], y[1], z[0], z[1]]
+    }
+    /// Decodes the bytes read from OUT_X_L to OUT_Z_H and converts them
+    /// to mg.
+    pub fn decode(&self, bytes: &[u8; SAMPLE_SIZE]) -> I32x3 {
+        self.normalize(self.decode_raw(bytes))
+    }
+    /// Like [`SampleFormat::decode`], refusing bytes failing
+    /// [`SampleFormat::check_raw`].
+    pub fn decode_checked(
+        &self,
+        bytes: &[u8; SAMPLE_SIZE],
+    ) -> Result<I32x3, &'static str> {
+        let raw = self.decode_raw(bytes);
+        self.check_raw(raw)?;
+        Ok(self.normalize(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctrl_reg_4_value::{Endianness, FullScaleSelection};
+    use crate::mode::Mode;
+
+    #[test]
+    fn little_endian_samples_are_decoded() {
+        let format = super::SampleFormat::new(
+            Mode::HighResolution,
+            FullScaleSelection::Gravity2G,
+            Endianness::LSBLowerAddress,
+        );
+        let bytes = [0x10, 0x3e, 0xf0, 0xc1, 0x00, 0x00];
+        let raw = format.decode_raw(&bytes);
+        assert_eq!((raw.x, raw.y, raw.z), (0x3e10, -0x3e10, 0));
+        let sample = format.decode(&bytes);
+        assert_eq!((sample.x, sample.y, sample.z), (993, -993, 0));
+    }
+
+    #[test]
+    fn big_endian_samples_are_decoded() {
+        let format = super::SampleFormat::new(
+            Mode::HighResolution,
+            FullScaleSelection::Gravity2G,
+            Endianness::MSBLowerAddress,
+        );
+        let bytes = [0x3e, 0x10, 0xc1, 0xf0, 0x00, 0x00];
+        let raw = format.decode_raw(&bytes);
+        assert_eq!((raw.x, raw.y, raw.z), (0x3e10, -0x3e10, 0));
+        let sample = format.decode(&bytes);
+        assert_eq!((sample.x, sample.y, sample.z), (993, -993, 0));
+    }
+
+    #[test]
+    fn samples_are_scaled_according_to_mode_and_range() {
+        let format = super::SampleFormat::new(
+            Mode::LowPower,
+            FullScaleSelection::Gravity16G,
+            Endianness::LSBLowerAddress,
+        );
+        let sample = format.decode(&[0x00, 0x05, 0x00, 0xfb, 0x00, 0x00]);
+        assert_eq!((sample.x, sample.y, sample.z), (960, -960, 0));
+    }
+
+    #[test]
+    fn samples_in_mg_are_encoded_to_output_registers() {
+        let format = super::SampleFormat::new(
+            Mode::Normal,
+            FullScaleSelection::Gravity4G,
+            Endianness::MSBLowerAddress,
+        );
+        let sample = micromath::vector::I32x3 {
+            x: 1000,
+            y: -250,
+            z: 9000,
+        };
+        let bytes = format.encode_raw(format.to_raw(sample));
+        let decoded = format.decode(&bytes);
+        assert_eq!((decoded.x, decoded.y, decoded.z), (1000, -248, 4088));
+    }
+}
@@ -0,0 +1,269 @@
+//! Lock-free single-producer single-consumer queue for handing FIFO samples
+//! from an interrupt handler to the main loop without allocation.
+//!
+//! The queue is split into a [`Producer`], used by the watermark interrupt
+//! handler through [`Producer::on_watermark`], and a [`Consumer`], used by
+//! the main loop through [`Consumer::read_batch`]. Only loads and stores of
+//! atomics are used, so it also works on cores without compare-and-swap.
+
+use crate::fifo_src_reg_value::FIFO_DEPTH;
+use crate::{Error, Lis3dh};
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use hal::blocking::spi::{Transfer, Write};
+use hal::digital::v2::OutputPin;
+use micromath::vector::I32x3;
+
+const EMPTY: I32x3 = I32x3 { x: 0, y: 0, z: 0 };
+/// Initialiser of the buffer slots; every use creates a new cell.
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: UnsafeCell<I32x3> = UnsafeCell::new(EMPTY);
+
+/// Queue holding up to `N - 1` samples.
+pub struct SampleQueue<const N: usize> {
+    buffer: [UnsafeCell<I32x3>; N],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicU32,
+}
+
+// The slots between head and tail belong to the consumer, the others to the
+// producer, and there is only one of each.
+unsafe impl<const N: usize> Sync for SampleQueue<N> {}
+
+impl<const N: usize> Default for SampleQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> SampleQueue<N> {
+    pub const fn new() -> Self {
+        assert!(N > 1, "Sample queue needs at least two slots");
+        SampleQueue {
+            buffer: [EMPTY_SLOT; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicU32::new(0),
+        }
+    }
+    pub const fn capacity(&self) -> usize {
+        N - 1
+    }
+    /// Splits the queue into its two ends, e.g. for a queue in a `static`
+    /// borrowed as `&'static mut`.
+    pub fn split(&mut self) -> (Producer<'_, N>, Consumer<'_, N>) {
+        let dropped = self.dropped.load(Ordering::Acquire);
+        (
+            Producer { queue: self },
+            Consumer {
+                queue: self,
+                reported_dropped: dropped,
+            },
+        )
+    }
+    fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (tail + N - head) % N
+    }
+}
+
+/// Writing end of a [`SampleQueue`].
+pub struct Producer<'a, const N: usize> {
+    queue: &'a SampleQueue<N>,
+}
+
+impl<const N: usize> Producer<'_, N> {
+    /// Adds a sample, giving it back if the queue is full. Rejected samples
+    /// are not counted as dropped.
+    pub fn enqueue(&mut self, sample: I32x3) -> Result<(), I32x3> {
+        let tail = self.queue.tail.load(Ordering::Relaxed);
+        let next = (tail + 1) % N;
+        if next == self.queue.head.load(Ordering::Acquire) {
+            return Err(sample);
+        }
+        unsafe { *self.queue.buffer[tail].get() = sample };
+        self.queue.tail.store(next, Ordering::Release);
+        Ok(())
+    }
+    /// Reads all unread FIFO samples into the queue and returns the number
+    /// of samples queued. Samples not fitting into the queue are dropped and
+    /// reported with the next batch, so the sensor FIFO is always emptied.
+    /// Corrupted samples are left out; the others are queued before
+    /// [`crate::Error::CorruptedFifoSamples`] is returned.
+    pub fn on_watermark<CS, SPI, CsE, SpiE>(
+        &mut self,
+        lis3dh: &mut Lis3dh,
+        cs: &mut CS,
+        spi: &mut SPI,
+    ) -> Result<usize, Error<CsE, SpiE>>
+    where
+        CS: OutputPin<Error = CsE>,
+        SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    {
+        let mut samples = [EMPTY; FIFO_DEPTH as usize];
+        let (len, corrupted) = crate::split_corrupted(
+            lis3dh.get_accel_norm_from_fifo(cs, spi, &mut samples),
+        )?;
+        let valid = len - corrupted.count_ones() as usize;
+        let mut queued = 0;
+        for (index, sample) in samples[..len].iter().enumerate() {
+            if corrupted & 1 << index != 0 {
+                continue;
+            }
+            if self.enqueue(*sample).is_err() {
+                break;
+            }
+            queued += 1;
+        }
+        if queued < valid {
+            let dropped = self.queue.dropped.load(Ordering::Relaxed);
+            self.queue.dropped.store(
+                dropped.wrapping_add((valid - queued) as u32),
+                Ordering::Release,
+            );
+        }
+        if corrupted != 0 {
+            return Err(Error::CorruptedFifoSamples {
+                read: len,
+                corrupted,
+            });
+        }
+        Ok(queued)
+    }
+}
+
+/// Result of [`Consumer::read_batch`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Batch {
+    samples: usize,
+    dropped: u32,
+}
+
+impl Batch {
+    /// Number of samples written into the batch buffer.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+    /// Number of samples dropped by [`Producer::on_watermark`] since the
+    /// previous batch because the queue was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+/// Reading end of a [`SampleQueue`].
+pub struct Consumer<'a, const N: usize> {
+    queue: &'a SampleQueue<N>,
+    reported_dropped: u32,
+}
+
+impl<const N: usize> Consumer<'_, N> {
+    pub fn dequeue(&mut self) -> Option<I32x3> {
+        let head = self.queue.head.load(Ordering::Relaxed);
+        if head == self.queue.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        let sample = unsafe { *self.queue.buffer[head].get() };
+        self.queue.head.store((head + 1) % N, Ordering::Release);
+        Some(sample)
+    }
+    /// Moves the oldest queued samples into `samples`.
+    pub fn read_batch(&mut self, samples: &mut [I32x3]) -> Batch {
+        let dropped = self.queue.dropped.load(Ordering::Acquire);
+        let mut len = 0;
+        for slot in samples.iter_mut() {
+            match self.dequeue() {
+                Some(sample) => *slot = sample,
+                None => break,
+            }
+            len += 1;
+        }
+        let batch = Batch {
+            samples: len,
+            dropped: dropped.wrapping_sub(self.reported_dropped),
+        };
+        self.reported_dropped = dropped;
+        batch
+    }
+    /// Number of queued samples.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SampleQueue;
+    use crate::ctrl_reg_1_value::ODR;
+    use crate::ctrl_reg_5_value::CtrlReg5Value;
+    use crate::enabled_enum::OnOff;
+    use crate::fifo_ctrl_reg_value::{FifoCtrlRegValue, FifoMode};
+    use crate::simulator::Simulator;
+    use crate::Lis3dh;
+    use micromath::vector::I32x3;
+
+    fn sample(z: i32) -> I32x3 {
+        I32x3 { x: 0, y: 0, z }
+    }
+
+    #[test]
+    fn samples_wrap_around_the_buffer() {
+        let mut queue = SampleQueue::<4>::new();
+        let (mut producer, mut consumer) = queue.split();
+        let mut batch = [sample(0); 2];
+        for round in 0..5 {
+            assert_eq!(producer.enqueue(sample(2 * round)), Ok(()));
+            assert_eq!(producer.enqueue(sample(2 * round + 1)), Ok(()));
+            assert_eq!(consumer.len(), 2);
+            assert_eq!(consumer.read_batch(&mut batch).samples(), 2);
+            assert_eq!(batch, [sample(2 * round), sample(2 * round + 1)]);
+        }
+        for z in 0..3 {
+            producer.enqueue(sample(z)).unwrap();
+        }
+        assert_eq!(producer.enqueue(sample(3)), Err(sample(3)));
+        assert_eq!(consumer.dequeue(), Some(sample(0)));
+    }
+
+    #[test]
+    fn watermark_drains_fifo_and_counts_overflow() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(ODR::Hz100);
+        let mut ctrl_reg5 = CtrlReg5Value::default();
+        ctrl_reg5.set_fifo(OnOff::Enabled);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut fifo_ctrl = FifoCtrlRegValue::default();
+        fifo_ctrl.set_fifo_mode(FifoMode::Stream);
+        lis3dh.write(&mut cs, &mut spi, fifo_ctrl).unwrap();
+
+        let mut queue = SampleQueue::<25>::new();
+        let (mut producer, mut consumer) = queue.split();
+        let mut batch = [sample(0); 32];
+        for z in 0..20 {
+            simulator.inject_sample(sample(16 * z));
+        }
+        let queued = producer.on_watermark(&mut lis3dh, &mut cs, &mut spi);
+        assert_eq!(queued.unwrap(), 20);
+        for z in 20..30 {
+            simulator.inject_sample(sample(16 * z));
+        }
+        let queued = producer.on_watermark(&mut lis3dh, &mut cs, &mut spi);
+        assert_eq!(queued.unwrap(), 4);
+        assert_eq!(simulator.fifo_len(), 0);
+
+        let first = consumer.read_batch(&mut batch);
+        assert_eq!((first.samples(), first.dropped()), (24, 6));
+        assert_eq!((batch[0].z, batch[23].z), (0, 16 * 23));
+        let second = consumer.read_batch(&mut batch);
+        assert_eq!((second.samples(), second.dropped()), (0, 0));
+        assert!(consumer.is_empty());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::register::Register;
+use crate::sample_format::{SampleFormat, SAMPLE_SIZE};
+use crate::timestamp::Clock;
+use crate::{Error, Lis3dh, RegisterAddresses};
+use hal::blocking::spi::{Transfer, Write};
+use hal::digital::v2::OutputPin;
+use micromath::vector::I32x3;
+
+/// Error of one sensor of a [`SensorArray`].
+#[derive(Debug)]
+pub struct SensorError<CsE, SpiE> {
+    /// Index of the failing sensor.
+    pub sensor: usize,
+    pub error: Error<CsE, SpiE>,
+}
+
+/// Samples of all sensors of a [`SensorArray`] read back-to-back.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleSet<const N: usize> {
+    samples: [I32x3; N],
+    timestamp_us: u64,
+    skew_us: u64,
+}
+
+impl<const N: usize> SampleSet<N> {
+    /// Calibrated samples in mg, in the order of the sensors.
+    pub fn samples(&self) -> &[I32x3; N] {
+        &self.samples
+    }
+    /// Time before the first sensor was read.
+    pub fn timestamp_us(&self) -> u64 {
+        self.timestamp_us
+    }
+    /// Time reading the output registers of all sensors took.
+    pub fn skew_us(&self) -> u64 {
+        self.skew_us
+    }
+}
+
+/// Several LIS3DH on one SPI bus, each with its own chip select and
+/// settings.
+///
+/// The chip select pins have to be of the same type; use an enum or a
+/// type-erased pin for pins of different ports.
+pub struct SensorArray<SPI, CS, const N: usize> {
+    spi: SPI,
+    chip_selects: [CS; N],
+    sensors: [Lis3dh; N],
+}
+
+impl<SPI, CS, CsE, SpiE, const N: usize> SensorArray<SPI, CS, N>
+where
+    CS: OutputPin<Error = CsE>,
+    SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+{
+    /// Takes the bus and the chip select pins, deselecting all sensors.
+    pub fn new(
+        spi: SPI,
+        mut chip_selects: [CS; N],
+    ) -> Result<Self, SensorError<CsE, SpiE>> {
+        for (sensor, cs) in chip_selects.iter_mut().enumerate() {
+            cs.set_high().map_err(|error| SensorError {
+                sensor,
+                error: Error::ChipSelectError(error),
+            })?;
+        }
+        Ok(SensorArray {
+            spi,
+            chip_selects,
+            sensors: [Lis3dh::default(); N],
+        })
+    }
+    /// Releases the bus and the chip select pins.
+    pub fn release(self) -> (SPI, [CS; N]) {
+        (self.spi, self.chip_selects)
+    }
+    pub fn len(&self) -> usize {
+        N
+    }
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+    /// Settings of a sensor, e.g. to change them before
+    /// [`SensorArray::apply_changes`].
+    pub fn sensor(&mut self, sensor: usize) -> Option<&mut Lis3dh> {
+        self.sensors.get_mut(sensor)
+    }
+    /// Runs a driver call for one sensor, e.g.
+    /// `array.with_sensor(1, |lis3dh, cs, spi| lis3dh.get_who_am_i(cs, spi))`.
+    /// Returns `None` if there is no sensor with the index.
+    pub fn with_sensor<T, F>(
+        &mut self,
+        sensor: usize,
+        call: F,
+    ) -> Option<Result<T, SensorError<CsE, SpiE>>>
+    where
+        F: FnOnce(
+            &mut Lis3dh,
+            &mut CS,
+            &mut SPI,
+        ) -> Result<T, Error<CsE, SpiE>>,
+    {
+        let lis3dh = self.sensors.get_mut(sensor)?;
+        let cs = &mut self.chip_selects[sensor];
+        Some(
+            call(lis3dh, cs, &mut self.spi)
+                .map_err(|error| SensorError { sensor, error }),
+        )
+    }
+    /// Writes the changed settings of every sensor.
+    pub fn apply_changes(&mut self) -> Result<(), SensorError<CsE, SpiE>> {
+        for sensor in 0..N {
+            self.call(sensor, |lis3dh, cs, spi| lis3dh.apply_changes(cs, spi))?;
+        }
+        Ok(())
+    }
+    /// Reads the calibrated acceleration in mg of all sensors. The output
+    /// registers are read back-to-back and converted afterwards, using the
+    /// sample format of the settings, to keep the skew between the sensors
+    /// small.
+    pub fn read_all(&mut self) -> Result<[I32x3; N], SensorError<CsE, SpiE>> {
+        let bytes = self.read_output_registers()?;
+        self.convert(&bytes)
+    }
+    /// Like [`SensorArray::read_all`], recording when the reads started and
+    /// how long they took.
+    pub fn read_all_timestamped<C: Clock>(
+        &mut self,
+        clock: &mut C,
+    ) -> Result<SampleSet<N>, SensorError<CsE, SpiE>> {
+        let timestamp_us = clock.now_us();
+        let bytes = self.read_output_registers()?;
+        let skew_us = clock.now_us().saturating_sub(timestamp_us);
+        Ok(SampleSet {
+            samples: self.convert(&bytes)?,
+            timestamp_us,
+            skew_us,
+        })
+    }
+    /// Writes the same register value to every sensor.
+    pub fn write_all<R: Register>(
+        &mut self,
+        value: R,
+    ) -> Result<(), SensorError<CsE, SpiE>> {
+        for sensor in 0..N {
+            self.call(sensor, |lis3dh, cs, spi| lis3dh.write(cs, spi, value))?;
+        }
+        Ok(())
+    }
+    /// [`SensorArray::with_sensor`] for the indices below `N` used by the
+    /// calls on every sensor.
+    fn call<T, F>(
+        &mut self,
+        sensor: usize,
+        call: F,
+    ) -> Result<T, SensorError<CsE, SpiE>>
+    where
+        F: FnOnce(
+            &mut Lis3dh,
+            &mut CS,
+            &mut SPI,
+        ) -> Result<T, Error<CsE, SpiE>>,
+    {
+        self.with_sensor(sensor, call)
+            .expect("Sensor index is below the array length")
+    }
+    fn read_output_registers(
+        &mut self,
+    ) -> Result<[[u8; SAMPLE_SIZE]; N], SensorError<CsE, SpiE>> {
+        let mut bytes = [[0xff; SAMPLE_SIZE]; N];
+        for (sensor, buffer) in bytes.iter_mut().enumerate() {
+            self.call(sensor, |lis3dh, cs, spi| {
+                lis3dh.read_multiple_bytes_from_spi(
+                    cs,
+                    spi,
+                    RegisterAddresses::OutXL as u8,
+                    buffer,
+                )
+            })?;
+        }
+        Ok(bytes)
+    }
+    fn convert(
+        &self,
+        bytes: &[[u8; SAMPLE_SIZE]; N],
+    ) -> Result<[I32x3; N], SensorError<CsE, SpiE>> {
+        let mut samples = [I32x3 { x: 0, y: 0, z: 0 }; N];
+        for (sensor, sample) in samples.iter_mut().enumerate() {
+            let lis3dh = &self.sensors[sensor];
+            let decoded = SampleFormat::from_settings(
+                &lis3dh.ctrl_reg1_setting(),
+                &lis3dh.ctrl_reg4_setting(),
+            )
+            .and_then(|format| format.decode_checked(&bytes[sensor]))
+            .map_err(|message| SensorError {
+                sensor,
+                error: Error::InvalidRegisterValue(message),
+            })?;
+            *sample = lis3dh.calibrate(lis3dh.axis_map().apply(decoded));
+        }
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SensorArray;
+    use crate::ctrl_reg_1_value::ODR;
+    use crate::ctrl_reg_4_value::FullScaleSelection;
+    use crate::fifo_ctrl_reg_value::{FifoCtrlRegValue, FifoMode};
+    use crate::simulator::{SimulatedBus, Simulator};
+    use crate::RegisterAddresses;
+    use micromath::vector::I32x3;
+
+    #[test]
+    fn every_sensor_keeps_its_own_settings() {
+        let simulators = [Simulator::new(), Simulator::new()];
+        let bus = SimulatedBus::new([&simulators[0], &simulators[1]]);
+        let chip_selects =
+            [simulators[0].chip_select(), simulators[1].chip_select()];
+        let mut array = SensorArray::new(bus, chip_selects).unwrap();
+        array.sensor(0).unwrap().set_output_data_rate(ODR::Hz100);
+        array.sensor(1).unwrap().set_output_data_rate(ODR::Hz400);
+        array.apply_changes().unwrap();
+        assert_eq!(simulators[0].register(RegisterAddresses::CtrlReg1), 0x57);
+        assert_eq!(simulators[1].register(RegisterAddresses::CtrlReg1), 0x77);
+        assert!(array.sensor(2).is_none());
+
+        let mut fifo_ctrl = FifoCtrlRegValue::default();
+        fifo_ctrl.set_fifo_mode(FifoMode::Stream);
+        array.write_all(fifo_ctrl).unwrap();
+        for simulator in &simulators {
+            assert_eq!(
+                simulator.register(RegisterAddresses::FifoCtrlReg),
+                0x80
+            );
+        }
+        let who_am_i = array
+            .with_sensor(1, |lis3dh, cs, spi| lis3dh.get_who_am_i(cs, spi))
+            .unwrap()
+            .unwrap();
+        assert_eq!(who_am_i, 0x33);
+        assert!(array
+            .with_sensor(2, |lis3dh, cs, spi| lis3dh.get_who_am_i(cs, spi))
+            .is_none());
+    }
+
+    #[test]
+    fn all_sensors_are_read_back_to_back() {
+        let simulators = [Simulator::new(), Simulator::new(), Simulator::new()];
+        let bus =
+            SimulatedBus::new([&simulators[0], &simulators[1], &simulators[2]]);
+        let chip_selects = [
+            simulators[0].chip_select(),
+            simulators[1].chip_select(),
+            simulators[2].chip_select(),
+        ];
+        let mut array = SensorArray::new(bus, chip_selects).unwrap();
+        for sensor in 0..3 {
+            array
+                .sensor(sensor)
+                .unwrap()
+                .set_output_data_rate(ODR::Hz100);
+        }
+        let mut ctrl_reg4 = array.sensor(2).unwrap().ctrl_reg4_setting();
+        ctrl_reg4.set_fs(FullScaleSelection::Gravity8G);
+        array.sensor(2).unwrap().set_ctrl_reg4_setting(ctrl_reg4);
+        array.apply_changes().unwrap();
+        for (index, simulator) in simulators.iter().enumerate() {
+            simulator.inject_sample(I32x3 {
+                x: 0,
+                y: 0,
+                z: 1024 + 64 * index as i32,
+            });
+        }
+        let mut now = 0;
+        let mut clock = || {
+            now += 40;
+            now
+        };
+        let set = array.read_all_timestamped(&mut clock).unwrap();
+        let z: [i32; 3] = set.samples().map(|sample| sample.z);
+        assert_eq!(z, [1024, 1088, 1152]);
+        assert_eq!((set.timestamp_us(), set.skew_us()), (40, 40));
+    }
+}
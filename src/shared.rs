@@ -0,0 +1,193 @@
+//! Driver wrapper for sharing a LIS3DH between interrupt handlers and the
+//! main loop, e.g. reading the FIFO on the watermark interrupt while the main
+//! loop reconfigures the sensor.
+//!
+//! [`SharedLis3dh::new`] is `const`, so the wrapper can be stored in a
+//! `static` and initialised with [`SharedLis3dh::init`] once the bus is set
+//! up.
+//!
+//! Every call gets the settings, the chip select and the bus together, so a
+//! SPI transfer is never interrupted by another one. What is still up to the
+//! caller is keeping the settings consistent between calls:
+//!
+//! * Changing settings and writing them with `apply_changes` belong into one
+//!   closure. An interrupt handler converting samples between the two would
+//!   use the new sample format for data of the old one.
+//! * Reading samples, `service_interrupt` and reading registers can be
+//!   interleaved freely with each other and with reconfiguration done in one
+//!   closure.
+//! * Changing the FIFO mode or the data rate empties the FIFO; a watermark
+//!   interrupt pending from before has to be ignored by its handler.
+
+use crate::Lis3dh;
+use core::cell::RefCell;
+use critical_section::Mutex;
+
+/// Why a [`SharedLis3dh`] could not be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SharedError {
+    /// [`SharedLis3dh::init`] has not been called yet.
+    Uninitialized,
+    /// Another context is using the driver with [`SharedLis3dh::try_lock`].
+    Busy,
+}
+
+// Stored in a `static` without heap, so the driver can not be boxed.
+#[allow(clippy::large_enum_variant)]
+enum State<CS, SPI> {
+    Uninitialized,
+    Idle(Lis3dh, CS, SPI),
+    Busy,
+}
+
+/// A [`Lis3dh`] with its chip select and bus, usable from several contexts.
+pub struct SharedLis3dh<CS, SPI> {
+    state: Mutex<RefCell<State<CS, SPI>>>,
+}
+
+impl<CS, SPI> Default for SharedLis3dh<CS, SPI> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<CS, SPI> SharedLis3dh<CS, SPI> {
+    pub const fn new() -> Self {
+        SharedLis3dh {
+            state: Mutex::new(RefCell::new(State::Uninitialized)),
+        }
+    }
+    /// Stores the driver, returning the previously stored one.
+    pub fn init(
+        &self,
+        lis3dh: Lis3dh,
+        cs: CS,
+        spi: SPI,
+    ) -> Option<(Lis3dh, CS, SPI)> {
+        critical_section::with(|token| {
+            let mut state = self.state.borrow_ref_mut(token);
+            match core::mem::replace(&mut *state, State::Idle(lis3dh, cs, spi))
+            {
+                State::Idle(lis3dh, cs, spi) => Some((lis3dh, cs, spi)),
+                _ => None,
+            }
+        })
+    }
+    /// Takes the driver back out, e.g. to release the bus.
+    pub fn release(&self) -> Result<(Lis3dh, CS, SPI), SharedError> {
+        critical_section::with(|token| {
+            let driver = self.take(token)?;
+            *self.state.borrow_ref_mut(token) = State::Uninitialized;
+            Ok(driver)
+        })
+    }
+    /// Runs `call` inside a critical section. Interrupts are masked for the
+    /// whole call, so keep it short, e.g. one register write or FIFO read.
+    /// Returns [`SharedError::Busy`] while an interrupted context is inside
+    /// [`SharedLis3dh::try_lock`].
+    pub fn lock<R, F>(&self, call: F) -> Result<R, SharedError>
+    where
+        F: FnOnce(&mut Lis3dh, &mut CS, &mut SPI) -> R,
+    {
+        critical_section::with(|token| {
+            match &mut *self.state.borrow_ref_mut(token) {
+                State::Idle(lis3dh, cs, spi) => Ok(call(lis3dh, cs, spi)),
+                State::Uninitialized => Err(SharedError::Uninitialized),
+                State::Busy => Err(SharedError::Busy),
+            }
+        })
+    }
+    /// Runs `call` with interrupts enabled, returning [`SharedError::Busy`]
+    /// right away if an interrupted context is using the driver. Never
+    /// blocks, so it can be used in interrupt handlers.
+    ///
+    /// A handler getting `Busy` must not return with its interrupt still
+    /// pending: the interrupted context only resumes once the handler is
+    /// done, so the handler would be entered again right away and never let
+    /// it release the driver. Instead the handler masks its interrupt or
+    /// sets a flag and returns, and the context holding the driver re-pends
+    /// the interrupt or handles the flag after the call finished.
+    pub fn try_lock<R, F>(&self, call: F) -> Result<R, SharedError>
+    where
+        F: FnOnce(&mut Lis3dh, &mut CS, &mut SPI) -> R,
+    {
+        let (mut lis3dh, mut cs, mut spi) =
+            critical_section::with(|token| self.take(token))?;
+        let result = call(&mut lis3dh, &mut cs, &mut spi);
+        critical_section::with(|token| {
+            *self.state.borrow_ref_mut(token) = State::Idle(lis3dh, cs, spi);
+        });
+        Ok(result)
+    }
+    fn take(
+        &self,
+        token: critical_section::CriticalSection,
+    ) -> Result<(Lis3dh, CS, SPI), SharedError> {
+        let mut state = self.state.borrow_ref_mut(token);
+        match core::mem::replace(&mut *state, State::Busy) {
+            State::Idle(lis3dh, cs, spi) => Ok((lis3dh, cs, spi)),
+            previous => {
+                let error = match previous {
+                    State::Uninitialized => SharedError::Uninitialized,
+                    _ => SharedError::Busy,
+                };
+                *state = previous;
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SharedError, SharedLis3dh};
+    use crate::ctrl_reg_1_value::ODR;
+    use crate::simulator::Simulator;
+    use crate::{Lis3dh, RegisterAddresses};
+
+    #[test]
+    fn driver_is_used_through_the_wrapper() {
+        let simulator = Simulator::new();
+        let shared = SharedLis3dh::new();
+        assert_eq!(
+            shared.lock(|_, _, _| ()).unwrap_err(),
+            SharedError::Uninitialized
+        );
+        assert!(shared
+            .init(Lis3dh::default(), simulator.chip_select(), simulator.spi())
+            .is_none());
+        shared
+            .lock(|lis3dh, cs, spi| {
+                lis3dh.set_output_data_rate(ODR::Hz100);
+                lis3dh.apply_changes(cs, spi)
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(simulator.register(RegisterAddresses::CtrlReg1), 0x57);
+        let who_am_i = shared
+            .try_lock(|lis3dh, cs, spi| lis3dh.get_who_am_i(cs, spi))
+            .unwrap()
+            .unwrap();
+        assert_eq!(who_am_i, 0x33);
+        assert!(shared.release().is_ok());
+        assert!(matches!(shared.release(), Err(SharedError::Uninitialized)));
+    }
+
+    #[test]
+    fn interrupting_context_sees_busy_driver() {
+        let simulator = Simulator::new();
+        let shared = SharedLis3dh::new();
+        shared.init(
+            Lis3dh::default(),
+            simulator.chip_select(),
+            simulator.spi(),
+        );
+        let nested = shared
+            .try_lock(|_, _, _| {
+                (shared.try_lock(|_, _, _| ()), shared.lock(|_, _, _| ()))
+            })
+            .unwrap();
+        assert_eq!(nested, (Err(SharedError::Busy), Err(SharedError::Busy)));
+        assert!(shared.lock(|_, _, _| ()).is_ok());
+    }
+}
@@ -0,0 +1,746 @@
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+use crate::ctrl_reg_4_value::CtrlReg4Value;
+use crate::ctrl_reg_5_value::CtrlReg5Value;
+use crate::enabled_enum::OnOff;
+use crate::fifo_ctrl_reg_value::{
+    FifoCtrlRegValue, FifoMode, TriggerSelection,
+};
+use crate::fifo_src_reg_value::FIFO_DEPTH;
+use crate::int_cfg::IntCfg;
+use crate::int_src::IntSrc;
+use crate::int_ths_value::threshold_step_mg;
+use crate::register::Register;
+use crate::sample_format::{SampleFormat, SAMPLE_SIZE};
+use crate::{
+    is_read_only, is_reserved, RegisterAddresses, SPI_AUTO_INCREMENT_BIT,
+    SPI_READ_BIT,
+};
+use core::cell::RefCell;
+use core::convert::Infallible;
+use hal::blocking::spi::{Transfer, Write};
+use hal::digital::v2::OutputPin;
+use micromath::vector::{I16x3, I32x3};
+
+/// Content of WHO_AM_I.
+pub const WHO_AM_I_VALUE: u8 = 0x33;
+
+const REGISTER_FILE_SIZE: usize = 0x40;
+const ADDRESS_MASK: u8 = 0x3f;
+
+const FIFO_STORED_SAMPLES_MASK: u8 = 0b1_1111;
+
+const FIFO_EMPTY_BIT_OFFSET: u8 = 5;
+const FIFO_OVERRUN_BIT_OFFSET: u8 = 6;
+const FIFO_WATERMARK_BIT_OFFSET: u8 = 7;
+
+const BOOT_BIT_OFFSET: u8 = 7;
+const NEW_DATA_AVAILABLE_BITS: u8 = 0x0f;
+const DATA_OVERRUN_BITS: u8 = 0xf0;
+
+const INTERRUPT_ACTIVE_BIT_OFFSET: u8 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimulatorError {
+    /// The bus was used while chip select was high.
+    ChipNotSelected,
+    /// Several sensors sharing a bus were selected at the same time.
+    BusContention,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum InterruptGenerator {
+    Int1,
+    Int2,
+}
+
+impl InterruptGenerator {
+    fn triggering(trigger_selection: TriggerSelection) -> Self {
+        match trigger_selection {
+            TriggerSelection::Int1 => InterruptGenerator::Int1,
+            TriggerSelection::Int2 => InterruptGenerator::Int2,
+        }
+    }
+    fn cfg(&self) -> RegisterAddresses {
+        match self {
+            InterruptGenerator::Int1 => RegisterAddresses::Int1Cfg,
+            InterruptGenerator::Int2 => RegisterAddresses::Int2Cfg,
+        }
+    }
+    fn src(&self) -> RegisterAddresses {
+        match self {
+            InterruptGenerator::Int1 => RegisterAddresses::Int1Src,
+            InterruptGenerator::Int2 => RegisterAddresses::Int2Src,
+        }
+    }
+    fn threshold(&self) -> RegisterAddresses {
+        match self {
+            InterruptGenerator::Int1 => RegisterAddresses::Int1Threshold,
+            InterruptGenerator::Int2 => RegisterAddresses::Int2Threshold,
+        }
+    }
+    fn is_latched(&self, ctrl_reg5: &CtrlReg5Value) -> bool {
+        let latch = match self {
+            InterruptGenerator::Int1 => ctrl_reg5.latch_int_on_int_1_src(),
+            InterruptGenerator::Int2 => ctrl_reg5.latch_int_on_int_2_src(),
+        };
+        latch == OnOff::Enabled
+    }
+}
+
+struct State {
+    registers: [u8; REGISTER_FILE_SIZE],
+    fifo: [[u8; SAMPLE_SIZE]; FIFO_DEPTH as usize],
+    fifo_start: usize,
+    fifo_len: usize,
+    stream_to_fifo_triggered: bool,
+    selected: bool,
+    address: Option<u8>,
+    reading: bool,
+    auto_increment: bool,
+}
+
+impl State {
+    fn new() -> Self {
+        let mut registers = [0; REGISTER_FILE_SIZE];
+        reset_registers(&mut registers);
+        State {
+            registers,
+            fifo: [[0; SAMPLE_SIZE]; FIFO_DEPTH as usize],
+            fifo_start: 0,
+            fifo_len: 0,
+            stream_to_fifo_triggered: false,
+            selected: false,
+            address: None,
+            reading: false,
+            auto_increment: false,
+        }
+    }
+    fn ctrl_reg4(&self) -> CtrlReg4Value {
+        CtrlReg4Value::try_from(
+            self.registers[RegisterAddresses::CtrlReg4 as usize],
+        )
+        .unwrap_or_default()
+    }
+    fn ctrl_reg5(&self) -> CtrlReg5Value {
+        CtrlReg5Value::try_from(
+            self.registers[RegisterAddresses::CtrlReg5 as usize],
+        )
+        .unwrap_or_default()
+    }
+    fn sample_format(&self) -> SampleFormat {
+        let ctrl_reg1 = CtrlReg1Value::try_from(
+            self.registers[RegisterAddresses::CtrlReg1 as usize],
+        )
+        .unwrap_or_default();
+        SampleFormat::from_settings(&ctrl_reg1, &self.ctrl_reg4())
+            .unwrap_or_else(|_| {
+                SampleFormat::new(
+                    crate::mode::Mode::Normal,
+                    self.ctrl_reg4().fs(),
+                    self.ctrl_reg4().ble(),
+                )
+            })
+    }
+    fn fifo_ctrl(&self) -> FifoCtrlRegValue {
+        FifoCtrlRegValue::try_from(
+            self.registers[RegisterAddresses::FifoCtrlReg as usize],
+        )
+        .unwrap_or_default()
+    }
+    fn fifo_mode(&self) -> FifoMode {
+        if self.ctrl_reg5().fifo() == OnOff::Disabled {
+            return FifoMode::Bypass;
+        }
+        self.fifo_ctrl().fifo_mode()
+    }
+    fn fifo_src(&self) -> u8 {
+        let len = self.fifo_len as u8;
+        ((len > self.fifo_ctrl().threshold()) as u8)
+            << FIFO_WATERMARK_BIT_OFFSET
+            | ((len == FIFO_DEPTH) as u8) << FIFO_OVERRUN_BIT_OFFSET
+            | ((len == 0) as u8) << FIFO_EMPTY_BIT_OFFSET
+            | len & FIFO_STORED_SAMPLES_MASK
+    }
+    fn clear_fifo(&mut self) {
+        self.fifo_start = 0;
+        self.fifo_len = 0;
+        self.stream_to_fifo_triggered = false;
+    }
+    fn push_to_fifo(&mut self, bytes: [u8; SAMPLE_SIZE]) {
+        let stop_when_full = match self.fifo_mode() {
+            FifoMode::Bypass => return,
+            FifoMode::Fifo => true,
+            FifoMode::Stream => false,
+            FifoMode::StreamToFifo => self.stream_to_fifo_triggered,
+        };
+        if self.fifo_len == FIFO_DEPTH as usize {
+            if stop_when_full {
+                return;
+            }
+            self.fifo_start = (self.fifo_start + 1) % FIFO_DEPTH as usize;
+            self.fifo_len -= 1;
+        }
+        let end = (self.fifo_start + self.fifo_len) % FIFO_DEPTH as usize;
+        self.fifo[end] = bytes;
+        self.fifo_len += 1;
+    }
+    fn pop_from_fifo(&mut self) {
+        if self.fifo_mode() == FifoMode::Bypass || self.fifo_len == 0 {
+            return;
+        }
+        let bytes = self.fifo[self.fifo_start];
+        self.fifo_start = (self.fifo_start + 1) % FIFO_DEPTH as usize;
+        self.fifo_len -= 1;
+        self.set_output_registers(bytes);
+    }
+    fn set_output_registers(&mut self, bytes: [u8; SAMPLE_SIZE]) {
+        let first = RegisterAddresses::OutXL as usize;
+        self.registers[first..first + SAMPLE_SIZE].copy_from_slice(&bytes);
+    }
+    fn update_interrupt_source(
+        &mut self,
+        generator: InterruptGenerator,
+        sample: I32x3,
+    ) {
+        let src = generator.src() as usize;
+        let latched = generator.is_latched(&self.ctrl_reg5());
+        if latched
+            && (self.registers[src] >> INTERRUPT_ACTIVE_BIT_OFFSET) & 1 == 1
+        {
+            return;
+        }
+        let cfg = IntCfg::try_from(self.registers[generator.cfg() as usize])
+            .unwrap_or_default();
+        let threshold = self.registers[generator.threshold() as usize] as u32
+            * threshold_step_mg(self.ctrl_reg4().fs());
+        self.registers[src] =
+            IntSrc::evaluate(&cfg, threshold, sample).raw_value();
+    }
+    fn is_interrupt_active(&self, generator: InterruptGenerator) -> bool {
+        (self.registers[generator.src() as usize]
+            >> INTERRUPT_ACTIVE_BIT_OFFSET)
+            & 1
+            == 1
+    }
+    fn inject(&mut self, raw: I16x3) {
+        let format = self.sample_format();
+        let bytes = format.encode_raw(raw);
+        self.set_output_registers(bytes);
+        let status = RegisterAddresses::StatusReg as usize;
+        if self.registers[status] & NEW_DATA_AVAILABLE_BITS != 0 {
+            self.registers[status] |= DATA_OVERRUN_BITS;
+        }
+        self.registers[status] |= NEW_DATA_AVAILABLE_BITS;
+
+        let sample = format.normalize(raw);
+        self.update_interrupt_source(InterruptGenerator::Int1, sample);
+        self.update_interrupt_source(InterruptGenerator::Int2, sample);
+        // The sample firing the trigger is the last one streamed.
+        self.push_to_fifo(bytes);
+        let trigger = InterruptGenerator::triggering(
+            self.fifo_ctrl().trigger_selection(),
+        );
+        if self.fifo_mode() == FifoMode::StreamToFifo
+            && self.is_interrupt_active(trigger)
+        {
+            self.stream_to_fifo_triggered = true;
+        }
+    }
+    fn read_register(&mut self, address: u8) -> u8 {
+        if is_reserved(address) {
+            return 0;
+        }
+        let index = address as usize;
+        match address {
+            a if a == RegisterAddresses::OutXL as u8 => self.pop_from_fifo(),
+            a if a == RegisterAddresses::FifSrcReg as u8 => {
+                return self.fifo_src()
+            }
+            _ => (),
+        }
+        let value = self.registers[index];
+        if address == RegisterAddresses::OutZH as u8 {
+            self.registers[RegisterAddresses::StatusReg as usize] = 0;
+        }
+        let ctrl_reg5 = self.ctrl_reg5();
+        for generator in [InterruptGenerator::Int1, InterruptGenerator::Int2] {
+            if address == generator.src() as u8
+                && generator.is_latched(&ctrl_reg5)
+            {
+                self.registers[index] = 0;
+            }
+        }
+        value
+    }
+    fn write_register(&mut self, address: u8, value: u8) {
+        if is_reserved(address) || is_read_only(address) {
+            return;
+        }
+        let fifo_mode = self.fifo_mode();
+        self.registers[address as usize] = value;
+        if address == RegisterAddresses::CtrlReg5 as u8
+            && (value >> BOOT_BIT_OFFSET) & 1 == 1
+        {
+            reset_registers(&mut self.registers);
+        }
+        if address == RegisterAddresses::FifoCtrlReg as u8
+            || self.fifo_mode() != fifo_mode
+        {
+            self.stream_to_fifo_triggered = false;
+            if self.fifo_mode() == FifoMode::Bypass {
+                self.clear_fifo();
+            }
+        }
+    }
+    fn next_address(&mut self) -> Option<u8> {
+        let address = self.address?;
+        if self.auto_increment {
+            self.address = Some((address + 1) & ADDRESS_MASK);
+        }
+        Some(address)
+    }
+    fn exchange(&mut self, word: u8) -> u8 {
+        match self.next_address() {
+            None => {
+                self.address = Some(word & ADDRESS_MASK);
+                self.reading = word & SPI_READ_BIT != 0;
+                self.auto_increment = word & SPI_AUTO_INCREMENT_BIT != 0;
+                0xff
+            }
+            Some(address) if self.reading => self.read_register(address),
+            Some(address) => {
+                self.write_register(address, word);
+                0xff
+            }
+        }
+    }
+}
+
+fn reset_registers(registers: &mut [u8; REGISTER_FILE_SIZE]) {
+    *registers = [0; REGISTER_FILE_SIZE];
+    registers[RegisterAddresses::WhoAmI as usize] = WHO_AM_I_VALUE;
+    registers[RegisterAddresses::CtrlReg0 as usize] = 0b0001_0000;
+    registers[RegisterAddresses::CtrlReg1 as usize] = 0b0000_0111;
+}
+
+/// Software model of the LIS3DH register file for testing without hardware.
+///
+/// The simulator hands out a chip select pin and an SPI bus implementing the
+/// traits used by [`crate::Lis3dh`]. It models reset values, reserved and
+/// read-only addresses, address auto-increment, latched interrupt sources
+/// and the FIFO in all four modes. Interrupt generators compare the absolute
+/// acceleration of every injected sample with their threshold; duration and
+/// 6D detection are not modelled.
+pub struct Simulator {
+    state: RefCell<State>,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Simulator::new()
+    }
+}
+
+impl Simulator {
+    pub fn new() -> Self {
+        Simulator {
+            state: RefCell::new(State::new()),
+        }
+    }
+    pub fn chip_select(&self) -> SimulatedChipSelect<'_> {
+        SimulatedChipSelect { simulator: self }
+    }
+    pub fn spi(&self) -> SimulatedSpi<'_> {
+        SimulatedSpi { simulator: self }
+    }
+    /// Returns the stored register content without the side effects of a
+    /// bus read.
+    pub fn register(&self, register: RegisterAddresses) -> u8 {
+        let state = self.state.borrow();
+        match register {
+            RegisterAddresses::FifSrcReg => state.fifo_src(),
+            _ => state.registers[register as usize],
+        }
+    }
+    /// Overwrites a register, including read-only ones, without the side
+    /// effects of a bus write.
+    pub fn set_register(&self, register: RegisterAddresses, value: u8) {
+        self.state.borrow_mut().registers[register as usize] = value;
+    }
+    pub fn fifo_len(&self) -> usize {
+        self.state.borrow().fifo_len
+    }
+    /// Makes a new left-justified raw sample available as if the sensor
+    /// had measured it.
+    pub fn inject_raw_sample(&self, raw: I16x3) {
+        self.state.borrow_mut().inject(raw);
+    }
+    /// Makes a new sample given in mg available, converted with the
+    /// currently configured resolution and range.
+    pub fn inject_sample(&self, sample: I32x3) {
+        let mut state = self.state.borrow_mut();
+        let raw = state.sample_format().to_raw(sample);
+        state.inject(raw);
+    }
+    /// Sets the auxiliary ADC outputs using the configured byte order.
+    pub fn set_adc_values(&self, values: [u16; 3]) {
+        let mut state = self.state.borrow_mut();
+        let endianness = state.ctrl_reg4().ble();
+        let first = RegisterAddresses::OutAdc1L as usize;
+        for (channel, value) in values.iter().enumerate() {
+            let bytes = endianness.encode_i16(*value as i16);
+            state.registers[first + 2 * channel] = bytes[0];
+            state.registers[first + 2 * channel + 1] = bytes[1];
+        }
+    }
+}
+
+pub struct SimulatedChipSelect<'a> {
+    simulator: &'a Simulator,
+}
+
+impl OutputPin for SimulatedChipSelect<'_> {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let mut state = self.simulator.state.borrow_mut();
+        state.selected = true;
+        state.address = None;
+        Ok(())
+    }
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.simulator.state.borrow_mut().selected = false;
+        Ok(())
+    }
+}
+
+pub struct SimulatedSpi<'a> {
+    simulator: &'a Simulator,
+}
+
+impl Write<u8> for SimulatedSpi<'_> {
+    type Error = SimulatorError;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let mut state = self.simulator.state.borrow_mut();
+        if !state.selected {
+            return Err(SimulatorError::ChipNotSelected);
+        }
+        for word in words {
+            state.exchange(*word);
+        }
+        Ok(())
+    }
+}
+
+impl Transfer<u8> for SimulatedSpi<'_> {
+    type Error = SimulatorError;
+
+    fn transfer<'w>(
+        &mut self,
+        words: &'w mut [u8],
+    ) -> Result<&'w [u8], Self::Error> {
+        let mut state = self.simulator.state.borrow_mut();
+        if !state.selected {
+            return Err(SimulatorError::ChipNotSelected);
+        }
+        for word in words.iter_mut() {
+            *word = state.exchange(*word);
+        }
+        Ok(words)
+    }
+}
+
+/// SPI bus shared by several simulators with their own chip select. Only
+/// the selected simulator responds.
+pub struct SimulatedBus<'a, const N: usize> {
+    simulators: [&'a Simulator; N],
+}
+
+impl<'a, const N: usize> SimulatedBus<'a, N> {
+    pub fn new(simulators: [&'a Simulator; N]) -> Self {
+        SimulatedBus { simulators }
+    }
+    fn selected(&self) -> Result<SimulatedSpi<'a>, SimulatorError> {
+        let mut selected = self
+            .simulators
+            .iter()
+            .filter(|simulator| simulator.state.borrow().selected);
+        let simulator =
+            selected.next().ok_or(SimulatorError::ChipNotSelected)?;
+        if selected.next().is_some() {
+            return Err(SimulatorError::BusContention);
+        }
+        Ok(simulator.spi())
+    }
+}
+
+impl<const N: usize> Write<u8> for SimulatedBus<'_, N> {
+    type Error = SimulatorError;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.selected()?.write(words)
+    }
+}
+
+impl<const N: usize> Transfer<u8> for SimulatedBus<'_, N> {
+    type Error = SimulatorError;
+
+    fn transfer<'w>(
+        &mut self,
+        words: &'w mut [u8],
+    ) -> Result<&'w [u8], Self::Error> {
+        self.selected()?.transfer(words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctrl_reg_4_value::Endianness;
+    use crate::ctrl_reg_5_value::CtrlReg5Value;
+    use crate::enabled_enum::OnOff;
+    use crate::int_cfg::IntCfg;
+    use crate::int_src::IntSrc;
+    use crate::int_ths_value::IntThs;
+    use crate::{Lis3dh, RegisterAddresses};
+    use hal::blocking::spi::{Transfer, Write};
+    use hal::digital::v2::OutputPin;
+    use micromath::vector::I32x3;
+
+    const ONE_G_ON_Z: I32x3 = I32x3 {
+        x: 0,
+        y: 0,
+        z: 1000,
+    };
+
+    #[test]
+    fn driver_reads_reset_values_and_verifies_written_settings() {
+        let simulator = super::Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        assert_eq!(lis3dh.get_who_am_i(&mut cs, &mut spi).unwrap(), 0x33);
+        assert!(lis3dh.verify_settings(&mut cs, &mut spi).unwrap().is_ok());
+        lis3dh.set_output_data_rate(crate::ctrl_reg_1_value::ODR::Hz400);
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+        assert!(lis3dh.verify_settings(&mut cs, &mut spi).unwrap().is_ok());
+        assert_eq!(simulator.register(RegisterAddresses::CtrlReg1), 0x77);
+    }
+
+    #[test]
+    fn angle_and_gravity_offset_are_truncated() {
+        let simulator = super::Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(crate::ctrl_reg_1_value::ODR::Hz100);
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+        simulator.inject_sample(ONE_G_ON_Z);
+        let result = lis3dh
+            .get_angle_and_gravity_offset(&mut cs, &mut spi)
+            .unwrap();
+        assert_eq!((result.angle(), result.offset()), (2, 0));
+        simulator.inject_sample(I32x3 {
+            x: 0,
+            y: 708,
+            z: 708,
+        });
+        let result = lis3dh
+            .get_angle_and_gravity_offset(&mut cs, &mut spi)
+            .unwrap();
+        assert_eq!((result.angle(), result.offset()), (46, 1));
+    }
+
+    #[test]
+    fn fixed_point_tilt_applies_the_calibration() {
+        let simulator = super::Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(crate::ctrl_reg_1_value::ODR::Hz100);
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+        lis3dh.set_calibration(Some(crate::calibration::Calibration::new(
+            [0.0, 0.0, 200.0],
+            [1.0; 3],
+        )));
+        simulator.inject_sample(I32x3 {
+            x: 0,
+            y: 0,
+            z: 1200,
+        });
+        let tilt = lis3dh.get_tilt_fixed(&mut cs, &mut spi).unwrap();
+        assert_eq!((tilt.magnitude(), tilt.is_valid()), (1000, true));
+        let result = lis3dh
+            .get_angle_and_gravity_offset_fixed(&mut cs, &mut spi)
+            .unwrap();
+        assert_eq!((result.angle(), result.offset()), (0, 0));
+    }
+
+    #[test]
+    fn addresses_auto_increment_only_when_requested() {
+        let simulator = super::Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut buffer = [0xff; 3];
+        cs.set_low().unwrap();
+        spi.write(&[RegisterAddresses::CtrlReg0 as u8 | 0xc0])
+            .unwrap();
+        spi.transfer(&mut buffer).unwrap();
+        cs.set_high().unwrap();
+        assert_eq!(buffer, [0x10, 0x00, 0x07]);
+        cs.set_low().unwrap();
+        spi.write(&[RegisterAddresses::CtrlReg0 as u8 | 0x80])
+            .unwrap();
+        spi.transfer(&mut buffer).unwrap();
+        cs.set_high().unwrap();
+        assert_eq!(buffer, [0x10, 0x10, 0x10]);
+        assert!(spi.transfer(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn latched_interrupt_source_is_cleared_on_read() {
+        let simulator = super::Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        let mut int1_cfg = IntCfg::default();
+        int1_cfg.set_zhie(OnOff::Enabled);
+        let mut int1_ths = IntThs::default();
+        int1_ths.set_threshold(40).unwrap();
+        let mut ctrl_reg5 = CtrlReg5Value::default();
+        ctrl_reg5.set_latch_int_on_int_1_src(OnOff::Enabled);
+        lis3dh.set_int1_cfg_setting(int1_cfg).unwrap();
+        lis3dh.set_int1_ths_setting(int1_ths);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+
+        simulator.inject_sample(ONE_G_ON_Z);
+        simulator.inject_sample(I32x3 { x: 0, y: 0, z: 0 });
+        let source: IntSrc = lis3dh.read(&mut cs, &mut spi).unwrap();
+        assert!(source.is_interrupt_active());
+        assert!(source.is_z_high());
+        let source: IntSrc = lis3dh.read(&mut cs, &mut spi).unwrap();
+        assert!(!source.is_interrupt_active());
+    }
+
+    #[test]
+    fn verification_keeps_latched_interrupt_and_fifo_samples() {
+        let simulator = super::Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        let mut int1_cfg = IntCfg::default();
+        int1_cfg.set_zhie(OnOff::Enabled);
+        let mut int1_ths = IntThs::default();
+        int1_ths.set_threshold(40).unwrap();
+        let mut ctrl_reg5 = CtrlReg5Value::default();
+        ctrl_reg5.set_latch_int_on_int_1_src(OnOff::Enabled);
+        ctrl_reg5.set_fifo(OnOff::Enabled);
+        lis3dh.set_int1_cfg_setting(int1_cfg).unwrap();
+        lis3dh.set_int1_ths_setting(int1_ths);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+        lis3dh
+            .set_register_raw_value(
+                RegisterAddresses::FifoCtrlReg,
+                0x80,
+                &mut cs,
+                &mut spi,
+            )
+            .unwrap();
+
+        simulator.inject_sample(ONE_G_ON_Z);
+        simulator.inject_sample(I32x3 { x: 0, y: 0, z: 0 });
+        assert!(lis3dh.verify_settings(&mut cs, &mut spi).unwrap().is_ok());
+        assert_eq!(simulator.fifo_len(), 2);
+        let source: IntSrc = lis3dh.read(&mut cs, &mut spi).unwrap();
+        assert!(source.is_interrupt_active());
+    }
+
+    #[test]
+    fn fifo_modes_store_samples_as_configured() {
+        let simulator = super::Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        let mut ctrl_reg5 = CtrlReg5Value::default();
+        ctrl_reg5.set_fifo(OnOff::Enabled);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+        let fifo_ctrl = RegisterAddresses::FifoCtrlReg;
+
+        let inject = |count: i32| {
+            for z in 0..count {
+                simulator.inject_sample(I32x3 {
+                    x: 0,
+                    y: 0,
+                    z: 16 * z,
+                });
+            }
+        };
+        lis3dh
+            .set_register_raw_value(fifo_ctrl, 0x40, &mut cs, &mut spi)
+            .unwrap();
+        inject(40);
+        let mut samples = [I32x3 { x: 0, y: 0, z: 0 }; 40];
+        let read = lis3dh
+            .get_accel_norm_from_fifo(&mut cs, &mut spi, &mut samples)
+            .unwrap();
+        assert_eq!((read, samples[31].z), (32, 16 * 31));
+
+        lis3dh
+            .set_register_raw_value(fifo_ctrl, 0x80, &mut cs, &mut spi)
+            .unwrap();
+        inject(40);
+        let read = lis3dh
+            .get_accel_norm_from_fifo(&mut cs, &mut spi, &mut samples)
+            .unwrap();
+        assert_eq!((read, samples[0].z, samples[31].z), (32, 16 * 8, 16 * 39));
+
+        lis3dh
+            .set_register_raw_value(fifo_ctrl, 0xc0, &mut cs, &mut spi)
+            .unwrap();
+        let mut int1_cfg = IntCfg::default();
+        int1_cfg.set_zhie(OnOff::Enabled);
+        let mut int1_ths = IntThs::default();
+        int1_ths.set_threshold(40).unwrap();
+        lis3dh.set_int1_cfg_setting(int1_cfg).unwrap();
+        lis3dh.set_int1_ths_setting(int1_ths);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        inject(60);
+        let read = lis3dh
+            .get_accel_norm_from_fifo(&mut cs, &mut spi, &mut samples)
+            .unwrap();
+        assert_eq!((read, samples[0].z, samples[31].z), (32, 16 * 10, 16 * 41));
+
+        lis3dh
+            .set_register_raw_value(fifo_ctrl, 0x40, &mut cs, &mut spi)
+            .unwrap();
+        inject(2);
+        lis3dh
+            .set_register_raw_value(fifo_ctrl, 0x00, &mut cs, &mut spi)
+            .unwrap();
+        assert_eq!(simulator.fifo_len(), 0);
+    }
+
+    #[test]
+    fn injected_samples_are_decoded_in_both_byte_orders() {
+        for endianness in
+            [Endianness::LSBLowerAddress, Endianness::MSBLowerAddress]
+        {
+            let simulator = super::Simulator::new();
+            let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+            let mut lis3dh = Lis3dh::default();
+            let mut ctrl_reg4 = lis3dh.ctrl_reg4_setting();
+            ctrl_reg4.set_ble(endianness);
+            lis3dh.set_ctrl_reg4_setting(ctrl_reg4);
+            lis3dh.write_all_settings(&mut cs, &mut spi).unwrap();
+
+            simulator.inject_sample(I32x3 {
+                x: -500,
+                y: 248,
+                z: 1000,
+            });
+            let sample = lis3dh.get_accel_norm(&mut cs, &mut spi).unwrap();
+            assert_eq!((sample.x, sample.y, sample.z), (-500, 248, 1000));
+            simulator.set_adc_values([0x1234, 0, 0]);
+            assert_eq!(
+                lis3dh.get_adc1_value(&mut cs, &mut spi).unwrap(),
+                0x1234
+            );
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+
 const NEW_DATA_ON_1_AXIS_BIT_OFFSET: u8 = 0;
 const NEW_DATA_ON_2_AXIS_BIT_OFFSET: u8 = 1;
 const NEW_DATA_ON_3_AXIS_BIT_OFFSET: u8 = 2;
@@ -7,7 +11,7 @@ const OVERRUN_ON_2_AXIS_BIT_OFFSET: u8 = 5;
 const OVERRUN_ON_3_AXIS_BIT_OFFSET: u8 = 6;
 const OVERRUN_ON_3_2_1_AXIS_BIT_OFFSET: u8 = 7;
 
-#[derive(Default)]
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
 pub struct StatusRegAuxValue {
     new_data_on1axis: bool,
     new_data_on2axis: bool,
@@ -48,6 +52,17 @@ impl StatusRegAuxValue {
     pub fn has_overrun_on3_2_1axis(&self) -> bool {
         self.overrun_on3_2_1axis
     }
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.overrun_on3_2_1axis as u8) << OVERRUN_ON_3_2_1_AXIS_BIT_OFFSET
+            | (self.overrun_on3axis as u8) << OVERRUN_ON_3_AXIS_BIT_OFFSET
+            | (self.overrun_on2axis as u8) << OVERRUN_ON_2_AXIS_BIT_OFFSET
+            | (self.overrun_on1axis as u8) << OVERRUN_ON_1_AXIS_BIT_OFFSET
+            | (self.new_data_on3_2_1axis as u8)
+                << NEW_DATA_ON_3_2_1_AXIS_BIT_OFFSET
+            | (self.new_data_on3axis as u8) << NEW_DATA_ON_3_AXIS_BIT_OFFSET
+            | (self.new_data_on2axis as u8) << NEW_DATA_ON_2_AXIS_BIT_OFFSET
+            | (self.new_data_on1axis as u8) << NEW_DATA_ON_1_AXIS_BIT_OFFSET
+    }
     pub(super) fn from_raw_value(value: u8) -> Self {
         StatusRegAuxValue {
             new_data_on1axis: super::check_if_bit_is_set(
@@ -87,6 +102,34 @@ impl StatusRegAuxValue {
     }
 }
 
+impl TryFrom<u8> for StatusRegAuxValue {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(StatusRegAuxValue::from_raw_value(value))
+    }
+}
+
+impl Register for StatusRegAuxValue {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::StatusRegAux;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadOnly;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("1da", 1 << NEW_DATA_ON_1_AXIS_BIT_OFFSET),
+        RegisterField::new("2da", 1 << NEW_DATA_ON_2_AXIS_BIT_OFFSET),
+        RegisterField::new("3da", 1 << NEW_DATA_ON_3_AXIS_BIT_OFFSET),
+        RegisterField::new("321da", 1 << NEW_DATA_ON_3_2_1_AXIS_BIT_OFFSET),
+        RegisterField::new("1or", 1 << OVERRUN_ON_1_AXIS_BIT_OFFSET),
+        RegisterField::new("2or", 1 << OVERRUN_ON_2_AXIS_BIT_OFFSET),
+        RegisterField::new("3or", 1 << OVERRUN_ON_3_AXIS_BIT_OFFSET),
+        RegisterField::new("321or", 1 << OVERRUN_ON_3_2_1_AXIS_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
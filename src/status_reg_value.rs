@@ -0,0 +1,115 @@
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
+
+const X_DA_BIT_OFFSET: u8 = 0;
+const Y_DA_BIT_OFFSET: u8 = 1;
+const Z_DA_BIT_OFFSET: u8 = 2;
+const ZYX_DA_BIT_OFFSET: u8 = 3;
+const X_OR_BIT_OFFSET: u8 = 4;
+const Y_OR_BIT_OFFSET: u8 = 5;
+const Z_OR_BIT_OFFSET: u8 = 6;
+const ZYX_OR_BIT_OFFSET: u8 = 7;
+
+/// Content of STATUS_REG. The flags are cleared by reading the output
+/// registers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatusRegValue {
+    x_new_data: bool,
+    y_new_data: bool,
+    z_new_data: bool,
+    zyx_new_data: bool,
+    x_overrun: bool,
+    y_overrun: bool,
+    z_overrun: bool,
+    zyx_overrun: bool,
+}
+
+impl StatusRegValue {
+    pub fn has_x_new_data(&self) -> bool {
+        self.x_new_data
+    }
+    pub fn has_y_new_data(&self) -> bool {
+        self.y_new_data
+    }
+    pub fn has_z_new_data(&self) -> bool {
+        self.z_new_data
+    }
+    pub fn has_zyx_new_data(&self) -> bool {
+        self.zyx_new_data
+    }
+    pub fn has_x_overrun(&self) -> bool {
+        self.x_overrun
+    }
+    pub fn has_y_overrun(&self) -> bool {
+        self.y_overrun
+    }
+    pub fn has_z_overrun(&self) -> bool {
+        self.z_overrun
+    }
+    /// True if a new sample overwrote one that was not read.
+    pub fn has_zyx_overrun(&self) -> bool {
+        self.zyx_overrun
+    }
+    pub(super) fn get_raw_value(&self) -> u8 {
+        (self.zyx_overrun as u8) << ZYX_OR_BIT_OFFSET
+            | (self.z_overrun as u8) << Z_OR_BIT_OFFSET
+            | (self.y_overrun as u8) << Y_OR_BIT_OFFSET
+            | (self.x_overrun as u8) << X_OR_BIT_OFFSET
+            | (self.zyx_new_data as u8) << ZYX_DA_BIT_OFFSET
+            | (self.z_new_data as u8) << Z_DA_BIT_OFFSET
+            | (self.y_new_data as u8) << Y_DA_BIT_OFFSET
+            | (self.x_new_data as u8) << X_DA_BIT_OFFSET
+    }
+}
+
+impl TryFrom<u8> for StatusRegValue {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(StatusRegValue {
+            x_new_data: super::check_if_bit_is_set(value, X_DA_BIT_OFFSET),
+            y_new_data: super::check_if_bit_is_set(value, Y_DA_BIT_OFFSET),
+            z_new_data: super::check_if_bit_is_set(value, Z_DA_BIT_OFFSET),
+            zyx_new_data: super::check_if_bit_is_set(value, ZYX_DA_BIT_OFFSET),
+            x_overrun: super::check_if_bit_is_set(value, X_OR_BIT_OFFSET),
+            y_overrun: super::check_if_bit_is_set(value, Y_OR_BIT_OFFSET),
+            z_overrun: super::check_if_bit_is_set(value, Z_OR_BIT_OFFSET),
+            zyx_overrun: super::check_if_bit_is_set(value, ZYX_OR_BIT_OFFSET),
+        })
+    }
+}
+
+impl Register for StatusRegValue {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::StatusReg;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadOnly;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("xda", 1 << X_DA_BIT_OFFSET),
+        RegisterField::new("yda", 1 << Y_DA_BIT_OFFSET),
+        RegisterField::new("zda", 1 << Z_DA_BIT_OFFSET),
+        RegisterField::new("zyxda", 1 << ZYX_DA_BIT_OFFSET),
+        RegisterField::new("xor", 1 << X_OR_BIT_OFFSET),
+        RegisterField::new("yor", 1 << Y_OR_BIT_OFFSET),
+        RegisterField::new("zor", 1 << Z_OR_BIT_OFFSET),
+        RegisterField::new("zyxor", 1 << ZYX_OR_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn conversion_from_raw_value_works() {
+        let status = super::StatusRegValue::try_from(0b1000_1100).unwrap();
+        assert!(status.has_zyx_new_data());
+        assert!(status.has_z_new_data());
+        assert!(!status.has_x_new_data());
+        assert!(status.has_zyx_overrun());
+        assert!(!status.has_z_overrun());
+        assert_eq!(status.get_raw_value(), 0b1000_1100);
+    }
+}
@@ -1,4 +1,7 @@
 use crate::enabled_enum::OnOff;
+use crate::register::{AccessMode, Register};
+use crate::register_field::RegisterField;
+use crate::RegisterAddresses;
 
 const TEMP_EN_BIT_OFFSET: u8 = 6;
 const ADC_EN_BIT_OFFSET: u8 = 7;
@@ -22,7 +25,25 @@ impl TempCfgRegValue {
     pub fn adc(&self) -> OnOff {
         self.adc
     }
-    pub(super) fn from_raw_value(value: u8) -> Self {
+    pub(super) fn get_raw_value(&self) -> u8 {
+        let mut result = 0_u8;
+        if self.adc == OnOff::Enabled {
+            result += 1 << ADC_EN_BIT_OFFSET;
+        }
+        if self.temp == OnOff::Enabled {
+            result += 1 << TEMP_EN_BIT_OFFSET;
+        }
+        result
+    }
+}
+
+impl TryFrom<u8> for TempCfgRegValue {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & 0b0011_1111 != 0 {
+            return Err("TEMP_CFG_REG bit 0 to 5 have to be 0");
+        }
         let temp = if value >> TEMP_EN_BIT_OFFSET & 1 == 1 {
             OnOff::Enabled
         } else {
@@ -33,16 +54,42 @@ impl TempCfgRegValue {
         } else {
             OnOff::Disabled
         };
-        TempCfgRegValue { temp, adc }
+        Ok(TempCfgRegValue { temp, adc })
     }
-    pub(super) fn get_raw_value(&self) -> u8 {
-        let mut result = 0_u8;
-        if self.adc == OnOff::Enabled {
-            result += 1 << ADC_EN_BIT_OFFSET;
-        }
-        if self.temp == OnOff::Enabled {
-            result += TEMP_EN_BIT_OFFSET << 1;
-        }
-        result
+}
+
+impl Register for TempCfgRegValue {
+    const ADDRESS: RegisterAddresses = RegisterAddresses::TempCfgReg;
+    const ACCESS_MODE: AccessMode = AccessMode::ReadWrite;
+    const RESET_VALUE: u8 = 0;
+    const FIELDS: &'static [RegisterField] = &[
+        RegisterField::new("temp", 1 << TEMP_EN_BIT_OFFSET),
+        RegisterField::new("adc", 1 << ADC_EN_BIT_OFFSET),
+    ];
+
+    fn raw_value(&self) -> u8 {
+        self.get_raw_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn conversion_to_raw_value_and_back_works() {
+        let mut temp_cfg_reg = super::TempCfgRegValue::default();
+        temp_cfg_reg.set_temp(super::OnOff::Enabled);
+        assert_eq!(temp_cfg_reg.get_raw_value(), 0b0100_0000);
+        temp_cfg_reg.set_adc(super::OnOff::Enabled);
+        assert_eq!(temp_cfg_reg.get_raw_value(), 0b1100_0000);
+        assert!(
+            super::TempCfgRegValue::try_from(0b1100_0000).unwrap()
+                == temp_cfg_reg
+        );
+    }
+
+    #[test]
+    fn conversion_from_raw_value_fails_if_lower_bits_are_set() {
+        assert!(super::TempCfgRegValue::try_from(0b1100_0001).is_err());
+        assert!(super::TempCfgRegValue::try_from(0b0010_0000).is_err());
     }
 }
@@ -0,0 +1,147 @@
+use hal::blocking::spi::{Transfer, Write};
+
+/// Bus whose data line is shared by SDI and SDO, as used by the LIS3DH in
+/// 3-wire SPI mode. The host drives the line while sending and releases it
+/// while the sensor answers, e.g. a half-duplex (bidirectional) SPI
+/// peripheral or a bit-banged bidirectional pin.
+pub trait HalfDuplexBus {
+    type Error;
+
+    fn send(&mut self, words: &[u8]) -> Result<(), Self::Error>;
+    fn receive(&mut self, words: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// Adapts a [`HalfDuplexBus`] to the SPI traits used by [`crate::Lis3dh`].
+///
+/// The driver sends every command with [`Write`] before reading the answer
+/// with [`Transfer`], so `transfer` only receives and overwrites `words`
+/// with the bytes clocked in from the sensor.
+pub struct ThreeWireSpi<B> {
+    bus: B,
+}
+
+impl<B: HalfDuplexBus> ThreeWireSpi<B> {
+    pub fn new(bus: B) -> Self {
+        ThreeWireSpi { bus }
+    }
+    pub fn bus_mut(&mut self) -> &mut B {
+        &mut self.bus
+    }
+    pub fn release(self) -> B {
+        self.bus
+    }
+}
+
+impl<B: HalfDuplexBus> Write<u8> for ThreeWireSpi<B> {
+    type Error = B::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.bus.send(words)
+    }
+}
+
+impl<B: HalfDuplexBus> Transfer<u8> for ThreeWireSpi<B> {
+    type Error = B::Error;
+
+    fn transfer<'w>(
+        &mut self,
+        words: &'w mut [u8],
+    ) -> Result<&'w [u8], Self::Error> {
+        self.bus.receive(words)?;
+        Ok(words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ctrl_reg_4_value::SpiModeSelection;
+    use crate::{RegisterAddresses, SPI_AUTO_INCREMENT_BIT, SPI_READ_BIT};
+    use core::convert::Infallible;
+
+    struct Pin;
+
+    impl hal::digital::v2::OutputPin for Pin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    /// Answers reads from `registers` and applies writes to them, like a
+    /// sensor sharing its data line.
+    struct SharedLine {
+        registers: [u8; 0x40],
+        address: usize,
+        auto_increment: bool,
+    }
+
+    impl SharedLine {
+        fn new() -> Self {
+            let mut registers = [0; 0x40];
+            registers[RegisterAddresses::WhoAmI as usize] = 0x33;
+            registers[RegisterAddresses::CtrlReg0 as usize] = 0x10;
+            registers[RegisterAddresses::CtrlReg1 as usize] = 0x07;
+            SharedLine {
+                registers,
+                address: 0,
+                auto_increment: false,
+            }
+        }
+    }
+
+    impl super::HalfDuplexBus for SharedLine {
+        type Error = Infallible;
+
+        fn send(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            self.address = (words[0] & 0x3f) as usize;
+            self.auto_increment = words[0] & SPI_AUTO_INCREMENT_BIT != 0;
+            if words[0] & SPI_READ_BIT == 0 {
+                for (offset, word) in words[1..].iter().enumerate() {
+                    self.registers[self.address + offset] = *word;
+                }
+            }
+            Ok(())
+        }
+        fn receive(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            for word in words.iter_mut() {
+                *word = self.registers[self.address];
+                if self.auto_increment {
+                    self.address += 1;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn registers_are_read_over_the_shared_line() {
+        let mut lis3dh = crate::Lis3dh::default();
+        let mut spi = super::ThreeWireSpi::new(SharedLine::new());
+        assert_eq!(lis3dh.get_who_am_i(&mut Pin, &mut spi).unwrap(), 0x33);
+        let snapshot =
+            lis3dh.get_register_snapshot(&mut Pin, &mut spi).unwrap();
+        assert_eq!(snapshot.who_am_i(), 0x33);
+        assert_eq!(snapshot.raw_value(RegisterAddresses::CtrlReg1), 0x07);
+    }
+
+    #[test]
+    fn switching_to_three_wire_mode_sets_and_verifies_sim() {
+        let mut lis3dh = crate::Lis3dh::default();
+        let mut spi = super::ThreeWireSpi::new(SharedLine::new());
+        lis3dh
+            .switch_to_three_wire_mode(&mut Pin, &mut spi)
+            .unwrap();
+        assert_eq!(
+            lis3dh.ctrl_reg4_setting().sim(),
+            SpiModeSelection::ThreeWireInterface
+        );
+        assert_eq!(
+            spi.release().registers[RegisterAddresses::CtrlReg4 as usize],
+            0x01
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::math::{atan2, sqrt};
+use micromath::vector::I32x3;
+use micromath::F32Ext;
+
+/// Acceleration at rest in mg.
+const GRAVITY: f32 = 1000.0;
+/// Default deviation of |a| from 1 g up to which a tilt is considered valid.
+pub const DEFAULT_TOLERANCE_MG: f32 = 100.0;
+
+type Matrix = [[f32; 3]; 3];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tilt {
+    roll: f32,
+    pitch: f32,
+    inclination: f32,
+    magnitude: f32,
+    valid: bool,
+}
+
+impl Tilt {
+    /// Rotation around the X axis in degrees, -180 to 180.
+    pub fn roll(&self) -> f32 {
+        self.roll
+    }
+    /// Rotation around the Y axis in degrees, -90 to 90.
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+    /// Angle between the Z axis and the vertical in degrees, 0 to 180.
+    pub fn inclination(&self) -> f32 {
+        self.inclination
+    }
+    /// Magnitude of the measured acceleration in mg.
+    pub fn magnitude(&self) -> f32 {
+        self.magnitude
+    }
+    /// False if the magnitude deviates from 1 g by more than the tolerance,
+    /// i.e. the sensor is accelerated and the angles do not describe its
+    /// orientation.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+}
+
+/// Calculates the tilt from acceleration samples in mg, optionally relative
+/// to an orientation captured with [`TiltCalculator::tare`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TiltCalculator {
+    tare: Matrix,
+    tolerance_mg: f32,
+}
+
+impl Default for TiltCalculator {
+    fn default() -> Self {
+        TiltCalculator::new(DEFAULT_TOLERANCE_MG)
+    }
+}
+
+impl TiltCalculator {
+    pub fn new(tolerance_mg: f32) -> Self {
+        TiltCalculator {
+            tare: IDENTITY,
+            tolerance_mg,
+        }
+    }
+    pub fn tolerance_mg(&self) -> f32 {
+        self.tolerance_mg
+    }
+    pub fn tilt(&self, sample: I32x3) -> Tilt {
+        let [x, y, z] = multiply(
+            &self.tare,
+            [sample.x as f32, sample.y as f32, sample.z as f32],
+        );
+        let horizontal = sqrt(x * x + y * y);
+        let magnitude = sqrt(x * x + y * y + z * z);
+        Tilt {
+            roll: atan2(y, z).to_degrees(),
+            pitch: atan2(-x, sqrt(y * y + z * z)).to_degrees(),
+            inclination: atan2(horizontal, z).to_degrees(),
+            magnitude,
+            valid: F32Ext::abs(magnitude - GRAVITY) <= self.tolerance_mg,
+        }
+    }
+    /// Captures the orientation of the sample as zero. Refused if the
+    /// sample does not describe an orientation, see [`Tilt::is_valid`].
+    pub fn tare(&mut self, sample: I32x3) -> Result<(), &'static str> {
+        let untared = TiltCalculator::new(self.tolerance_mg).tilt(sample);
+        if !untared.is_valid() {
+            return Err("Sample deviates too far from 1 g to tare");
+        }
+        let magnitude = untared.magnitude();
+        let down = [
+            sample.x as f32 / magnitude,
+            sample.y as f32 / magnitude,
+            sample.z as f32 / magnitude,
+        ];
+        self.tare = rotation_to_z(down);
+        Ok(())
+    }
+    pub fn clear_tare(&mut self) {
+        self.tare = IDENTITY;
+    }
+}
+
+fn multiply(matrix: &Matrix, vector: [f32; 3]) -> [f32; 3] {
+    matrix
+        .map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+/// Rotation turning the unit vector `from` onto the Z axis (Rodrigues'
+/// formula).
+fn rotation_to_z(from: [f32; 3]) -> Matrix {
+    let cos = from[2];
+    if cos < -1.0 + 1e-6 {
+        // Upside down, turn by 180° around the X axis.
+        return [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, -1.0]];
+    }
+    // Rotation axis `from` × Z, not normalised.
+    let (ax, ay) = (from[1], -from[0]);
+    let k = 1.0 / (1.0 + cos);
+    [
+        [1.0 - k * ay * ay, k * ax * ay, ay],
+        [k * ax * ay, 1.0 - k * ax * ax, -ax],
+        [-ay, ax, 1.0 - k * (ax * ax + ay * ay)],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use micromath::vector::I32x3;
+    use micromath::F32Ext;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            F32Ext::abs(actual - expected) < 0.2,
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn roll_pitch_and_inclination_cover_all_quadrants() {
+        let calculator = super::TiltCalculator::default();
+        let tilt = calculator.tilt(I32x3 {
+            x: 0,
+            y: 707,
+            z: 707,
+        });
+        assert_close(tilt.roll(), 45.0);
+        assert_close(tilt.pitch(), 0.0);
+        assert_close(tilt.inclination(), 45.0);
+        let tilt = calculator.tilt(I32x3 {
+            x: 0,
+            y: 707,
+            z: -707,
+        });
+        assert_close(tilt.roll(), 135.0);
+        assert_close(tilt.inclination(), 135.0);
+        let tilt = calculator.tilt(I32x3 {
+            x: 0,
+            y: -707,
+            z: -707,
+        });
+        assert_close(tilt.roll(), -135.0);
+        let tilt = calculator.tilt(I32x3 {
+            x: 500,
+            y: 0,
+            z: 866,
+        });
+        assert_close(tilt.pitch(), -30.0);
+        assert_close(tilt.inclination(), 30.0);
+        assert!(tilt.is_valid());
+    }
+
+    #[test]
+    fn accelerated_samples_are_marked_invalid() {
+        let calculator = super::TiltCalculator::default();
+        assert!(!calculator
+            .tilt(I32x3 {
+                x: 0,
+                y: 0,
+                z: 1500
+            })
+            .is_valid());
+        assert!(!calculator.tilt(I32x3 { x: 0, y: 0, z: 0 }).is_valid());
+        assert!(calculator.tilt(I32x3 { x: 0, y: 0, z: 950 }).is_valid());
+    }
+
+    #[test]
+    fn tare_makes_the_current_orientation_zero() {
+        let mut calculator = super::TiltCalculator::default();
+        let mounted = I32x3 {
+            x: 174,
+            y: 0,
+            z: 985,
+        };
+        calculator.tare(mounted).unwrap();
+        let tilt = calculator.tilt(mounted);
+        assert_close(tilt.roll(), 0.0);
+        assert_close(tilt.pitch(), 0.0);
+        assert_close(tilt.inclination(), 0.0);
+        let tilt = calculator.tilt(I32x3 {
+            x: 0,
+            y: 0,
+            z: 1000,
+        });
+        assert_close(tilt.inclination(), 10.0);
+        calculator
+            .tare(I32x3 {
+                x: 0,
+                y: 0,
+                z: -1000,
+            })
+            .unwrap();
+        assert_close(
+            calculator
+                .tilt(I32x3 {
+                    x: 0,
+                    y: 0,
+                    z: -1000,
+                })
+                .inclination(),
+            0.0,
+        );
+        assert!(calculator
+            .tare(I32x3 {
+                x: 0,
+                y: 0,
+                z: 2000
+            })
+            .is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use micromath::vector::I32x3;
+
+/// Monotonic clock counting microseconds, e.g. backed by a hardware
+/// timer. Closures returning the time implement it as well.
+pub trait Clock {
+    fn now_us(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    fn now_us(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// Acceleration in mg with the time it was sampled.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimestampedSample {
+    sample: I32x3,
+    timestamp_us: u64,
+}
+
+impl TimestampedSample {
+    pub fn new(sample: I32x3, timestamp_us: u64) -> Self {
+        TimestampedSample {
+            sample,
+            timestamp_us,
+        }
+    }
+    pub fn sample(&self) -> I32x3 {
+        self.sample
+    }
+    pub fn timestamp_us(&self) -> u64 {
+        self.timestamp_us
+    }
+}
+
+/// Time of the sample `index` in the FIFO when the sample `anchor_index`
+/// was taken at `anchor_us`. Each offset is calculated from the sample
+/// rate directly, so rounding errors do not add up.
+pub(crate) fn fifo_sample_time_us(
+    anchor_us: u64,
+    anchor_index: usize,
+    index: usize,
+    sample_rate_millihertz: u32,
+) -> u64 {
+    let offset = |samples: usize| {
+        (samples as u64 * 1_000_000_000 + sample_rate_millihertz as u64 / 2)
+            / sample_rate_millihertz as u64
+    };
+    if index >= anchor_index {
+        anchor_us + offset(index - anchor_index)
+    } else {
+        anchor_us.saturating_sub(offset(anchor_index - index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimestampedSample;
+    use crate::ctrl_reg_1_value::ODR;
+    use crate::ctrl_reg_5_value::CtrlReg5Value;
+    use crate::enabled_enum::OnOff;
+    use crate::fifo_ctrl_reg_value::{FifoCtrlRegValue, FifoMode};
+    use crate::simulator::Simulator;
+    use crate::{Error, Lis3dh};
+    use micromath::vector::I32x3;
+
+    #[test]
+    fn polled_samples_carry_the_clock_time() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(ODR::Hz100);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut now = 5_000;
+        let mut clock = || {
+            now += 10_000;
+            now
+        };
+        simulator.inject_sample(I32x3 {
+            x: 0,
+            y: 0,
+            z: 1008,
+        });
+        let first = lis3dh
+            .get_accel_norm_timestamped(&mut cs, &mut spi, &mut clock)
+            .unwrap();
+        let second = lis3dh
+            .get_accel_norm_timestamped(&mut cs, &mut spi, &mut clock)
+            .unwrap();
+        assert_eq!(first.sample().z, 1008);
+        assert_eq!(first.timestamp_us(), 15_000);
+        assert_eq!(second.timestamp_us(), 25_000);
+    }
+
+    #[test]
+    fn fifo_sample_times_are_calculated_from_the_watermark() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh
+            .set_output_data_rate(ODR::HrNormal1Pont344kHzLowPower5Point376kHz);
+        let mut ctrl_reg5 = CtrlReg5Value::default();
+        ctrl_reg5.set_fifo(OnOff::Enabled);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut fifo_ctrl = FifoCtrlRegValue::default();
+        fifo_ctrl.set_fifo_mode(FifoMode::Stream);
+        fifo_ctrl.set_threshold(19).unwrap();
+        lis3dh.write(&mut cs, &mut spi, fifo_ctrl).unwrap();
+        for z in 0..20 {
+            simulator.inject_sample(I32x3 {
+                x: 0,
+                y: 0,
+                z: 16 * z,
+            });
+        }
+        assert!(lis3dh
+            .get_fifo_src_value(&mut cs, &mut spi)
+            .unwrap()
+            .is_watermark_reached());
+        let interrupt_time_us = 1_000_000;
+        for z in 20..25 {
+            simulator.inject_sample(I32x3 {
+                x: 0,
+                y: 0,
+                z: 16 * z,
+            });
+        }
+        let mut samples = [TimestampedSample::default(); 32];
+        let read = lis3dh
+            .get_accel_norm_from_fifo_timestamped(
+                &mut cs,
+                &mut spi,
+                interrupt_time_us,
+                None,
+                &mut samples,
+            )
+            .unwrap();
+        assert_eq!(read, 25);
+        assert_eq!(samples[19].timestamp_us(), 1_000_000);
+        assert_eq!(samples[19].sample().z, 16 * 19);
+        // 1344 Hz, 19 samples earlier and 5 samples later.
+        assert_eq!(samples[0].timestamp_us(), 1_000_000 - 14_137);
+        assert_eq!(samples[24].timestamp_us(), 1_000_000 + 3_720);
+    }
+
+    #[test]
+    fn fifo_is_not_read_partially() {
+        let simulator = Simulator::new();
+        let (mut cs, mut spi) = (simulator.chip_select(), simulator.spi());
+        let mut lis3dh = Lis3dh::default();
+        lis3dh.set_output_data_rate(ODR::Hz100);
+        let mut ctrl_reg5 = CtrlReg5Value::default();
+        ctrl_reg5.set_fifo(OnOff::Enabled);
+        lis3dh.set_ctrl_reg5_setting(ctrl_reg5);
+        lis3dh.apply_changes(&mut cs, &mut spi).unwrap();
+        let mut fifo_ctrl = FifoCtrlRegValue::default();
+        fifo_ctrl.set_fifo_mode(FifoMode::Stream);
+        fifo_ctrl.set_threshold(4).unwrap();
+        lis3dh.write(&mut cs, &mut spi, fifo_ctrl).unwrap();
+        for z in 0..6 {
+            simulator.inject_sample(I32x3 {
+                x: 0,
+                y: 0,
+                z: 16 * z,
+            });
+        }
+        let mut samples = [TimestampedSample::default(); 6];
+        assert!(matches!(
+            lis3dh.get_accel_norm_from_fifo_timestamped(
+                &mut cs,
+                &mut spi,
+                50_000,
+                None,
+                &mut samples[..5],
+            ),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            lis3dh.get_accel_norm_from_fifo_timestamped(
+                &mut cs,
+                &mut spi,
+                50_000,
+                Some(0),
+                &mut samples,
+            ),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert_eq!(simulator.fifo_len(), 6);
+        // Measured 98.5 Hz instead of the nominal 100 Hz.
+        let read = lis3dh
+            .get_accel_norm_from_fifo_timestamped(
+                &mut cs,
+                &mut spi,
+                50_000,
+                Some(98_500),
+                &mut samples,
+            )
+            .unwrap();
+        assert_eq!(read, 6);
+        assert_eq!(samples[0].timestamp_us(), 50_000 - 40_609);
+        assert_eq!(samples[5].timestamp_us(), 50_000 + 10_152);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::register::fields_of;
+use crate::register_snapshot::RegisterSnapshot;
+use crate::{Lis3dh, RegisterAddresses, MANAGED_REGISTERS};
+
+/// Name reported for differing bits that do not belong to any field.
+pub const RESERVED_FIELD_NAME: &str = "reserved";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldMismatch {
+    field: &'static str,
+    expected: u8,
+    actual: u8,
+}
+
+impl FieldMismatch {
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+    pub fn expected(&self) -> u8 {
+        self.expected
+    }
+    pub fn actual(&self) -> u8 {
+        self.actual
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterVerification {
+    register: RegisterAddresses,
+    expected: u8,
+    actual: u8,
+}
+
+impl RegisterVerification {
+    pub fn new(register: RegisterAddresses, expected: u8, actual: u8) -> Self {
+        RegisterVerification {
+            register,
+            expected,
+            actual,
+        }
+    }
+    pub fn register(&self) -> RegisterAddresses {
+        self.register
+    }
+    pub fn expected(&self) -> u8 {
+        self.expected
+    }
+    pub fn actual(&self) -> u8 {
+        self.actual
+    }
+    pub fn is_matching(&self) -> bool {
+        self.expected == self.actual
+    }
+    /// Lists the differing fields of the register. Differing bits outside
+    /// of the known fields are reported unshifted as [`RESERVED_FIELD_NAME`].
+    pub fn differing_fields(&self) -> impl Iterator<Item = FieldMismatch> {
+        let fields = fields_of(self.register);
+        let differing_bits = self.expected ^ self.actual;
+        let reserved_mask =
+            !fields.iter().fold(0_u8, |mask, field| mask | field.mask());
+        let expected = self.expected;
+        let actual = self.actual;
+        let reserved =
+            (differing_bits & reserved_mask != 0).then_some(FieldMismatch {
+                field: RESERVED_FIELD_NAME,
+                expected: expected & reserved_mask,
+                actual: actual & reserved_mask,
+            });
+        fields
+            .iter()
+            .filter(move |field| differing_bits & field.mask() != 0)
+            .map(move |field| FieldMismatch {
+                field: field.name(),
+                expected: field.value_of(expected),
+                actual: field.value_of(actual),
+            })
+            .chain(reserved)
+    }
+}
+
+/// Result of comparing every register managed by [`Lis3dh`] with the values
+/// read back from the device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerificationReport {
+    registers: [RegisterVerification; MANAGED_REGISTERS.len()],
+}
+
+impl VerificationReport {
+    pub fn new(settings: &Lis3dh, snapshot: &RegisterSnapshot) -> Self {
+        Self::from_values(
+            settings,
+            MANAGED_REGISTERS.map(|register| snapshot.raw_value(register)),
+        )
+    }
+    /// Creates the report from the values read back, in the order of
+    /// [`MANAGED_REGISTERS`].
+    pub fn from_values(
+        settings: &Lis3dh,
+        values: [u8; MANAGED_REGISTERS.len()],
+    ) -> Self {
+        let registers = core::array::from_fn(|index| {
+            let register = MANAGED_REGISTERS[index];
+            RegisterVerification::new(
+                register,
+                settings.setting_raw_value(register).unwrap_or_default(),
+                values[index],
+            )
+        });
+        VerificationReport { registers }
+    }
+    pub fn is_ok(&self) -> bool {
+        self.registers.iter().all(RegisterVerification::is_matching)
+    }
+    pub fn registers(&self) -> &[RegisterVerification] {
+        &self.registers
+    }
+    pub fn mismatches(&self) -> impl Iterator<Item = &RegisterVerification> {
+        self.registers
+            .iter()
+            .filter(|register| !register.is_matching())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RegisterAddresses;
+
+    #[test]
+    fn differing_fields_are_reported() {
+        let verification = super::RegisterVerification::new(
+            RegisterAddresses::CtrlReg1,
+            0b0111_0111,
+            0b0101_0110,
+        );
+        assert!(!verification.is_matching());
+        let mut fields = verification.differing_fields();
+        let x_en = fields.next().unwrap();
+        assert_eq!(x_en.field(), "x_en");
+        assert_eq!((x_en.expected(), x_en.actual()), (1, 0));
+        let odr = fields.next().unwrap();
+        assert_eq!(odr.field(), "output_data_rate");
+        assert_eq!((odr.expected(), odr.actual()), (0b0111, 0b0101));
+        assert!(fields.next().is_none());
+    }
+
+    #[test]
+    fn differing_reserved_bits_are_reported() {
+        let verification = super::RegisterVerification::new(
+            RegisterAddresses::CtrlReg0,
+            0b1001_0000,
+            0b1001_0001,
+        );
+        let mut fields = verification.differing_fields();
+        let reserved = fields.next().unwrap();
+        assert_eq!(reserved.field(), super::RESERVED_FIELD_NAME);
+        assert_eq!((reserved.expected(), reserved.actual()), (0x10, 0x11));
+        assert!(fields.next().is_none());
+    }
+
+    #[test]
+    fn report_lists_every_managed_register() {
+        let settings = crate::Lis3dh::default();
+        let mut values = [0_u8; crate::register_snapshot::SNAPSHOT_LENGTH];
+        values[RegisterAddresses::CtrlReg0 as usize - 0x07] = 0b0001_0000;
+        values[RegisterAddresses::CtrlReg1 as usize - 0x07] = 0b0000_0011;
+        let snapshot = crate::register_snapshot::RegisterSnapshot::new(values);
+        let report = super::VerificationReport::new(&settings, &snapshot);
+        assert_eq!(report.registers().len(), crate::MANAGED_REGISTERS.len());
+        assert!(!report.is_ok());
+        let mut mismatches = report.mismatches();
+        let mismatch = mismatches.next().unwrap();
+        assert_eq!(mismatch.register(), RegisterAddresses::CtrlReg1);
+        assert_eq!(mismatch.differing_fields().next().unwrap().field(), "z_en");
+        assert!(mismatches.next().is_none());
+    }
+}
@@ -0,0 +1,392 @@
+use crate::ctrl_reg_1_value::CtrlReg1Value;
+use crate::gravity_filter::rounded_hz;
+use crate::math::{sin_cos, sqrt};
+use core::f32::consts::PI;
+use micromath::vector::I32x3;
+
+/// Vibration of one axis in mg, measured around the mean so gravity does
+/// not contribute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisStatistics {
+    mean: f32,
+    rms: f32,
+    peak: f32,
+    peak_to_peak: f32,
+}
+
+impl AxisStatistics {
+    fn of(values: impl Iterator<Item = i32> + Clone) -> Self {
+        let (count, sum, min, max) = values.clone().fold(
+            (0_u32, 0_i64, i32::MAX, i32::MIN),
+            |(count, sum, min, max), value| {
+                (
+                    count + 1,
+                    sum + value as i64,
+                    min.min(value),
+                    max.max(value),
+                )
+            },
+        );
+        let mean = sum as f32 / count as f32;
+        let sum_of_squares: f32 = values
+            .map(|value| {
+                let deviation = value as f32 - mean;
+                deviation * deviation
+            })
+            .sum();
+        AxisStatistics {
+            mean,
+            rms: sqrt(sum_of_squares / count as f32),
+            peak: (max as f32 - mean).max(mean - min as f32),
+            peak_to_peak: (max - min) as f32,
+        }
+    }
+    /// Static part of the acceleration, e.g. gravity.
+    pub fn mean(&self) -> f32 {
+        self.mean
+    }
+    pub fn rms(&self) -> f32 {
+        self.rms
+    }
+    /// Largest deviation from the mean.
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+    pub fn peak_to_peak(&self) -> f32 {
+        self.peak_to_peak
+    }
+    /// Peak divided by RMS, about 1.41 for a pure sine and higher for
+    /// impulsive vibration like bearing damage. Zero without vibration.
+    pub fn crest_factor(&self) -> f32 {
+        if self.rms > 0.0 {
+            self.peak / self.rms
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Per-axis vibration statistics of a window of samples in mg, e.g. read
+/// with [`crate::Lis3dh::get_accel_norm_from_fifo`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VibrationStatistics {
+    x: AxisStatistics,
+    y: AxisStatistics,
+    z: AxisStatistics,
+}
+
+impl VibrationStatistics {
+    pub fn new(samples: &[I32x3]) -> Result<Self, &'static str> {
+        if samples.is_empty() {
+            return Err("At least one sample is needed");
+        }
+        Ok(VibrationStatistics {
+            x: AxisStatistics::of(samples.iter().map(|sample| sample.x)),
+            y: AxisStatistics::of(samples.iter().map(|sample| sample.y)),
+            z: AxisStatistics::of(samples.iter().map(|sample| sample.z)),
+        })
+    }
+    pub fn x(&self) -> AxisStatistics {
+        self.x
+    }
+    pub fn y(&self) -> AxisStatistics {
+        self.y
+    }
+    pub fn z(&self) -> AxisStatistics {
+        self.z
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+    /// No windowing, for vibration periodic within the window.
+    Rectangular,
+    /// Hann window, reducing leakage of frequencies between bins.
+    Hann,
+}
+
+/// Calculates magnitude spectra of windows of `N` samples. `N` has to be a
+/// power of two.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpectrumAnalyzer<const N: usize> {
+    weights: [f32; N],
+    sample_rate_millihertz: u32,
+}
+
+impl<const N: usize> SpectrumAnalyzer<N> {
+    pub fn new(
+        window: Window,
+        sample_rate_hz: u32,
+    ) -> Result<Self, &'static str> {
+        SpectrumAnalyzer::new_millihertz(
+            window,
+            sample_rate_hz.saturating_mul(1000),
+        )
+    }
+    /// Like [`SpectrumAnalyzer::new`] for a fractional sample rate, e.g.
+    /// [`crate::rate_estimator::RateEstimate::millihertz`].
+    pub fn new_millihertz(
+        window: Window,
+        sample_rate_millihertz: u32,
+    ) -> Result<Self, &'static str> {
+        if N < 2 || !N.is_power_of_two() {
+            return Err("Window length must be a power of two");
+        }
+        if sample_rate_millihertz == 0 {
+            return Err("Sample rate must not be zero");
+        }
+        let mut weights = [1.0; N];
+        if window == Window::Hann {
+            for (index, weight) in weights.iter_mut().enumerate() {
+                let (_, cos) = sin_cos(2.0 * PI * index as f32 / N as f32);
+                *weight = 0.5 - 0.5 * cos;
+            }
+        }
+        Ok(SpectrumAnalyzer {
+            weights,
+            sample_rate_millihertz,
+        })
+    }
+    /// Creates an analyzer for the sample rate configured in CTRL_REG1.
+    pub fn for_settings(
+        window: Window,
+        ctrl_reg1: &CtrlReg1Value,
+    ) -> Result<Self, &'static str> {
+        SpectrumAnalyzer::new(window, ctrl_reg1.sample_rate_hz()?)
+    }
+    /// Sample rate rounded to whole Hz.
+    pub fn sample_rate_hz(&self) -> u32 {
+        rounded_hz(self.sample_rate_millihertz)
+    }
+    pub fn sample_rate_millihertz(&self) -> u32 {
+        self.sample_rate_millihertz
+    }
+    /// Sets the sample rate used to label the bins.
+    pub fn set_sample_rate_hz(
+        &mut self,
+        sample_rate_hz: u32,
+    ) -> Result<(), &'static str> {
+        self.set_sample_rate_millihertz(sample_rate_hz.saturating_mul(1000))
+    }
+    /// Like [`SpectrumAnalyzer::set_sample_rate_hz`] for a fractional
+    /// sample rate, e.g. [`crate::rate_estimator::RateEstimate::millihertz`]
+    /// measured with a [`crate::rate_estimator::RateEstimator`].
+    pub fn set_sample_rate_millihertz(
+        &mut self,
+        sample_rate_millihertz: u32,
+    ) -> Result<(), &'static str> {
+        if sample_rate_millihertz == 0 {
+            return Err("Sample rate must not be zero");
+        }
+        self.sample_rate_millihertz = sample_rate_millihertz;
+        Ok(())
+    }
+    /// Spectrum of one axis, 0 for X, 1 for Y and 2 for Z.
+    pub fn analyze(
+        &self,
+        samples: &[I32x3; N],
+        axis: usize,
+    ) -> Result<Spectrum<N>, &'static str> {
+        let value = |sample: &I32x3| match axis {
+            0 => Ok(sample.x),
+            1 => Ok(sample.y),
+            2 => Ok(sample.z),
+            _ => Err("Axis must be 0, 1 or 2"),
+        };
+        let mut real = [0.0; N];
+        let mut imaginary = [0.0; N];
+        for (index, sample) in samples.iter().enumerate() {
+            real[index] = value(sample)? as f32 * self.weights[index];
+        }
+        fft(&mut real, &mut imaginary);
+        // Scale so a bin shows the amplitude of a sine at its frequency.
+        let gain: f32 = self.weights.iter().sum();
+        let mut magnitudes = [0.0; N];
+        for bin in 0..=N / 2 {
+            let scale = if bin == 0 || bin == N / 2 { 1.0 } else { 2.0 };
+            magnitudes[bin] = scale
+                * sqrt(real[bin] * real[bin] + imaginary[bin] * imaginary[bin])
+                / gain;
+        }
+        Ok(Spectrum {
+            magnitudes,
+            sample_rate_millihertz: self.sample_rate_millihertz,
+        })
+    }
+}
+
+/// Single-sided amplitude spectrum in mg with `N / 2 + 1` bins from 0 Hz
+/// to half the sample rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spectrum<const N: usize> {
+    magnitudes: [f32; N],
+    sample_rate_millihertz: u32,
+}
+
+impl<const N: usize> Spectrum<N> {
+    pub fn magnitudes(&self) -> &[f32] {
+        &self.magnitudes[..=N / 2]
+    }
+    /// Distance between neighbouring bins in Hz.
+    pub fn resolution_hz(&self) -> f32 {
+        self.sample_rate_millihertz as f32 / 1000.0 / N as f32
+    }
+    pub fn frequency_hz(&self, bin: usize) -> f32 {
+        bin as f32 * self.resolution_hz()
+    }
+    /// Bins as pairs of frequency in Hz and amplitude in mg.
+    pub fn bins(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.magnitudes()
+            .iter()
+            .enumerate()
+            .map(|(bin, magnitude)| (self.frequency_hz(bin), *magnitude))
+    }
+    /// Strongest bin above 0 Hz as frequency in Hz and amplitude in mg.
+    pub fn dominant(&self) -> (f32, f32) {
+        self.bins().skip(1).fold((0.0, 0.0), |strongest, bin| {
+            if bin.1 > strongest.1 {
+                bin
+            } else {
+                strongest
+            }
+        })
+    }
+}
+
+/// In-place iterative radix-2 FFT.
+fn fft<const N: usize>(real: &mut [f32; N], imaginary: &mut [f32; N]) {
+    let bits = N.trailing_zeros();
+    for index in 0..N {
+        let reversed = index.reverse_bits() >> (usize::BITS - bits);
+        if reversed > index {
+            real.swap(index, reversed);
+            imaginary.swap(index, reversed);
+        }
+    }
+    let mut length = 2;
+    while length <= N {
+        let half = length / 2;
+        for k in 0..half {
+            let (sin, cos) = sin_cos(-2.0 * PI * k as f32 / length as f32);
+            for start in (0..N).step_by(length) {
+                let (even, odd) = (start + k, start + k + half);
+                let odd_real = real[odd] * cos - imaginary[odd] * sin;
+                let odd_imaginary = real[odd] * sin + imaginary[odd] * cos;
+                real[odd] = real[even] - odd_real;
+                imaginary[odd] = imaginary[even] - odd_imaginary;
+                real[even] += odd_real;
+                imaginary[even] += odd_imaginary;
+            }
+        }
+        length *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpectrumAnalyzer, VibrationStatistics, Window};
+    use crate::ctrl_reg_1_value::{CtrlReg1Value, LPEn, ODR};
+    use core::f32::consts::PI;
+    use micromath::vector::I32x3;
+    use micromath::F32Ext;
+
+    /// Sensor at rest on Z, vibrating on X with two sines.
+    fn pump(sample_rate_hz: u32) -> [I32x3; 256] {
+        let mut samples = [I32x3 { x: 0, y: 0, z: 0 }; 256];
+        for (index, sample) in samples.iter_mut().enumerate() {
+            let time = index as f32 / sample_rate_hz as f32;
+            let (fundamental, _) = crate::math::sin_cos(2.0 * PI * 84.0 * time);
+            let (harmonic, _) = crate::math::sin_cos(2.0 * PI * 252.0 * time);
+            *sample = I32x3 {
+                x: F32Ext::round(400.0 * fundamental + 100.0 * harmonic) as i32,
+                y: 0,
+                z: 1000,
+            };
+        }
+        samples
+    }
+
+    fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            F32Ext::abs(actual - expected) < tolerance,
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn statistics_ignore_the_static_part() {
+        let statistics = VibrationStatistics::new(&pump(1344)).unwrap();
+        assert_close(statistics.z().mean(), 1000.0, 0.01);
+        assert_eq!(statistics.z().rms(), 0.0);
+        assert_eq!(statistics.z().crest_factor(), 0.0);
+        // RMS of two sines is sqrt((400² + 100²) / 2).
+        assert_close(statistics.x().rms(), 291.5, 1.0);
+        assert_close(statistics.x().peak(), 354.0, 1.0);
+        assert_close(statistics.x().crest_factor(), 1.21, 0.01);
+        assert_close(
+            statistics.x().peak_to_peak(),
+            2.0 * statistics.x().peak(),
+            2.0,
+        );
+        assert!(VibrationStatistics::new(&[]).is_err());
+    }
+
+    #[test]
+    fn spectrum_bins_are_labelled_with_the_output_data_rate() {
+        let mut ctrl_reg1 = CtrlReg1Value::default();
+        ctrl_reg1
+            .set_output_data_rate(ODR::HrNormal1Pont344kHzLowPower5Point376kHz);
+        let analyzer = SpectrumAnalyzer::<256>::for_settings(
+            Window::Rectangular,
+            &ctrl_reg1,
+        )
+        .unwrap();
+        let spectrum = analyzer.analyze(&pump(1344), 0).unwrap();
+        assert_eq!(spectrum.magnitudes().len(), 129);
+        assert_eq!(spectrum.resolution_hz(), 5.25);
+        // 84 Hz and 252 Hz fall exactly on bins 16 and 48.
+        assert_eq!(spectrum.dominant().0, 84.0);
+        assert_close(spectrum.magnitudes()[16], 400.0, 1.0);
+        assert_close(spectrum.magnitudes()[48], 100.0, 1.0);
+        assert!(spectrum.magnitudes()[30] < 1.0);
+        ctrl_reg1.set_l_p_en(LPEn::LowPowerEnabled);
+        let analyzer = SpectrumAnalyzer::<256>::for_settings(
+            Window::Rectangular,
+            &ctrl_reg1,
+        )
+        .unwrap();
+        assert_eq!(analyzer.sample_rate_hz(), 5376);
+        // A measured rate keeps its fraction.
+        let mut analyzer =
+            SpectrumAnalyzer::<256>::new(Window::Rectangular, 1344).unwrap();
+        analyzer.set_sample_rate_millihertz(1_344_500).unwrap();
+        assert_eq!(analyzer.sample_rate_hz(), 1345);
+        let spectrum = analyzer.analyze(&pump(1344), 0).unwrap();
+        assert_close(spectrum.resolution_hz(), 5.252, 0.001);
+        assert!(analyzer.set_sample_rate_millihertz(0).is_err());
+    }
+
+    #[test]
+    fn hann_window_reduces_leakage() {
+        let samples = pump(1300);
+        let rectangular =
+            SpectrumAnalyzer::<256>::new(Window::Rectangular, 1300)
+                .unwrap()
+                .analyze(&samples, 0)
+                .unwrap();
+        let hann = SpectrumAnalyzer::<256>::new(Window::Hann, 1300)
+            .unwrap()
+            .analyze(&samples, 0)
+            .unwrap();
+        // 84 Hz lies between bins, far bins only see leakage.
+        assert!(hann.magnitudes()[30] < rectangular.magnitudes()[30] / 10.0);
+        assert_close(hann.dominant().0, 86.33, 0.01);
+        assert!(SpectrumAnalyzer::<100>::new(Window::Hann, 1300).is_err());
+        assert!(SpectrumAnalyzer::<256>::new(Window::Hann, 1300)
+            .unwrap()
+            .analyze(&samples, 3)
+            .is_err());
+    }
+}